@@ -0,0 +1,262 @@
+//! Accessibility tree extraction for screen readers and automated testing.
+
+use crate::types::{AxNode, AxRole, Document, Element};
+
+/// Elements that never appear in the accessibility tree.
+const EXCLUDED_TAGS: &[&str] = &[
+    "head", "script", "style", "template", "noscript", "meta", "link", "title",
+];
+
+impl Document {
+    /// Build the accessibility tree of the document.
+    ///
+    /// Roles come from the `role` attribute when it names a known role, and
+    /// from the tag otherwise. Hidden subtrees are dropped, and generic or
+    /// presentational wrappers are flattened into their parent.
+    pub fn accessibility_tree(&self) -> AxNode {
+        let mut root = AxNode::new(AxRole::Document, collapse_whitespace(&self.title));
+        collect(&self.root, &mut root.children);
+        root
+    }
+}
+
+impl AxNode {
+    fn new(role: AxRole, name: String) -> Self {
+        Self {
+            role,
+            name,
+            level: None,
+            checked: None,
+            expanded: None,
+            disabled: false,
+            children: Vec::new(),
+        }
+    }
+}
+
+/// Append the accessibility nodes for `element` to `out`.
+fn collect(element: &Element, out: &mut Vec<AxNode>) {
+    if is_hidden(element) {
+        return;
+    }
+
+    let mut children = Vec::new();
+    if let Some(text) = element.text_content.as_deref().map(collapse_whitespace)
+        && !text.is_empty()
+    {
+        children.push(AxNode::new(AxRole::StaticText, text));
+    }
+    for child in &element.children {
+        collect(child, &mut children);
+    }
+
+    let Some(role) = role_of(element) else {
+        out.extend(children);
+        return;
+    };
+    let mut node = AxNode::new(role, accessible_name(element, role));
+    node.children = children;
+    node.level = (role == AxRole::Heading).then(|| heading_level(element)).flatten();
+    node.checked = match element.attribute("aria-checked") {
+        Some(value) => Some(value.eq_ignore_ascii_case("true")),
+        None if matches!(role, AxRole::Checkbox | AxRole::Radio) => {
+            Some(element.attribute("checked").is_some())
+        },
+        None => None,
+    };
+    node.expanded = element.attribute("aria-expanded").map(|v| v.eq_ignore_ascii_case("true"));
+    node.disabled = element.attribute("disabled").is_some()
+        || element
+            .attribute("aria-disabled")
+            .is_some_and(|v| v.eq_ignore_ascii_case("true"));
+    out.push(node);
+}
+
+fn is_hidden(element: &Element) -> bool {
+    EXCLUDED_TAGS.contains(&element.tag.as_str())
+        || element.attribute("hidden").is_some()
+        || element.attribute("aria-hidden").is_some_and(|v| v.eq_ignore_ascii_case("true"))
+        || (element.tag == "input"
+            && element.attribute("type").is_some_and(|t| t.eq_ignore_ascii_case("hidden")))
+}
+
+/// Role of an element, or `None` if it is generic or presentational.
+fn role_of(element: &Element) -> Option<AxRole> {
+    if let Some(role) = element.attribute("role") {
+        let role = role.split_whitespace().next().unwrap_or("").to_ascii_lowercase();
+        if matches!(role.as_str(), "presentation" | "none") {
+            return None;
+        }
+        if let Some(role) = parse_role(&role) {
+            return Some(role);
+        }
+    }
+
+    match element.tag.as_str() {
+        "a" if element.attribute("href").is_some() => Some(AxRole::Link),
+        "article" => Some(AxRole::Article),
+        "aside" => Some(AxRole::Complementary),
+        "button" => Some(AxRole::Button),
+        "dialog" => Some(AxRole::Dialog),
+        "footer" => Some(AxRole::ContentInfo),
+        "form" => Some(AxRole::Form),
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => Some(AxRole::Heading),
+        "header" => Some(AxRole::Banner),
+        // Images with empty alt text are decorative.
+        "img" if element.attribute("alt") != Some("") => Some(AxRole::Image),
+        "input" => Some(match element.attribute("type").map(str::to_ascii_lowercase).as_deref() {
+            Some("checkbox") => AxRole::Checkbox,
+            Some("radio") => AxRole::Radio,
+            Some("button" | "submit" | "reset" | "image") => AxRole::Button,
+            _ => AxRole::TextBox,
+        }),
+        "li" => Some(AxRole::ListItem),
+        "main" => Some(AxRole::Main),
+        "nav" => Some(AxRole::Navigation),
+        "ol" | "ul" => Some(AxRole::List),
+        "p" => Some(AxRole::Paragraph),
+        "section" if element.attribute("aria-label").is_some() => Some(AxRole::Region),
+        "select" => Some(AxRole::ComboBox),
+        "table" => Some(AxRole::Table),
+        "td" | "th" => Some(AxRole::Cell),
+        "textarea" => Some(AxRole::TextBox),
+        "tr" => Some(AxRole::Row),
+        _ => None,
+    }
+}
+
+fn parse_role(role: &str) -> Option<AxRole> {
+    Some(match role {
+        "article" => AxRole::Article,
+        "banner" => AxRole::Banner,
+        "button" => AxRole::Button,
+        "cell" | "gridcell" => AxRole::Cell,
+        "checkbox" | "switch" => AxRole::Checkbox,
+        "combobox" | "listbox" => AxRole::ComboBox,
+        "complementary" => AxRole::Complementary,
+        "contentinfo" => AxRole::ContentInfo,
+        "dialog" | "alertdialog" => AxRole::Dialog,
+        "form" => AxRole::Form,
+        "heading" => AxRole::Heading,
+        "img" | "image" => AxRole::Image,
+        "link" => AxRole::Link,
+        "list" => AxRole::List,
+        "listitem" => AxRole::ListItem,
+        "main" => AxRole::Main,
+        "navigation" => AxRole::Navigation,
+        "paragraph" => AxRole::Paragraph,
+        "radio" => AxRole::Radio,
+        "region" => AxRole::Region,
+        "row" => AxRole::Row,
+        "table" | "grid" => AxRole::Table,
+        "textbox" | "searchbox" => AxRole::TextBox,
+        _ => return None,
+    })
+}
+
+/// Compute the accessible name: `aria-label`, then `alt` for images, then the
+/// text content for roles named from their contents, then `title`.
+fn accessible_name(element: &Element, role: AxRole) -> String {
+    if let Some(label) = element.attribute("aria-label").map(collapse_whitespace)
+        && !label.is_empty()
+    {
+        return label;
+    }
+    if role == AxRole::Image
+        && let Some(alt) = element.attribute("alt")
+    {
+        return collapse_whitespace(alt);
+    }
+    let named_from_content = matches!(
+        role,
+        AxRole::Button
+            | AxRole::Cell
+            | AxRole::Checkbox
+            | AxRole::Heading
+            | AxRole::Link
+            | AxRole::ListItem
+            | AxRole::Radio
+    );
+    if named_from_content {
+        let text = collapse_whitespace(&element.inner_text());
+        if !text.is_empty() {
+            return text;
+        }
+    }
+    element.attribute("title").map(collapse_whitespace).unwrap_or_default()
+}
+
+/// Level of a heading from its tag (`h1`–`h6`) or `aria-level`.
+fn heading_level(element: &Element) -> Option<u8> {
+    element
+        .attribute("aria-level")
+        .and_then(|level| level.trim().parse().ok())
+        .or_else(|| element.tag.strip_prefix('h')?.parse().ok())
+        .filter(|level| (1..=6).contains(level))
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(body: Element) -> Document {
+        Document {
+            title: "Pets".into(),
+            root:  Element::new("html").with_child(Element::new("head")).with_child(body),
+            url:   "https://example.com/".into(),
+        }
+    }
+
+    #[test]
+    fn test_roles_and_names() {
+        let body = Element::new("body")
+            .with_child(Element::new("img").with_attribute("alt", "cat"))
+            .with_child(Element::new("div").with_attribute("role", "button").with_text("Adopt"))
+            .with_child(
+                Element::new("input")
+                    .with_attribute("type", "checkbox")
+                    .with_attribute("aria-label", "Subscribe")
+                    .with_attribute("checked", ""),
+            );
+        let tree = document(body).accessibility_tree();
+
+        assert_eq!(tree.role, AxRole::Document);
+        assert_eq!(tree.name, "Pets");
+        let [image, button, checkbox] = &tree.children[..] else {
+            panic!("unexpected tree: {tree:?}");
+        };
+        assert_eq!((image.role, image.name.as_str()), (AxRole::Image, "cat"));
+        assert_eq!((button.role, button.name.as_str()), (AxRole::Button, "Adopt"));
+        assert_eq!(checkbox.role, AxRole::Checkbox);
+        assert_eq!(checkbox.name, "Subscribe");
+        assert_eq!(checkbox.checked, Some(true));
+    }
+
+    #[test]
+    fn test_presentational_and_hidden_nodes_are_pruned() {
+        let body = Element::new("body").with_child(
+            Element::new("div")
+                .with_child(Element::new("h2").with_text("  Cats  "))
+                .with_child(Element::new("img").with_attribute("alt", ""))
+                .with_child(Element::new("p").with_attribute("aria-hidden", "true").with_text("x"))
+                .with_child(
+                    Element::new("table").with_attribute("role", "presentation").with_child(
+                        Element::new("tr").with_attribute("role", "none").with_text("Layout"),
+                    ),
+                ),
+        );
+        let tree = document(body).accessibility_tree();
+
+        assert_eq!(tree.children.len(), 2);
+        let heading = &tree.children[0];
+        assert_eq!((heading.role, heading.name.as_str()), (AxRole::Heading, "Cats"));
+        assert_eq!(heading.level, Some(2));
+        assert_eq!(heading.children[0].role, AxRole::StaticText);
+        assert_eq!(tree.children[1].role, AxRole::StaticText);
+        assert_eq!(tree.children[1].name, "Layout");
+    }
+}
@@ -0,0 +1,220 @@
+//! HTTP-style document cache.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::{fetch::FetchResponse, types::Document};
+
+/// Result of a cache lookup.
+#[derive(Debug, Clone)]
+pub(crate) enum CacheLookup {
+    /// Entry is within its TTL and can be served directly.
+    Fresh(Document),
+    /// Entry has expired and must be revalidated with these validators.
+    Stale {
+        etag:          Option<String>,
+        last_modified: Option<String>,
+    },
+    /// No entry for the URL.
+    Miss,
+}
+
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    document:      Document,
+    size:          usize,
+    expires_at:    Instant,
+    etag:          Option<String>,
+    last_modified: Option<String>,
+    last_used:     u64,
+}
+
+/// Documents keyed by URL, evicted least-recently-used once the total body
+/// size exceeds the capacity.
+#[derive(Debug, Clone)]
+pub(crate) struct DocumentCache {
+    entries:     HashMap<String, CacheEntry>,
+    capacity:    usize,
+    used:        usize,
+    default_ttl: Duration,
+    clock:       u64,
+}
+
+impl DocumentCache {
+    /// Create a cache holding up to `capacity` bytes of response bodies.
+    ///
+    /// `default_ttl` applies to responses without `Cache-Control: max-age`.
+    pub(crate) fn new(capacity: usize, default_ttl: Duration) -> Self {
+        Self { entries: HashMap::new(), capacity, used: 0, default_ttl, clock: 0 }
+    }
+
+    /// Look up a URL at time `now`.
+    pub(crate) fn lookup(&mut self, url: &str, now: Instant) -> CacheLookup {
+        self.clock += 1;
+        let Some(entry) = self.entries.get_mut(url) else {
+            return CacheLookup::Miss;
+        };
+        entry.last_used = self.clock;
+        if now < entry.expires_at {
+            CacheLookup::Fresh(entry.document.clone())
+        } else {
+            CacheLookup::Stale {
+                etag:          entry.etag.clone(),
+                last_modified: entry.last_modified.clone(),
+            }
+        }
+    }
+
+    /// Store a freshly fetched document, unless the response forbids caching
+    /// or is larger than the whole cache.
+    pub(crate) fn insert(
+        &mut self,
+        url: &str,
+        document: Document,
+        response: &FetchResponse,
+        now: Instant,
+    ) {
+        self.remove(url);
+        let size = response.body.len();
+        let Some(ttl) = self.ttl(response) else {
+            return;
+        };
+        if size > self.capacity {
+            return;
+        }
+
+        while self.used + size > self.capacity {
+            let Some(oldest) =
+                self.entries.iter().min_by_key(|(_, e)| e.last_used).map(|(url, _)| url.clone())
+            else {
+                break;
+            };
+            self.remove(&oldest);
+        }
+
+        self.clock += 1;
+        self.used += size;
+        self.entries.insert(
+            url.to_string(),
+            CacheEntry {
+                document,
+                size,
+                expires_at: now + ttl,
+                etag: response.header("etag").map(str::to_string),
+                last_modified: response.header("last-modified").map(str::to_string),
+                last_used: self.clock,
+            },
+        );
+    }
+
+    /// Extend a stale entry after a `304 Not Modified` and return its document.
+    pub(crate) fn revalidate(
+        &mut self,
+        url: &str,
+        response: &FetchResponse,
+        now: Instant,
+    ) -> Option<Document> {
+        let ttl = self.ttl(response).unwrap_or_default();
+        let entry = self.entries.get_mut(url)?;
+        entry.expires_at = now + ttl;
+        Some(entry.document.clone())
+    }
+
+    /// Drop the entry for a URL, if any.
+    pub(crate) fn remove(&mut self, url: &str) {
+        if let Some(entry) = self.entries.remove(url) {
+            self.used -= entry.size;
+        }
+    }
+
+    /// Lifetime of a response, or `None` if it must not be stored.
+    fn ttl(&self, response: &FetchResponse) -> Option<Duration> {
+        let Some(cache_control) = response.header("cache-control") else {
+            return Some(self.default_ttl);
+        };
+        let mut ttl = self.default_ttl;
+        for directive in cache_control.split(',').map(str::trim) {
+            if directive.eq_ignore_ascii_case("no-store") {
+                return None;
+            }
+            if directive.eq_ignore_ascii_case("no-cache") {
+                ttl = Duration::ZERO;
+            } else if let Some((name, seconds)) = directive.split_once('=')
+                && name.trim().eq_ignore_ascii_case("max-age")
+                && let Ok(seconds) = seconds.trim().parse()
+            {
+                ttl = Duration::from_secs(seconds);
+            }
+        }
+        Some(ttl)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Element;
+
+    fn document(url: &str) -> Document {
+        Document { title: String::new(), root: Element::new("html"), url: url.into() }
+    }
+
+    fn response(body_len: usize, headers: &[(&str, &str)]) -> FetchResponse {
+        let mut response = FetchResponse::ok("text/html", vec![b' '; body_len]);
+        response.headers = headers.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        response
+    }
+
+    #[test]
+    fn test_ttl_expiry_marks_entry_stale() {
+        let mut cache = DocumentCache::new(1024, Duration::from_secs(300));
+        let now = Instant::now();
+        let headers = [("Cache-Control", "max-age=60"), ("ETag", "\"v1\"")];
+        cache.insert("https://a.test/", document("https://a.test/"), &response(10, &headers), now);
+
+        let fresh = cache.lookup("https://a.test/", now + Duration::from_secs(59));
+        assert!(matches!(fresh, CacheLookup::Fresh(_)));
+        let stale = cache.lookup("https://a.test/", now + Duration::from_secs(60));
+        assert!(
+            matches!(stale, CacheLookup::Stale { etag: Some(e), last_modified: None } if e == "\"v1\"")
+        );
+
+        let later = now + Duration::from_secs(120);
+        assert!(cache.revalidate("https://a.test/", &response(0, &headers), later).is_some());
+        assert!(matches!(cache.lookup("https://a.test/", later), CacheLookup::Fresh(_)));
+
+        cache.insert(
+            "https://b.test/",
+            document("https://b.test/"),
+            &response(10, &[("Cache-Control", "no-store")]),
+            now,
+        );
+        assert!(matches!(cache.lookup("https://b.test/", now), CacheLookup::Miss));
+    }
+
+    #[test]
+    fn test_lru_eviction_by_size() {
+        let mut cache = DocumentCache::new(100, Duration::from_secs(300));
+        let now = Instant::now();
+        for url in ["https://a.test/", "https://b.test/"] {
+            cache.insert(url, document(url), &response(40, &[]), now);
+        }
+        // Touch `a` so `b` becomes least recently used.
+        cache.lookup("https://a.test/", now);
+        cache.insert("https://c.test/", document("https://c.test/"), &response(40, &[]), now);
+
+        assert!(cache.entries.contains_key("https://a.test/"));
+        assert!(!cache.entries.contains_key("https://b.test/"));
+        assert!(cache.entries.contains_key("https://c.test/"));
+
+        cache.insert(
+            "https://huge.test/",
+            document("https://huge.test/"),
+            &response(101, &[]),
+            now,
+        );
+        assert!(!cache.entries.contains_key("https://huge.test/"));
+    }
+}
@@ -1,34 +1,126 @@
 //! Browser plugin configuration.
 
+use std::time::Duration;
+
+use crate::{
+    parser::DEFAULT_MAX_PARSE_DEPTH,
+    types::{MixedContentPolicy, PrivacyMode, ReferrerPolicy, StyleSheet},
+};
+
 /// Configuration for the browser plugin.
 #[derive(Debug, Clone)]
 pub struct BrowserConfig {
     /// Enable JavaScript execution.
-    pub enable_javascript:    bool,
-    /// Enable image loading.
-    pub enable_images:        bool,
+    pub enable_javascript:      bool,
+    /// Domains whose scripts always run, even with JavaScript disabled.
+    pub script_allowed_hosts:   Vec<String>,
+    /// Domains whose scripts never run.
+    pub script_blocked_hosts:   Vec<String>,
+    /// Fetch `<img>` images.
+    pub enable_images:          bool,
+    /// Fetch CSS `background-image`s.
+    pub load_background_images: bool,
+    /// Fetch the page's favicon. The resolved URL is recorded on the tab
+    /// either way.
+    pub load_favicons:          bool,
     /// Enable CSS.
-    pub enable_css:           bool,
-    /// Maximum concurrent connections.
-    pub max_connections:      usize,
+    pub enable_css:             bool,
+    /// Send and store cookies.
+    pub enable_cookies:         bool,
+    /// Keep unmuted `<video autoplay>` and `<audio autoplay>` from starting
+    /// until the user plays them.
+    pub block_autoplay:         bool,
+    /// Refuse pages' requests to open new windows, unless their origin is
+    /// granted the popup permission.
+    pub block_popups:           bool,
+    /// Maximum concurrent subresource fetches; extra requests are queued.
+    pub max_connections:        usize,
+    /// Time allowed for a fetch before it fails with a timeout (ms); 0
+    /// disables the limit.
+    pub request_timeout_ms:     u64,
     /// User agent string.
-    pub user_agent:           String,
+    pub user_agent:             String,
     /// Enable consciousness pattern recognition.
-    pub enable_consciousness: bool,
-    /// Maximum memory usage (bytes).
-    pub max_memory:           usize,
+    pub enable_consciousness:   bool,
+    /// Maximum memory usage (bytes); background tabs are hibernated, least
+    /// recently used first, while loaded documents need more.
+    pub max_memory:             usize,
+    /// Idle time after which a background tab is hibernated; `None` keeps
+    /// background tabs loaded.
+    pub hibernate_after:        Option<Duration>,
+    /// Largest document body that will be parsed (bytes); bigger responses
+    /// fail the navigation.
+    pub max_document_bytes:     usize,
+    /// Device width for `width=device-width` viewports (px); `None` uses the
+    /// viewport width.
+    pub device_width:           Option<f32>,
+    /// Page opened by `go_home`.
+    pub home_url:               String,
+    /// Page loaded into newly opened tabs.
+    pub new_tab_url:            String,
+    /// Search URL for navigation input that is not a URL; `{}` is replaced
+    /// by the encoded query.
+    pub search_template:        String,
+    /// User stylesheet, cascaded between author normal and author
+    /// `!important` declarations.
+    pub user_stylesheet:        Option<StyleSheet>,
+    /// Document cache size limit (MB).
+    pub cache_size_mb:          u32,
+    /// Cache lifetime for responses without `Cache-Control: max-age`.
+    pub cache_ttl:              Duration,
+    /// Maximum element nesting depth accepted by the HTML parser.
+    pub max_parse_depth:        usize,
+    /// Use the dark theme for pages generated by the browser.
+    pub dark_mode:              bool,
+    /// Privacy protection level; stricter modes tighten content defaults.
+    pub privacy_mode:           PrivacyMode,
+    /// Referrer policy for pages that do not set one; `None` picks one by
+    /// privacy mode.
+    pub referrer_policy:        Option<ReferrerPolicy>,
+    /// Refuse to load pages over plain `http:`.
+    pub https_only:             bool,
+    /// Handling of `http:` subresources on `https:` pages; `None` blocks
+    /// them in HTTPS-only, strict and private modes and upgrades them
+    /// otherwise.
+    pub mixed_content_policy:   Option<MixedContentPolicy>,
+    /// Tracker hosts that are never loaded; subdomains are blocked too.
+    pub blocked_hosts:          Vec<String>,
 }
 
 impl Default for BrowserConfig {
     fn default() -> Self {
         Self {
-            enable_javascript:    true,
-            enable_images:        true,
-            enable_css:           true,
-            max_connections:      6,
-            user_agent:           String::from("EssentiaBrowser/1.0"),
-            enable_consciousness: true,
-            max_memory:           512 * 1024 * 1024, // 512 MB
+            enable_javascript:      true,
+            script_allowed_hosts:   Vec::new(),
+            script_blocked_hosts:   Vec::new(),
+            enable_images:          true,
+            load_background_images: true,
+            load_favicons:          true,
+            enable_css:             true,
+            enable_cookies:         true,
+            block_autoplay:         true,
+            block_popups:           false,
+            max_connections:        6,
+            request_timeout_ms:     30_000,
+            user_agent:             String::from("EssentiaBrowser/1.0"),
+            enable_consciousness:   true,
+            max_memory:             512 * 1024 * 1024, // 512 MB
+            hibernate_after:        Some(Duration::from_secs(30 * 60)),
+            max_document_bytes:     32 * 1024 * 1024, // 32 MB
+            device_width:           None,
+            home_url:               String::from("about:home"),
+            new_tab_url:            String::from("about:blank"),
+            search_template:        String::from("https://duckduckgo.com/?q={}"),
+            user_stylesheet:        None,
+            cache_size_mb:          256,
+            cache_ttl:              Duration::from_secs(300),
+            max_parse_depth:        DEFAULT_MAX_PARSE_DEPTH,
+            dark_mode:              false,
+            privacy_mode:           PrivacyMode::Standard,
+            referrer_policy:        None,
+            https_only:             false,
+            mixed_content_policy:   None,
+            blocked_hosts:          Vec::new(),
         }
     }
 }
@@ -0,0 +1,198 @@
+//! CSS parser.
+
+use crate::{
+    errors::BrowserResult,
+    selector::Selector,
+    types::{CssRule, StyleSheet},
+};
+
+/// CSS parser producing [`StyleSheet`]s from source text.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CssParser;
+
+impl CssParser {
+    /// Parse a stylesheet.
+    ///
+    /// Comments are stripped and at-rules (`@media`, `@font-face`, ...) are
+    /// skipped along with their blocks. A selector list becomes one rule per
+    /// selector, sharing the declarations. Malformed rules are skipped as
+    /// described by [`parse_with_diagnostics`](Self::parse_with_diagnostics).
+    pub fn parse(css: &str) -> BrowserResult<StyleSheet> {
+        Ok(Self::parse_with_diagnostics(css).0)
+    }
+
+    /// Parse a stylesheet, never failing.
+    ///
+    /// Rules without a selector or with an invalid selector in their list,
+    /// declarations without a colon, and a rule left unterminated at the end
+    /// of the input are skipped and described in the returned diagnostics.
+    pub fn parse_with_diagnostics(css: &str) -> (StyleSheet, Vec<String>) {
+        let css = strip_comments(css);
+        let mut rules = Vec::new();
+        let mut diagnostics = Vec::new();
+        let mut rest = css.as_str();
+
+        while let Some(open) = rest.find('{') {
+            let prelude = rest[..open].trim();
+            let Some(close) = block_end(&rest[open + 1..]) else {
+                diagnostics.push(format!("unterminated block after `{prelude}`"));
+                break;
+            };
+            let block = &rest[open + 1..open + 1 + close];
+            rest = &rest[open + 2 + close..];
+
+            if prelude.starts_with('@') {
+                continue;
+            }
+            if prelude.is_empty() {
+                diagnostics.push("rule without a selector".into());
+                continue;
+            }
+            let selectors = split_selector_list(prelude);
+            if let Some(invalid) = selectors.iter().find(|s| Selector::parse(s).is_err()) {
+                diagnostics.push(format!("invalid selector `{invalid}` in `{prelude}`"));
+                continue;
+            }
+            for declaration in block.split(';').map(str::trim) {
+                if !declaration.is_empty() && !declaration.contains(':') {
+                    diagnostics
+                        .push(format!("declaration without ':' in `{prelude}`: {declaration}"));
+                }
+            }
+            let declarations = parse_declarations(block);
+            rules.extend(selectors.into_iter().map(|selector| CssRule {
+                selector:     selector.to_string(),
+                declarations: declarations.clone(),
+            }));
+        }
+        (StyleSheet { rules }, diagnostics)
+    }
+}
+
+/// Split a selector list at its top-level commas, ignoring commas inside
+/// brackets, parentheses and quoted strings.
+pub(crate) fn split_selector_list(list: &str) -> Vec<&str> {
+    let mut selectors = Vec::new();
+    let (mut depth, mut quote, mut start) = (0usize, None, 0);
+    for (i, c) in list.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {},
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '[' | '(') => depth += 1,
+            (None, ']' | ')') => depth = depth.saturating_sub(1),
+            (None, ',') if depth == 0 => {
+                selectors.push(list[start..i].trim());
+                start = i + 1;
+            },
+            _ => {},
+        }
+    }
+    selectors.push(list[start..].trim());
+    selectors
+}
+
+/// Parse a `property: value; ...` declaration list. Declarations without a
+/// colon or with an empty name are skipped.
+pub(crate) fn parse_declarations(block: &str) -> Vec<(String, String)> {
+    block
+        .split(';')
+        .filter_map(|declaration| {
+            let (property, value) = declaration.split_once(':')?;
+            let property = property.trim();
+            let property = if property.starts_with("--") {
+                property.to_string()
+            } else {
+                property.to_ascii_lowercase()
+            };
+            (!property.is_empty()).then(|| (property, value.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Offset of the `}` closing a block whose `{` precedes `css`, allowing for
+/// nested blocks.
+fn block_end(css: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, c) in css.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            _ => {},
+        }
+    }
+    None
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut out = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        out.push_str(&rest[..start]);
+        rest = match rest[start + 2..].find("*/") {
+            Some(end) => &rest[start + 2 + end + 2..],
+            None => "",
+        };
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rules_and_skip_at_rules() {
+        let sheet = CssParser::parse(
+            "/* base */ p { Color: red; width: 10px; } \
+             @media print { p { display: none } } \
+             :root { --Gap: 4px } div{",
+        )
+        .unwrap();
+
+        let rules: Vec<_> = sheet.rules.iter().map(|r| r.selector.as_str()).collect();
+        assert_eq!(rules, ["p", ":root"]);
+        assert_eq!(
+            sheet.rules[0].declarations,
+            [
+                ("color".to_string(), "red".to_string()),
+                ("width".to_string(), "10px".to_string())
+            ]
+        );
+        assert_eq!(sheet.rules[1].declarations, [("--Gap".to_string(), "4px".to_string())]);
+    }
+
+    #[test]
+    fn test_selector_lists_expand_into_rules() {
+        let sheet = CssParser::parse("h1, h2 { color: red; font-size: 14px }").unwrap();
+        let selectors: Vec<_> = sheet.rules.iter().map(|r| r.selector.as_str()).collect();
+        assert_eq!(selectors, ["h1", "h2"]);
+        assert_eq!(sheet.rules[0].declarations, sheet.rules[1].declarations);
+        assert_eq!(sheet.rules[1].declarations[1], ("font-size".into(), "14px".into()));
+
+        let sheet = CssParser::parse("[title='a,b'], p {}").unwrap();
+        assert_eq!(sheet.rules.len(), 2);
+        assert_eq!(sheet.rules[0].selector, "[title='a,b']");
+    }
+
+    #[test]
+    fn test_malformed_rules_are_skipped_with_diagnostics() {
+        let (sheet, diagnostics) = CssParser::parse_with_diagnostics(
+            "{ color: red } p, { margin: 0 } div { color blue; width: 1px; } a { ",
+        );
+        let selectors: Vec<_> = sheet.rules.iter().map(|r| r.selector.as_str()).collect();
+        assert_eq!(selectors, ["div"]);
+        assert_eq!(sheet.rules[0].declarations, [("width".to_string(), "1px".to_string())]);
+        assert_eq!(
+            diagnostics,
+            [
+                "rule without a selector",
+                "invalid selector `` in `p,`",
+                "declaration without ':' in `div`: color blue",
+                "unterminated block after `a`",
+            ]
+        );
+    }
+}
@@ -0,0 +1,224 @@
+//! Structural diffing and patching of documents.
+
+use crate::{
+    errors::{BrowserError, BrowserResult},
+    types::{Document, DomPatch, Element},
+};
+
+impl Document {
+    /// Describe the changes that turn this document into `other`.
+    ///
+    /// Unchanged leading and trailing children are skipped, so a localized
+    /// edit yields only the patches for the elements that changed. Applying
+    /// the result with [`apply_patches`](Self::apply_patches) reproduces
+    /// `other`'s title and element tree, except for the order of attributes:
+    /// an element whose attributes were only reordered yields no patch, and
+    /// added attributes go after the existing ones. Attribute names are
+    /// compared exactly, both here and when applying.
+    pub fn diff(&self, other: &Document) -> Vec<DomPatch> {
+        let mut patches = Vec::new();
+        if self.title != other.title {
+            patches.push(DomPatch::SetTitle(other.title.clone()));
+        }
+        diff_element(&self.root, &other.root, &mut Vec::new(), &mut patches);
+        patches
+    }
+
+    /// Apply patches produced by [`diff`](Self::diff), in order.
+    ///
+    /// Fails without further changes at the first patch whose path does not
+    /// exist; patches before it stay applied.
+    pub fn apply_patches(&mut self, patches: &[DomPatch]) -> BrowserResult<()> {
+        for patch in patches {
+            self.apply_patch(patch)?;
+        }
+        Ok(())
+    }
+
+    fn apply_patch(&mut self, patch: &DomPatch) -> BrowserResult<()> {
+        match patch {
+            DomPatch::SetTitle(title) => self.title = title.clone(),
+            DomPatch::Replace { path, element } => *self.element_at(path)? = element.clone(),
+            DomPatch::Insert { parent, index, element } => {
+                let children = &mut self.element_at(parent)?.children;
+                if *index > children.len() {
+                    return Err(invalid_path(parent));
+                }
+                children.insert(*index, element.clone());
+            },
+            DomPatch::Remove { path } => {
+                let (index, parent) = path.split_last().ok_or_else(|| invalid_path(path))?;
+                let children = &mut self.element_at(parent)?.children;
+                if *index >= children.len() {
+                    return Err(invalid_path(path));
+                }
+                children.remove(*index);
+            },
+            DomPatch::SetAttribute { path, name, value } => {
+                let attributes = &mut self.element_at(path)?.attributes;
+                match attributes.iter_mut().find(|(n, _)| n == name) {
+                    Some((_, existing)) => *existing = value.clone(),
+                    None => attributes.push((name.clone(), value.clone())),
+                }
+            },
+            DomPatch::RemoveAttribute { path, name } => {
+                self.element_at(path)?.attributes.retain(|(n, _)| n != name);
+            },
+            DomPatch::SetText { path, text } => self.element_at(path)?.text_content = text.clone(),
+        }
+        Ok(())
+    }
+
+    fn element_at(&mut self, path: &[usize]) -> BrowserResult<&mut Element> {
+        let mut element = &mut self.root;
+        for &index in path {
+            element = element.children.get_mut(index).ok_or_else(|| invalid_path(path))?;
+        }
+        Ok(element)
+    }
+}
+
+fn invalid_path(path: &[usize]) -> BrowserError {
+    BrowserError::Render(format!("Patch path {path:?} does not exist"))
+}
+
+fn diff_element(old: &Element, new: &Element, path: &mut Vec<usize>, out: &mut Vec<DomPatch>) {
+    if old.tag != new.tag {
+        out.push(DomPatch::Replace { path: path.clone(), element: new.clone() });
+        return;
+    }
+
+    for (name, value) in &new.attributes {
+        if exact_attribute(old, name) != Some(value) {
+            out.push(DomPatch::SetAttribute {
+                path:  path.clone(),
+                name:  name.clone(),
+                value: value.clone(),
+            });
+        }
+    }
+    for (name, _) in &old.attributes {
+        if exact_attribute(new, name).is_none() {
+            out.push(DomPatch::RemoveAttribute { path: path.clone(), name: name.clone() });
+        }
+    }
+    if old.text_content != new.text_content {
+        out.push(DomPatch::SetText { path: path.clone(), text: new.text_content.clone() });
+    }
+
+    diff_children(&old.children, &new.children, path, out);
+}
+
+/// The value of the attribute named exactly `name`, unlike
+/// [`Element::attribute`], which ignores ASCII case.
+fn exact_attribute<'a>(element: &'a Element, name: &str) -> Option<&'a String> {
+    element.attributes.iter().find(|(n, _)| n == name).map(|(_, value)| value)
+}
+
+/// Diff two child lists.
+///
+/// Children are split into an unchanged prefix and suffix around a changed
+/// middle. Middle children are paired by position and diffed in place
+/// (using their original indices), then surplus old children are removed
+/// back to front and surplus new ones inserted front to back.
+fn diff_children(old: &[Element], new: &[Element], path: &mut Vec<usize>, out: &mut Vec<DomPatch>) {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    for (offset, (a, b)) in old_middle.iter().zip(new_middle).enumerate() {
+        path.push(prefix + offset);
+        diff_element(a, b, path, out);
+        path.pop();
+    }
+
+    let paired = old_middle.len().min(new_middle.len());
+    for index in (prefix + paired..prefix + old_middle.len()).rev() {
+        let mut child = path.clone();
+        child.push(index);
+        out.push(DomPatch::Remove { path: child });
+    }
+    for (offset, element) in new_middle.iter().enumerate().skip(paired) {
+        out.push(DomPatch::Insert {
+            parent:  path.clone(),
+            index:   prefix + offset,
+            element: element.clone(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(items: &[&str]) -> Document {
+        let list = items.iter().fold(Element::new("ul"), |ul, item| {
+            ul.with_child(Element::new("li").with_text(*item))
+        });
+        Document {
+            title: "List".into(),
+            root:  Element::new("html")
+                .with_child(Element::new("body").with_child(Element::new("h1")).with_child(list)),
+            url:   "https://example.com/".into(),
+        }
+    }
+
+    #[test]
+    fn test_single_text_change_yields_minimal_patch() {
+        let before = page(&["one", "two", "three"]);
+        let after = page(&["one", "2", "three"]);
+
+        let patches = before.diff(&after);
+        assert_eq!(patches, [DomPatch::SetText { path: vec![0, 1, 1], text: Some("2".into()) }]);
+
+        let mut patched = before.clone();
+        patched.apply_patches(&patches).unwrap();
+        assert_eq!(patched.root, after.root);
+        assert!(before.diff(&before).is_empty());
+    }
+
+    #[test]
+    fn test_structural_changes_round_trip() {
+        let before = page(&["a", "b", "c", "d"]);
+        let mut after = page(&["a", "x", "y", "d", "e"]);
+        after.title = "Updated".into();
+        after.root.children[0].children[0] = Element::new("h2").with_attribute("id", "top");
+        after.root.children[0].attributes.push(("class".into(), "dark".into()));
+
+        let patches = before.diff(&after);
+        assert!(patches.contains(&DomPatch::SetTitle("Updated".into())));
+        assert!(
+            patches
+                .iter()
+                .any(|p| matches!(p, DomPatch::Replace { path, .. } if path == &[0, 0]))
+        );
+
+        let mut patched = before.clone();
+        patched.apply_patches(&patches).unwrap();
+        assert_eq!(patched.root, after.root);
+        assert_eq!(patched.title, "Updated");
+
+        // Removing trailing children and attributes works too.
+        let mut patched = after.clone();
+        patched.apply_patches(&after.diff(&before)).unwrap();
+        assert_eq!(patched.root, before.root);
+
+        // Renaming an attribute's case is a change like any other.
+        let mut renamed = before.clone();
+        renamed.root.children[0].attributes.push(("ID".into(), "main".into()));
+        let mut lowered = before.clone();
+        lowered.root.children[0].attributes.push(("id".into(), "main".into()));
+        let mut patched = renamed.clone();
+        patched.apply_patches(&renamed.diff(&lowered)).unwrap();
+        assert_eq!(patched.root, lowered.root);
+
+        let bogus = [DomPatch::Remove { path: vec![5] }];
+        assert!(patched.apply_patches(&bogus).is_err());
+    }
+}
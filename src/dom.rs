@@ -0,0 +1,749 @@
+//! DOM traversal and content extraction helpers.
+
+use std::time::Duration;
+
+use crate::{
+    css::split_selector_list,
+    selector::Selector,
+    types::{
+        Document, Element, ReadingStats, ReferrerPolicy, ResourceHint, ResourceHintKind,
+        RobotsDirectives, ViewportMeta, ViewportWidth,
+    },
+    url,
+};
+
+/// Average adult silent reading speed used by [`Document::reading_stats`].
+pub const DEFAULT_WORDS_PER_MINUTE: u32 = 230;
+
+/// Where an author stylesheet comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum StyleSource {
+    /// CSS text of a `<style>` element.
+    Inline(String),
+    /// Resolved URL of a `<link rel="stylesheet">`.
+    Linked(String),
+}
+
+/// Elements whose text is never rendered as page content.
+const NON_CONTENT_TAGS: &[&str] = &["script", "style", "template", "noscript"];
+
+impl Element {
+    /// Get the value of an attribute.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Text content of this element and its descendants, joined by spaces.
+    ///
+    /// Script and style bodies are skipped.
+    pub fn inner_text(&self) -> String {
+        let mut parts = Vec::new();
+        self.collect_text(&mut parts);
+        parts.join(" ")
+    }
+
+    /// First element in the subtree (including `self`) whose `id` attribute
+    /// is `id`, in document order.
+    pub fn get_element_by_id(&self, id: &str) -> Option<&Element> {
+        if self.attribute("id") == Some(id) {
+            return Some(self);
+        }
+        self.children.iter().find_map(|child| child.get_element_by_id(id))
+    }
+
+    /// Every element in the subtree (including `self`) with the given tag,
+    /// matched case-insensitively, in document order.
+    pub fn get_elements_by_tag(&self, tag: &str) -> Vec<&Element> {
+        let mut out = Vec::new();
+        self.find_all_tag(tag, &mut out);
+        out
+    }
+
+    fn collect_text<'a>(&'a self, parts: &mut Vec<&'a str>) {
+        if NON_CONTENT_TAGS.contains(&self.tag.as_str()) {
+            return;
+        }
+        if let Some(text) = self.text_content.as_deref().map(str::trim)
+            && !text.is_empty()
+        {
+            parts.push(text);
+        }
+        for child in &self.children {
+            child.collect_text(parts);
+        }
+    }
+
+    fn estimated_size(&self) -> usize {
+        let attributes: usize = self
+            .attributes
+            .iter()
+            .map(|(name, value)| std::mem::size_of::<(String, String)>() + name.len() + value.len())
+            .sum();
+        let children: usize = self.children.iter().map(Element::estimated_size).sum();
+        std::mem::size_of::<Element>()
+            + self.tag.len()
+            + attributes
+            + self.text_content.as_ref().map_or(0, String::len)
+            + children
+    }
+
+    /// Find the first element in the subtree (including `self`) with the given
+    /// tag, in document order.
+    fn find_tag(&self, tag: &str) -> Option<&Element> {
+        if self.tag == tag {
+            return Some(self);
+        }
+        self.children.iter().find_map(|child| child.find_tag(tag))
+    }
+
+    /// Find every element in the subtree (including `self`) with the given
+    /// tag, in document order.
+    fn find_all_tag<'a>(&'a self, tag: &str, out: &mut Vec<&'a Element>) {
+        if self.tag.eq_ignore_ascii_case(tag) {
+            out.push(self);
+        }
+        for child in &self.children {
+            child.find_all_tag(tag, out);
+        }
+    }
+}
+
+impl Document {
+    /// Base URL for resolving relative references.
+    ///
+    /// The `href` of the first `<base>` element wins over the document URL.
+    pub fn base_url(&self) -> String {
+        self.elements_with_tag("base")
+            .into_iter()
+            .find_map(|base| base.attribute("href"))
+            .map_or_else(|| self.url.clone(), |href| url::resolve(&self.url, href))
+    }
+
+    /// Resolved URLs of the page's hyperlinks, in document order.
+    ///
+    /// Fragment-only and `javascript:` links are skipped.
+    pub fn links(&self) -> Vec<String> {
+        self.resolve_links(|_| true)
+    }
+
+    /// Links a well-behaved crawler may follow.
+    ///
+    /// Empty when the page is marked `nofollow`; otherwise [`links`](Self::links)
+    /// minus anchors with `rel="nofollow"`.
+    pub fn followable_links(&self) -> Vec<String> {
+        if self.meta_robots().nofollow {
+            return Vec::new();
+        }
+        self.resolve_links(|a| {
+            !a.attribute("rel").is_some_and(|rel| {
+                rel.split_whitespace().any(|r| r.eq_ignore_ascii_case("nofollow"))
+            })
+        })
+    }
+
+    /// First element matching a CSS selector list, in document order.
+    ///
+    /// A list containing an invalid selector matches nothing.
+    pub fn query_selector(&self, selector: &str) -> Option<&Element> {
+        self.query(selector, true).into_iter().next()
+    }
+
+    /// Every element matching a CSS selector list, in document order.
+    ///
+    /// A list containing an invalid selector matches nothing.
+    pub fn query_selector_all(&self, selector: &str) -> Vec<&Element> {
+        self.query(selector, false)
+    }
+
+    fn query(&self, selector: &str, first_only: bool) -> Vec<&Element> {
+        let selectors: Result<Vec<_>, _> =
+            split_selector_list(selector).into_iter().map(Selector::parse).collect();
+        let mut out = Vec::new();
+        if let Ok(selectors) = selectors {
+            collect_matching(&self.root, &selectors, &mut Vec::new(), first_only, &mut out);
+        }
+        out
+    }
+
+    /// Parse the page's `<meta name="robots">` directives.
+    ///
+    /// Directives from multiple robots tags are combined. `none` implies both
+    /// `noindex` and `nofollow`.
+    pub fn meta_robots(&self) -> RobotsDirectives {
+        let mut directives = RobotsDirectives::default();
+        let robots = self.elements_with_tag("meta").into_iter().filter(|meta| {
+            meta.attribute("name").is_some_and(|n| n.eq_ignore_ascii_case("robots"))
+        });
+        for meta in robots {
+            let content = meta.attribute("content").unwrap_or("");
+            for directive in content.split(',').map(|d| d.trim().to_ascii_lowercase()) {
+                match directive.as_str() {
+                    "noindex" => directives.noindex = true,
+                    "nofollow" => directives.nofollow = true,
+                    "noarchive" => directives.noarchive = true,
+                    "nosnippet" => directives.nosnippet = true,
+                    "none" => {
+                        directives.noindex = true;
+                        directives.nofollow = true;
+                    },
+                    _ => {},
+                }
+            }
+        }
+        directives
+    }
+
+    /// Rough memory held by the element tree (bytes).
+    pub(crate) fn estimated_size(&self) -> usize {
+        std::mem::size_of::<Document>()
+            + self.title.len()
+            + self.url.len()
+            + self.root.estimated_size()
+    }
+
+    /// Policy set by `<meta name="referrer">`; the last recognized one wins.
+    pub fn referrer_policy(&self) -> Option<ReferrerPolicy> {
+        self.elements_with_tag("meta")
+            .into_iter()
+            .rev()
+            .filter(|meta| {
+                meta.attribute("name").is_some_and(|n| n.eq_ignore_ascii_case("referrer"))
+            })
+            .find_map(|meta| ReferrerPolicy::parse(meta.attribute("content").unwrap_or("")))
+    }
+
+    /// Resolved URLs of the page's `<img>` sources, in document order.
+    pub fn images(&self) -> Vec<String> {
+        let base = self.base_url();
+        self.elements_with_tag("img")
+            .into_iter()
+            .filter_map(|img| img.attribute("src"))
+            .map(|src| url::resolve(&base, src))
+            .collect()
+    }
+
+    /// The page's author style sources in document order: the text of each
+    /// `<style>` element and the resolved URL of each
+    /// `<link rel="stylesheet">`.
+    pub(crate) fn style_sources(&self) -> Vec<StyleSource> {
+        let base = self.base_url();
+        let mut sources = Vec::new();
+        let mut stack = vec![&self.root];
+        while let Some(element) = stack.pop() {
+            if element.tag == "style" {
+                sources.push(StyleSource::Inline(element.text_content.clone().unwrap_or_default()));
+            } else if element.tag == "link"
+                && element.attribute("rel").is_some_and(|rel| {
+                    rel.split_whitespace().any(|r| r.eq_ignore_ascii_case("stylesheet"))
+                })
+                && let Some(href) = element.attribute("href")
+            {
+                sources.push(StyleSource::Linked(url::resolve(&base, href)));
+            }
+            stack.extend(element.children.iter().rev());
+        }
+        sources
+    }
+
+    /// The page's `preconnect`, `dns-prefetch` and `preload` link hints in
+    /// document order. A link whose `rel` lists several hints yields one
+    /// entry per hint.
+    pub fn resource_hints(&self) -> Vec<ResourceHint> {
+        let base = self.base_url();
+        let mut hints = Vec::new();
+        for link in self.elements_with_tag("link") {
+            let (Some(rel), Some(href)) = (link.attribute("rel"), link.attribute("href")) else {
+                continue;
+            };
+            for rel in rel.split_whitespace() {
+                let kind = match rel.to_ascii_lowercase().as_str() {
+                    "preconnect" => ResourceHintKind::Preconnect,
+                    "dns-prefetch" => ResourceHintKind::DnsPrefetch,
+                    "preload" => ResourceHintKind::Preload,
+                    _ => continue,
+                };
+                hints.push(ResourceHint {
+                    kind,
+                    url: url::resolve(&base, href),
+                    destination: (kind == ResourceHintKind::Preload)
+                        .then(|| link.attribute("as").map(str::to_ascii_lowercase))
+                        .flatten(),
+                });
+            }
+        }
+        hints
+    }
+
+    /// Preloaded resources the page declares with the given `as` destination.
+    pub(crate) fn preloads(&self, destination: &str) -> Vec<String> {
+        self.resource_hints()
+            .into_iter()
+            .filter(|hint| {
+                hint.kind == ResourceHintKind::Preload
+                    && hint.destination.as_deref() == Some(destination)
+            })
+            .map(|hint| hint.url)
+            .collect()
+    }
+
+    /// Resolved URL of the page's favicon.
+    ///
+    /// Uses the first `<link rel="icon">`, falling back to `/favicon.ico` for
+    /// http(s) pages.
+    pub fn favicon_url(&self) -> Option<String> {
+        let base = self.base_url();
+        let declared = self.elements_with_tag("link").into_iter().find_map(|link| {
+            let rel = link.attribute("rel")?;
+            rel.split_whitespace()
+                .any(|r| r.eq_ignore_ascii_case("icon"))
+                .then(|| link.attribute("href"))
+                .flatten()
+        });
+        match declared {
+            Some(href) => Some(url::resolve(&base, href)),
+            None if base.starts_with("http://") || base.starts_with("https://") => {
+                Some(url::resolve(&base, "/favicon.ico"))
+            },
+            None => None,
+        }
+    }
+
+    /// Heading structure of the page: each `h1`–`h6` level and its text, in
+    /// document order. Headings without text are skipped.
+    pub fn outline(&self) -> Vec<(u8, String)> {
+        let mut outline = Vec::new();
+        let mut stack = vec![&self.root];
+        while let Some(element) = stack.pop() {
+            let level = match element.tag.as_bytes() {
+                [b'h', level @ b'1'..=b'6'] => Some(level - b'0'),
+                _ => None,
+            };
+            if let Some(level) = level {
+                let text = element.inner_text().split_whitespace().collect::<Vec<_>>().join(" ");
+                if !text.is_empty() {
+                    outline.push((level, text));
+                }
+                continue;
+            }
+            stack.extend(element.children.iter().rev());
+        }
+        outline
+    }
+
+    /// Word count and reading time of the page's text at
+    /// [`DEFAULT_WORDS_PER_MINUTE`].
+    pub fn reading_stats(&self) -> ReadingStats {
+        self.reading_stats_at(DEFAULT_WORDS_PER_MINUTE)
+    }
+
+    /// Word count and reading time of the page's text at the given reading
+    /// speed. Script and style text is not counted.
+    pub fn reading_stats_at(&self, words_per_minute: u32) -> ReadingStats {
+        let word_count = self.root.inner_text().split_whitespace().count();
+        let words_per_minute = words_per_minute.max(1);
+        let seconds = (word_count as u64 * 60).div_ceil(u64::from(words_per_minute));
+        ReadingStats { word_count, words_per_minute, reading_time: Duration::from_secs(seconds) }
+    }
+
+    /// `<video>` and `<audio>` elements with `autoplay`, in document order:
+    /// the media that starts playing on its own once the page loads.
+    pub fn autoplay_media(&self) -> Vec<&Element> {
+        let mut media = Vec::new();
+        let mut stack = vec![&self.root];
+        while let Some(element) = stack.pop() {
+            if matches!(element.tag.as_str(), "video" | "audio")
+                && element.attribute("autoplay").is_some()
+            {
+                media.push(element);
+            }
+            stack.extend(element.children.iter().rev());
+        }
+        media
+    }
+
+    /// Unmuted [`autoplay_media`](Self::autoplay_media) in document order:
+    /// the media that autoplay blocking holds back.
+    pub fn unmuted_autoplay_media(&self) -> Vec<&Element> {
+        let mut media = self.autoplay_media();
+        media.retain(|element| element.attribute("muted").is_none());
+        media
+    }
+
+    /// Parse the page's `<meta name="viewport">` tag, if present.
+    pub fn viewport_meta(&self) -> Option<ViewportMeta> {
+        let mut stack = vec![&self.root];
+        while let Some(element) = stack.pop() {
+            if element.tag == "meta"
+                && element.attribute("name").is_some_and(|n| n.eq_ignore_ascii_case("viewport"))
+            {
+                return Some(parse_viewport_content(element.attribute("content").unwrap_or("")));
+            }
+            stack.extend(element.children.iter().rev());
+        }
+        None
+    }
+
+    /// Extract the main article content of the page.
+    ///
+    /// Prefers an explicit `<article>` or `<main>` element; otherwise picks the
+    /// element whose direct paragraph children carry the most text. Returns
+    /// `None` when the page has no paragraph content.
+    pub fn extract_article(&self) -> Option<Element> {
+        if let Some(article) = self.root.find_tag("article").or_else(|| self.root.find_tag("main"))
+        {
+            return Some(article.clone());
+        }
+
+        let mut best: Option<(&Element, usize)> = None;
+        let mut stack = vec![&self.root];
+        while let Some(element) = stack.pop() {
+            let score: usize = element
+                .children
+                .iter()
+                .filter(|child| child.tag == "p")
+                .map(|child| child.inner_text().len())
+                .sum();
+            if score > best.map_or(0, |(_, s)| s) {
+                best = Some((element, score));
+            }
+            stack.extend(element.children.iter().rev());
+        }
+
+        best.map(|(element, _)| element.clone())
+    }
+
+    fn resolve_links(&self, include: impl Fn(&Element) -> bool) -> Vec<String> {
+        let base = self.base_url();
+        self.elements_with_tag("a")
+            .into_iter()
+            .filter(|a| include(a))
+            .filter_map(|a| a.attribute("href"))
+            .map(str::trim)
+            .filter(|href| {
+                !href.starts_with('#')
+                    && !href.get(..11).is_some_and(|s| s.eq_ignore_ascii_case("javascript:"))
+            })
+            .map(|href| url::resolve(&base, href))
+            .collect()
+    }
+
+    fn elements_with_tag(&self, tag: &str) -> Vec<&Element> {
+        let mut out = Vec::new();
+        self.root.find_all_tag(tag, &mut out);
+        out
+    }
+}
+
+/// Collect the elements of a subtree matching any of `selectors`, in
+/// document order; `ancestors` holds the elements above `element`, root
+/// first. Returns whether collection stopped at a first match.
+fn collect_matching<'a>(
+    element: &'a Element,
+    selectors: &[Selector],
+    ancestors: &mut Vec<&'a Element>,
+    first_only: bool,
+    out: &mut Vec<&'a Element>,
+) -> bool {
+    if selectors.iter().any(|s| s.matches(element, ancestors)) {
+        out.push(element);
+        if first_only {
+            return true;
+        }
+    }
+    ancestors.push(element);
+    let done = element
+        .children
+        .iter()
+        .any(|child| collect_matching(child, selectors, ancestors, first_only, out));
+    ancestors.pop();
+    done
+}
+
+/// Parse the `content` of a viewport meta tag, e.g.
+/// `width=device-width, initial-scale=1`.
+fn parse_viewport_content(content: &str) -> ViewportMeta {
+    let mut meta = ViewportMeta::default();
+    for entry in content.split([',', ';']) {
+        let Some((key, value)) = entry.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "width" => {
+                meta.width = if value.eq_ignore_ascii_case("device-width") {
+                    Some(ViewportWidth::DeviceWidth)
+                } else {
+                    value.parse().ok().filter(|w: &f32| *w > 0.0).map(ViewportWidth::Pixels)
+                };
+            },
+            "initial-scale" => {
+                meta.initial_scale = value.parse().ok().filter(|s: &f32| *s > 0.0);
+            },
+            _ => {},
+        }
+    }
+    meta
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(root: Element) -> Document {
+        Document { title: String::new(), root, url: "https://example.com/".into() }
+    }
+
+    #[test]
+    fn test_viewport_meta_parsing() {
+        let meta = Element::new("meta")
+            .with_attribute("name", "viewport")
+            .with_attribute("content", "width=device-width, initial-scale=1.5");
+        let doc = document(Element::new("html").with_child(Element::new("head").with_child(meta)));
+
+        let viewport = doc.viewport_meta().unwrap();
+        assert_eq!(viewport.width, Some(ViewportWidth::DeviceWidth));
+        assert_eq!(viewport.initial_scale, Some(1.5));
+
+        assert_eq!(parse_viewport_content("width=600").width, Some(ViewportWidth::Pixels(600.0)));
+        assert_eq!(parse_viewport_content("width=bogus").width, None);
+        assert!(document(Element::new("html")).viewport_meta().is_none());
+    }
+
+    #[test]
+    fn test_base_href_overrides_document_url() {
+        let head = Element::new("head")
+            .with_child(
+                Element::new("base").with_attribute("href", "https://cdn.example.org/docs/"),
+            )
+            .with_child(Element::new("base").with_attribute("href", "https://ignored.example/"))
+            .with_child(
+                Element::new("link")
+                    .with_attribute("rel", "shortcut icon")
+                    .with_attribute("href", "icon.png"),
+            );
+        let body = Element::new("body")
+            .with_child(Element::new("a").with_attribute("href", "guide.html"))
+            .with_child(Element::new("img").with_attribute("src", "../logo.png"));
+        let doc = Document {
+            title: String::new(),
+            root:  Element::new("html").with_child(head).with_child(body),
+            url:   "https://example.com/blog/post".into(),
+        };
+
+        assert_eq!(doc.base_url(), "https://cdn.example.org/docs/");
+        assert_eq!(doc.links(), ["https://cdn.example.org/docs/guide.html"]);
+        assert_eq!(doc.images(), ["https://cdn.example.org/logo.png"]);
+        assert_eq!(doc.favicon_url().as_deref(), Some("https://cdn.example.org/docs/icon.png"));
+    }
+
+    fn robots_document(content: &str) -> Document {
+        let head = Element::new("head").with_child(
+            Element::new("meta")
+                .with_attribute("name", "ROBOTS")
+                .with_attribute("content", content),
+        );
+        let body = Element::new("body")
+            .with_child(Element::new("a").with_attribute("href", "/about"))
+            .with_child(
+                Element::new("a")
+                    .with_attribute("href", "/ads")
+                    .with_attribute("rel", "sponsored nofollow"),
+            );
+        document(Element::new("html").with_child(head).with_child(body))
+    }
+
+    #[test]
+    fn test_meta_robots_combined_directives() {
+        let robots = robots_document("NoIndex, nofollow , noarchive").meta_robots();
+        assert_eq!(
+            robots,
+            RobotsDirectives {
+                noindex:   true,
+                nofollow:  true,
+                noarchive: true,
+                nosnippet: false,
+            }
+        );
+
+        let none = robots_document("none").meta_robots();
+        assert!(none.noindex && none.nofollow);
+        assert_eq!(document(Element::new("html")).meta_robots(), RobotsDirectives::default());
+    }
+
+    #[test]
+    fn test_followable_links_respect_nofollow() {
+        let doc = robots_document("noindex");
+        assert_eq!(doc.links(), ["https://example.com/about", "https://example.com/ads"]);
+        assert_eq!(doc.followable_links(), ["https://example.com/about"]);
+
+        assert!(robots_document("nofollow").followable_links().is_empty());
+    }
+
+    #[test]
+    fn test_outline_lists_headings_in_order() {
+        let section = Element::new("section")
+            .with_child(Element::new("h2").with_text("Setup"))
+            .with_child(Element::new("h3").with_child(Element::new("em").with_text("Linux")))
+            .with_child(Element::new("h3").with_text("   "))
+            .with_child(Element::new("p").with_text("Not a heading"));
+        let body = Element::new("body")
+            .with_child(Element::new("h1").with_text("Guide"))
+            .with_child(section)
+            .with_child(Element::new("h2").with_text("  Usage\n notes "));
+        let doc = document(Element::new("html").with_child(body));
+
+        assert_eq!(
+            doc.outline(),
+            [
+                (1, "Guide".to_string()),
+                (2, "Setup".to_string()),
+                (3, "Linux".to_string()),
+                (2, "Usage notes".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resource_hints_by_kind() {
+        let link = |rel: &str, href: &str| {
+            Element::new("link").with_attribute("rel", rel).with_attribute("href", href)
+        };
+        let head = Element::new("head")
+            .with_child(link("preconnect", "https://cdn.example.org"))
+            .with_child(link("dns-prefetch", "//fonts.example.net"))
+            .with_child(link("stylesheet", "/site.css"))
+            .with_child(link("preload", "/hero.png").with_attribute("as", "Image"))
+            .with_child(link("preconnect dns-prefetch", "https://api.example.com"));
+        let doc = Document {
+            title: String::new(),
+            root:  Element::new("html").with_child(head),
+            url:   "https://example.com/page".into(),
+        };
+
+        let hints: Vec<_> = doc
+            .resource_hints()
+            .into_iter()
+            .map(|hint| (hint.kind, hint.url, hint.destination))
+            .collect();
+        assert_eq!(
+            hints,
+            [
+                (ResourceHintKind::Preconnect, "https://cdn.example.org".to_string(), None),
+                (ResourceHintKind::DnsPrefetch, "https://fonts.example.net".to_string(), None),
+                (
+                    ResourceHintKind::Preload,
+                    "https://example.com/hero.png".to_string(),
+                    Some("image".to_string())
+                ),
+                (ResourceHintKind::Preconnect, "https://api.example.com".to_string(), None),
+                (ResourceHintKind::DnsPrefetch, "https://api.example.com".to_string(), None),
+            ]
+        );
+        assert_eq!(doc.preloads("image"), ["https://example.com/hero.png"]);
+        assert!(doc.preloads("style").is_empty());
+    }
+
+    #[test]
+    fn test_reading_stats() {
+        let paragraph = "The quick brown fox jumps over the lazy dog again and again.";
+        let doc = document(
+            Element::new("body")
+                .with_child(Element::new("script").with_text("var ignored = true;"))
+                .with_child(Element::new("p").with_text(paragraph)),
+        );
+
+        let stats = doc.reading_stats_at(120);
+        assert_eq!(stats.word_count, 12);
+        assert_eq!(stats.reading_time, Duration::from_secs(6));
+        assert_eq!(doc.reading_stats().words_per_minute, DEFAULT_WORDS_PER_MINUTE);
+        assert_eq!(doc.reading_stats().reading_time, Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_get_element_by_id_and_tag() {
+        let root = Element::new("body")
+            .with_child(
+                Element::new("div")
+                    .with_attribute("id", "outer")
+                    .with_child(Element::new("p").with_attribute("id", "first").with_text("One"))
+                    .with_child(Element::new("div").with_child(Element::new("p").with_text("Two"))),
+            )
+            .with_child(Element::new("p").with_attribute("id", "first").with_text("Three"));
+
+        assert_eq!(root.get_element_by_id("first").unwrap().text_content.as_deref(), Some("One"));
+        assert_eq!(root.get_element_by_id("outer").unwrap().tag, "div");
+        assert!(root.get_element_by_id("missing").is_none());
+
+        let texts: Vec<_> = root
+            .get_elements_by_tag("P")
+            .iter()
+            .filter_map(|p| p.text_content.as_deref())
+            .collect();
+        assert_eq!(texts, ["One", "Two", "Three"]);
+        assert_eq!(root.get_elements_by_tag("div").len(), 2);
+        assert!(root.get_elements_by_tag("table").is_empty());
+
+        // Tags built in upper case match too.
+        let upper = Element::new("BODY").with_child(Element::new("DIV"));
+        assert_eq!(upper.get_elements_by_tag("div").len(), 1);
+        assert_eq!(upper.get_elements_by_tag("Body").len(), 1);
+    }
+
+    #[test]
+    fn test_query_selector_in_document_order() {
+        let item = |text: &str| Element::new("li").with_attribute("class", "item").with_text(text);
+        let doc = document(
+            Element::new("body")
+                .with_child(Element::new("p").with_text("Intro"))
+                .with_child(
+                    Element::new("div")
+                        .with_attribute("id", "main")
+                        .with_child(Element::new("ul").with_child(item("A")).with_child(item("B")))
+                        .with_child(
+                            Element::new("section").with_child(Element::new("p").with_text("Deep")),
+                        ),
+                )
+                .with_child(item("C")),
+        );
+        let texts = |elements: Vec<&Element>| -> Vec<String> {
+            elements.iter().filter_map(|e| e.text_content.clone()).collect()
+        };
+
+        assert_eq!(texts(doc.query_selector_all(".item")), ["A", "B", "C"]);
+        assert_eq!(texts(doc.query_selector_all("#main p")), ["Deep"]);
+        assert_eq!(texts(doc.query_selector_all("body > p, ul li")), ["Intro", "A", "B"]);
+        assert_eq!(doc.query_selector("#main *").unwrap().tag, "ul");
+        assert_eq!(doc.query_selector(".item").unwrap().text_content.as_deref(), Some("A"));
+        assert!(doc.query_selector("a.nav-link").is_none());
+        assert!(doc.query_selector_all("p, ###").is_empty());
+    }
+
+    #[test]
+    fn test_links_resolve_against_document_url() {
+        let link = |href: &str| Element::new("a").with_attribute("href", href);
+        let mut doc = document(
+            Element::new("body")
+                .with_child(link("https://other.org/page"))
+                .with_child(link("../about.html"))
+                .with_child(link("//cdn.example.net/file"))
+                .with_child(link("#section"))
+                .with_child(link("JavaScript:void(0)"))
+                .with_child(link(" next?page=2 "))
+                .with_child(Element::new("a").with_text("no href")),
+        );
+        doc.url = "https://example.com/docs/guide/index.html".into();
+
+        assert_eq!(
+            doc.links(),
+            [
+                "https://other.org/page",
+                "https://example.com/docs/about.html",
+                "https://cdn.example.net/file",
+                "https://example.com/docs/guide/next?page=2",
+            ]
+        );
+    }
+}
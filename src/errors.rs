@@ -17,6 +17,8 @@ pub enum BrowserError {
     Network(String),
     /// Navigation error.
     Navigation(String),
+    /// Navigation refused by a privacy or security setting.
+    Blocked(String),
 }
 
 impl fmt::Display for BrowserError {
@@ -28,6 +30,7 @@ impl fmt::Display for BrowserError {
             Self::Render(msg) => write!(f, "Render error: {msg}"),
             Self::Network(msg) => write!(f, "Network error: {msg}"),
             Self::Navigation(msg) => write!(f, "Navigation error: {msg}"),
+            Self::Blocked(msg) => write!(f, "Blocked: {msg}"),
         }
     }
 }
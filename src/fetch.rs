@@ -0,0 +1,445 @@
+//! Content fetching contract.
+//!
+//! The browser never performs network I/O itself; embedders supply a
+//! [`ContentFetcher`] (typically backed by essentia_net_plugin).
+
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Condvar, Mutex, PoisonError, mpsc},
+    thread,
+    time::Duration,
+};
+
+use crate::errors::{BrowserError, BrowserResult};
+
+/// Kind of resource being requested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceKind {
+    /// Top-level HTML document.
+    Document,
+    /// Image referenced by the page, including CSS background images.
+    Image,
+    /// Site icon shown with the tab.
+    Favicon,
+    /// Stylesheet linked with `<link rel="stylesheet">`.
+    Stylesheet,
+}
+
+/// A request for a single resource.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchRequest {
+    /// Absolute URL to fetch.
+    pub url:     String,
+    /// Kind of resource.
+    pub kind:    ResourceKind,
+    /// Request headers.
+    pub headers: Vec<(String, String)>,
+    /// Time after which the browser gives up on the request. Fetchers should
+    /// abort by then too, so abandoned requests do not keep running.
+    pub timeout: Option<Duration>,
+    /// Whether the fetcher may send and store cookies for this request.
+    pub cookies: bool,
+}
+
+impl FetchRequest {
+    /// Create a request with no extra headers and no timeout.
+    pub fn new(url: impl Into<String>, kind: ResourceKind) -> Self {
+        Self { url: url.into(), kind, headers: Vec::new(), timeout: None, cookies: true }
+    }
+
+    /// Set the request timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Add a request header.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Get a request header by case-insensitive name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// A fetched resource.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchResponse {
+    /// HTTP status code.
+    pub status:       u16,
+    /// Content type of the body.
+    pub content_type: String,
+    /// Response headers.
+    pub headers:      Vec<(String, String)>,
+    /// Response body.
+    pub body:         Vec<u8>,
+}
+
+impl FetchResponse {
+    /// Create a `200 OK` response.
+    pub fn ok(content_type: impl Into<String>, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status:       200,
+            content_type: content_type.into(),
+            headers:      Vec::new(),
+            body:         body.into(),
+        }
+    }
+
+    /// Get a response header by case-insensitive name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Body decoded as UTF-8, replacing invalid sequences.
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+/// Source of page content and subresources.
+pub trait ContentFetcher: Send + Sync {
+    /// Fetch a resource.
+    fn fetch(&self, request: &FetchRequest) -> BrowserResult<FetchResponse>;
+}
+
+/// Future resolving to the response of an [`AsyncContentFetcher`].
+#[cfg(feature = "async")]
+pub type FetchFuture<'a> =
+    std::pin::Pin<Box<dyn Future<Output = BrowserResult<FetchResponse>> + Send + 'a>>;
+
+/// Source of page content for embedders running in an async runtime.
+///
+/// Unlike [`ContentFetcher`], the browser does not enforce
+/// [`FetchRequest::timeout`] on these fetches; the fetcher should.
+#[cfg(feature = "async")]
+pub trait AsyncContentFetcher: Send + Sync {
+    /// Fetch a resource.
+    fn fetch<'a>(&'a self, request: &'a FetchRequest) -> FetchFuture<'a>;
+}
+
+/// Fetcher used when no embedder fetcher is configured.
+///
+/// Every document is an empty page and every subresource is empty.
+#[derive(Debug, Default)]
+pub struct BlankFetcher;
+
+impl ContentFetcher for BlankFetcher {
+    fn fetch(&self, request: &FetchRequest) -> BrowserResult<FetchResponse> {
+        Ok(match request.kind {
+            ResourceKind::Document => {
+                FetchResponse::ok("text/html", "<!DOCTYPE html><html><body></body></html>")
+            },
+            ResourceKind::Stylesheet => FetchResponse::ok("text/css", Vec::new()),
+            ResourceKind::Image | ResourceKind::Favicon => {
+                FetchResponse::ok("application/octet-stream", Vec::new())
+            },
+        })
+    }
+}
+
+/// Fetch a request, failing with `Network("timeout")` if the fetcher has not
+/// answered within the request's timeout.
+///
+/// With a timeout the fetch runs on its own thread, which is abandoned (not
+/// killed) when the deadline passes.
+pub(crate) fn fetch_with_timeout(
+    fetcher: &Arc<dyn ContentFetcher>,
+    request: &FetchRequest,
+) -> BrowserResult<FetchResponse> {
+    fetch_holding(fetcher, request, ())
+}
+
+/// [`fetch_with_timeout`], keeping `guard` alive until the fetch itself
+/// finishes, even when it is abandoned past its deadline.
+fn fetch_holding<G: Send + 'static>(
+    fetcher: &Arc<dyn ContentFetcher>,
+    request: &FetchRequest,
+    guard: G,
+) -> BrowserResult<FetchResponse> {
+    let Some(timeout) = request.timeout else {
+        let _guard = guard;
+        return fetcher.fetch(request);
+    };
+
+    let (sender, receiver) = mpsc::channel();
+    let fetcher = Arc::clone(fetcher);
+    let request = request.clone();
+    thread::spawn(move || {
+        let _guard = guard;
+        // The receiver is gone if the request already timed out.
+        let _ = sender.send(fetcher.fetch(&request));
+    });
+    receiver.recv_timeout(timeout).unwrap_or_else(|e| {
+        Err(BrowserError::Network(match e {
+            mpsc::RecvTimeoutError::Timeout => "timeout".into(),
+            mpsc::RecvTimeoutError::Disconnected => "fetcher panicked".into(),
+        }))
+    })
+}
+
+/// Counting semaphore bounding how many subresource fetches run at once.
+#[derive(Debug)]
+pub(crate) struct ConnectionLimiter {
+    max:   usize,
+    slots: Arc<Slots>,
+}
+
+/// Free connection slots, shared with the permits holding the others.
+#[derive(Debug)]
+struct Slots {
+    available: Mutex<usize>,
+    released:  Condvar,
+}
+
+/// A held connection slot, returned to the limiter on drop.
+///
+/// A permit is not tied to the limiter's lifetime, so a fetch abandoned
+/// past its timeout keeps its slot until it actually finishes.
+pub(crate) struct ConnectionPermit {
+    slots: Arc<Slots>,
+}
+
+impl ConnectionLimiter {
+    /// Allow up to `max` concurrent fetches (at least one).
+    pub(crate) fn new(max: usize) -> Self {
+        let max = max.max(1);
+        let slots = Slots { available: Mutex::new(max), released: Condvar::new() };
+        Self { max, slots: Arc::new(slots) }
+    }
+
+    /// Block until a connection slot is free and take it.
+    pub(crate) fn acquire(&self) -> ConnectionPermit {
+        let slots = &self.slots;
+        let mut available = slots.available.lock().unwrap_or_else(PoisonError::into_inner);
+        while *available == 0 {
+            available = slots.released.wait(available).unwrap_or_else(PoisonError::into_inner);
+        }
+        *available -= 1;
+        ConnectionPermit { slots: Arc::clone(slots) }
+    }
+
+    /// Fetch every request, queuing those beyond the connection limit.
+    /// Each fetch is bounded by its request's timeout; a timed-out fetch
+    /// holds its connection until it finishes.
+    ///
+    /// Results are returned in request order, one per request; a fetch that
+    /// panics yields `Network("fetcher panicked")`.
+    pub(crate) fn fetch_all(
+        &self,
+        fetcher: &Arc<dyn ContentFetcher>,
+        requests: &[FetchRequest],
+    ) -> Vec<BrowserResult<FetchResponse>> {
+        let queue = Mutex::new(requests.iter().enumerate());
+        let workers = self.max.min(requests.len());
+        let mut results: Vec<_> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut done = Vec::new();
+                        loop {
+                            let next = queue.lock().unwrap_or_else(PoisonError::into_inner).next();
+                            let Some((index, request)) = next else {
+                                break done;
+                            };
+                            let permit = self.acquire();
+                            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                                fetch_holding(fetcher, request, permit)
+                            }))
+                            .unwrap_or_else(|_| {
+                                Err(BrowserError::Network("fetcher panicked".into()))
+                            });
+                            done.push((index, result));
+                        }
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap_or_default())
+                .collect()
+        });
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+}
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        *self.slots.available.lock().unwrap_or_else(PoisonError::into_inner) += 1;
+        self.slots.released.notify_one();
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use std::{
+        collections::HashMap,
+        sync::{
+            Mutex,
+            atomic::{AtomicUsize, Ordering},
+        },
+        thread,
+        time::Duration,
+    };
+
+    use super::*;
+
+    /// Fetcher serving canned responses (blank content otherwise) and
+    /// recording every request.
+    ///
+    /// Conditional requests whose `If-None-Match` equals the canned response's
+    /// `ETag` get `304 Not Modified`.
+    #[derive(Debug, Default)]
+    pub(crate) struct MockFetcher {
+        responses: HashMap<String, FetchResponse>,
+        requests:  Mutex<Vec<FetchRequest>>,
+        delay:     Duration,
+        in_flight: AtomicUsize,
+        peak:      AtomicUsize,
+    }
+
+    impl MockFetcher {
+        pub(crate) fn with_response(mut self, url: &str, response: FetchResponse) -> Self {
+            self.responses.insert(url.to_string(), response);
+            self
+        }
+
+        /// Take this long to answer each request.
+        pub(crate) fn with_delay(mut self, delay: Duration) -> Self {
+            self.delay = delay;
+            self
+        }
+
+        /// Highest number of requests in flight at once.
+        pub(crate) fn peak_concurrency(&self) -> usize {
+            self.peak.load(Ordering::SeqCst)
+        }
+
+        /// All requests made so far.
+        pub(crate) fn requests(&self) -> Vec<FetchRequest> {
+            self.requests.lock().unwrap().clone()
+        }
+
+        /// URLs requested so far for the given resource kind.
+        pub(crate) fn requested(&self, kind: ResourceKind) -> Vec<String> {
+            let requests = self.requests.lock().unwrap();
+            requests.iter().filter(|r| r.kind == kind).map(|r| r.url.clone()).collect()
+        }
+    }
+
+    impl ContentFetcher for MockFetcher {
+        fn fetch(&self, request: &FetchRequest) -> BrowserResult<FetchResponse> {
+            self.requests.lock().unwrap().push(request.clone());
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(in_flight, Ordering::SeqCst);
+            thread::sleep(self.delay);
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            let Some(response) = self.responses.get(&request.url) else {
+                return BlankFetcher.fetch(request);
+            };
+            if let Some(etag) = response.header("etag")
+                && request.header("if-none-match") == Some(etag)
+            {
+                return Ok(FetchResponse { status: 304, body: Vec::new(), ..response.clone() });
+            }
+            Ok(response.clone())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{testing::MockFetcher, *};
+
+    #[test]
+    fn test_fetch_all_respects_connection_limit() {
+        let fetcher = Arc::new(MockFetcher::default().with_delay(Duration::from_millis(5)));
+        let limiter = ConnectionLimiter::new(3);
+        let requests: Vec<_> = (0..12)
+            .map(|i| FetchRequest::new(format!("https://example.com/{i}.png"), ResourceKind::Image))
+            .collect();
+
+        let shared: Arc<dyn ContentFetcher> = fetcher.clone();
+        let results = limiter.fetch_all(&shared, &requests);
+        assert_eq!(results.len(), 12);
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(fetcher.requests().len(), 12);
+        assert!((1..=3).contains(&fetcher.peak_concurrency()));
+
+        // Permits are shared: a slot held elsewhere reduces the parallelism.
+        let fetcher = Arc::new(MockFetcher::default().with_delay(Duration::from_millis(5)));
+        let shared: Arc<dyn ContentFetcher> = fetcher.clone();
+        let _held = limiter.acquire();
+        limiter.fetch_all(&shared, &requests);
+        assert!((1..=2).contains(&fetcher.peak_concurrency()));
+    }
+
+    #[test]
+    fn test_fetch_all_survives_panicking_fetcher() {
+        struct PanickingFetcher;
+
+        impl ContentFetcher for PanickingFetcher {
+            fn fetch(&self, request: &FetchRequest) -> BrowserResult<FetchResponse> {
+                assert!(!request.url.contains("panic"), "fetcher failed");
+                BlankFetcher.fetch(request)
+            }
+        }
+
+        let fetcher: Arc<dyn ContentFetcher> = Arc::new(PanickingFetcher);
+        let limiter = ConnectionLimiter::new(1);
+        let requests: Vec<_> = ["a", "panic", "b"]
+            .into_iter()
+            .map(|name| format!("https://example.com/{name}.png"))
+            .map(|url| FetchRequest::new(url, ResourceKind::Image))
+            .collect();
+
+        let results = limiter.fetch_all(&fetcher, &requests);
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok() && results[2].is_ok());
+        assert!(matches!(&results[1], Err(BrowserError::Network(m)) if m == "fetcher panicked"));
+
+        let timed: Vec<_> = requests
+            .iter()
+            .map(|r| r.clone().with_timeout(Duration::from_secs(5)))
+            .collect();
+        let results = limiter.fetch_all(&fetcher, &timed);
+        assert_eq!(results.len(), 3);
+        assert!(matches!(&results[1], Err(BrowserError::Network(m)) if m == "fetcher panicked"));
+    }
+
+    #[test]
+    fn test_fetch_all_applies_request_timeout() {
+        let mock = Arc::new(MockFetcher::default().with_delay(Duration::from_millis(200)));
+        let fetcher: Arc<dyn ContentFetcher> = mock.clone();
+        let limiter = ConnectionLimiter::new(2);
+        let requests: Vec<_> = (0..4)
+            .map(|i| {
+                FetchRequest::new(format!("https://example.com/{i}.png"), ResourceKind::Image)
+                    .with_timeout(Duration::from_millis(20))
+            })
+            .collect();
+
+        let results = limiter.fetch_all(&fetcher, &requests);
+        assert_eq!(results.len(), 4);
+        assert!(
+            results
+                .iter()
+                .all(|result| matches!(result, Err(BrowserError::Network(m)) if m == "timeout"))
+        );
+        // Abandoned fetches keep their connections until they finish.
+        assert_eq!(mock.peak_concurrency(), 2);
+    }
+}
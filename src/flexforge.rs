@@ -21,7 +21,11 @@
 use std::{
     collections::HashMap,
     fmt::Debug,
-    sync::{Arc, Mutex},
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        Arc, Mutex, MutexGuard, PoisonError,
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+    },
 };
 
 use essentia_traits::plugin_contracts::flexforge_integration::{
@@ -30,6 +34,15 @@ use essentia_traits::plugin_contracts::flexforge_integration::{
     UiConfigurable,
 };
 
+use crate::{
+    pages,
+    renderer::{RenderEngine, downscale_rgba, rgba_len},
+    session::{BrowserSession, SessionTab},
+    style::Cascade,
+    types::{Document, PrivacyMode, RenderTiming},
+    url,
+};
+
 /// Browser configuration for FlexForge panel
 #[derive(Debug, Clone)]
 pub struct BrowserFlexForgeConfig {
@@ -48,6 +61,9 @@ pub struct BrowserFlexForgeConfig {
     pub user_agent:            String,
     pub default_zoom:          u32,
     pub dark_mode:             bool,
+    // Navigation
+    pub home_url:              String,
+    pub new_tab_url:           String,
     // AI Features
     pub ai_content_summary:    bool,
     pub ai_translation:        bool,
@@ -69,6 +85,8 @@ impl Default for BrowserFlexForgeConfig {
             user_agent:            "EssentiaBrowser/1.0 (FlexForge)".to_string(),
             default_zoom:          100,
             dark_mode:             false,
+            home_url:              "about:home".to_string(),
+            new_tab_url:           "about:blank".to_string(),
             ai_content_summary:    true,
             ai_translation:        false,
             ai_reading_mode:       false,
@@ -88,6 +106,13 @@ pub struct BrowserTab {
     pub can_go_forward: bool,
     pub zoom_level:     u32,
     pub pinned:         bool,
+    pub muted:          bool,
+    pub playing_media:  bool,
+    pub document:       Option<Document>,
+    /// Set when rendering the tab failed; cleared by `recover_tab`.
+    pub crashed:        bool,
+    /// Tab group this tab belongs to, if any.
+    pub group_id:       Option<u64>,
 }
 
 impl Default for BrowserTab {
@@ -102,10 +127,63 @@ impl Default for BrowserTab {
             can_go_forward: false,
             zoom_level:     100,
             pinned:         false,
+            muted:          false,
+            playing_media:  false,
+            document:       None,
+            crashed:        false,
+            group_id:       None,
         }
     }
 }
 
+/// A named, coloured group of tabs kept adjacent in the tab strip.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TabGroup {
+    pub id:    u64,
+    pub name:  String,
+    pub color: String,
+}
+
+/// Where an address bar suggestion came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuggestionSource {
+    History,
+    Bookmark,
+    OpenTab,
+}
+
+/// Address bar autocomplete suggestion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub url:    String,
+    pub title:  String,
+    pub source: SuggestionSource,
+}
+
+/// A visited or bookmarked page; `last_used` orders entries by recency.
+#[derive(Debug, Clone)]
+struct PageEntry {
+    url:       String,
+    title:     String,
+    last_used: u64,
+}
+
+/// How well a page matches address bar input: prefix matches of the URL
+/// (ignoring scheme and `www.`) or title beat substring matches.
+fn match_quality(entry: &PageEntry, input: &str) -> Option<u8> {
+    let url = entry.url.to_ascii_lowercase();
+    let bare_url = url.split_once("://").map_or(url.as_str(), |(_, rest)| rest);
+    let bare_url = bare_url.strip_prefix("www.").unwrap_or(bare_url);
+    let title = entry.title.to_lowercase();
+    if bare_url.starts_with(input) || url.starts_with(input) || title.starts_with(input) {
+        Some(2)
+    } else if url.contains(input) || title.contains(input) {
+        Some(1)
+    } else {
+        None
+    }
+}
+
 /// Browser metrics for monitoring.
 #[derive(Debug, Clone, Default)]
 pub struct BrowserMetrics {
@@ -115,19 +193,196 @@ pub struct BrowserMetrics {
     pub blocked_trackers: u64,
     pub render_fps:       f32,
     pub page_load_ms:     u64,
+    /// Stage breakdown of the latest streamed frame.
+    pub render_timing:    RenderTiming,
+}
+
+/// A streamed frame of the active tab's viewport.
+#[derive(Debug, Clone)]
+pub struct RenderFrame {
+    pub stream_id: u64,
+    /// Frame number within the stream, starting at 0.
+    pub sequence:  u64,
+    pub width:     u32,
+    pub height:    u32,
+    /// RGBA pixels, row-major.
+    pub pixels:    Vec<u8>,
+}
+
+/// Zoom levels a tab may use, in percent.
+const ZOOM_RANGE: std::ops::RangeInclusive<u32> = 25..=500;
+
+/// Frames buffered per receiver; a consumer that falls further behind
+/// misses frames instead of queueing them.
+const FRAME_QUEUE_DEPTH: usize = 2;
+
+/// Keyboard shortcuts (in normalized form) and the toolbar actions they trigger.
+const SHORTCUT_ACTIONS: &[(&str, &str)] = &[
+    ("Alt+Left", "browser_back"),
+    ("Alt+Right", "browser_forward"),
+    ("F5", "browser_refresh"),
+    ("Alt+Home", "browser_home"),
+    ("Ctrl+T", "browser_new_tab"),
+    ("Ctrl+W", "browser_close_tab"),
+    ("F12", "browser_devtools"),
+    ("Ctrl+Shift+S", "browser_ai_summary"),
+];
+
+/// Normalizes a shortcut such as `shift+ctrl+s` to `Ctrl+Shift+S`.
+///
+/// Modifiers are ordered `Ctrl`, `Alt`, `Shift`, `Meta` and the key is
+/// capitalized, so equivalent spellings compare equal.
+fn normalize_shortcut(combo: &str) -> Option<String> {
+    let mut modifiers = [false; 4];
+    let mut key = None;
+
+    for part in combo.split('+').map(str::trim).filter(|p| !p.is_empty()) {
+        match part.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => modifiers[0] = true,
+            "alt" | "option" => modifiers[1] = true,
+            "shift" => modifiers[2] = true,
+            "meta" | "cmd" | "super" => modifiers[3] = true,
+            other => {
+                if key.is_some() {
+                    return None;
+                }
+                let mut chars = other.chars();
+                let first = chars.next()?.to_ascii_uppercase();
+                key = Some(format!("{first}{}", chars.as_str()));
+            },
+        }
+    }
+
+    let mut normalized: Vec<String> = ["Ctrl", "Alt", "Shift", "Meta"]
+        .iter()
+        .zip(modifiers)
+        .filter(|(_, held)| *held)
+        .map(|(name, _)| (*name).to_string())
+        .collect();
+    normalized.push(key?);
+    Some(normalized.join("+"))
+}
+
+/// Locks a mutex, recovering the data if a previous holder panicked.
+///
+/// Used for reads and for state that is safe to build on after a panic
+/// (caches, metrics, single-field updates).
+fn recover_lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Locks a mutex for an update that must not be applied on top of state a
+/// panicking thread may have left half-written.
+fn checked_lock<'a, T>(mutex: &'a Mutex<T>, name: &str) -> Result<MutexGuard<'a, T>, String> {
+    mutex.lock().map_err(|_| format!("Browser {name} lock poisoned"))
+}
+
+/// Takes the next ID from the counter `next`, skipping 0 and IDs that
+/// `in_use` reports as live once the counter has wrapped around.
+///
+/// At most `live` IDs are in use, so one of the next `live + 1` candidates
+/// is free unless the ID space is exhausted.
+fn allocate_id(next: &mut u64, live: usize, in_use: impl Fn(u64) -> bool) -> Result<u64, String> {
+    for _ in 0..=live {
+        let id = *next;
+        *next = next.wrapping_add(1).max(1);
+        if id != 0 && !in_use(id) {
+            return Ok(id);
+        }
+    }
+    Err("ID space exhausted".to_string())
+}
+
+/// Open tabs keyed by ID, remembering their order in the tab strip.
+#[derive(Debug, Default)]
+struct TabSet {
+    tabs:   HashMap<u64, BrowserTab>,
+    order:  Vec<u64>,
+    groups: HashMap<u64, TabGroup>,
+}
+
+impl TabSet {
+    fn insert(&mut self, tab_id: u64, tab: BrowserTab) {
+        if self.tabs.insert(tab_id, tab).is_none() {
+            self.order.push(tab_id);
+        }
+    }
+
+    fn remove(&mut self, tab_id: &u64) -> Option<BrowserTab> {
+        self.order.retain(|id| id != tab_id);
+        self.tabs.remove(tab_id)
+    }
+
+    fn get(&self, tab_id: &u64) -> Option<&BrowserTab> {
+        self.tabs.get(tab_id)
+    }
+
+    fn get_mut(&mut self, tab_id: &u64) -> Option<&mut BrowserTab> {
+        self.tabs.get_mut(tab_id)
+    }
+
+    fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Tabs in tab strip order.
+    fn values(&self) -> impl Iterator<Item = &BrowserTab> {
+        self.order.iter().filter_map(|id| self.tabs.get(id))
+    }
+
+    /// Moves a tab to `index` in the tab strip, then regroups.
+    fn move_to(&mut self, tab_id: u64, index: usize) -> bool {
+        let Some(position) = self.order.iter().position(|id| *id == tab_id) else {
+            return false;
+        };
+        self.order.remove(position);
+        self.order.insert(index.min(self.order.len()), tab_id);
+        self.regroup();
+        true
+    }
+
+    /// Pulls every grouped tab next to the first tab of its group, keeping
+    /// the relative order of all other tabs.
+    fn regroup(&mut self) {
+        let group_of = |id: &u64| self.tabs.get(id).and_then(|tab| tab.group_id);
+        let mut order = Vec::with_capacity(self.order.len());
+        for id in &self.order {
+            match group_of(id) {
+                None => order.push(*id),
+                Some(group) if !order.iter().any(|placed| group_of(placed) == Some(group)) => {
+                    order.extend(self.order.iter().filter(|other| group_of(other) == Some(group)));
+                },
+                Some(_) => {},
+            }
+        }
+        self.order = order;
+    }
 }
 
+/// Rendered tab thumbnails keyed by tab ID and maximum dimension.
+type ThumbnailCache = HashMap<(u64, u32), Vec<u8>>;
+
 /// FlexForge integration for the Browser plugin
 #[derive(Debug)]
 pub struct BrowserFlexForgeIntegration {
     config:         Arc<Mutex<BrowserFlexForgeConfig>>,
     metrics:        Arc<Mutex<BrowserMetrics>>,
-    tabs:           Arc<Mutex<HashMap<u64, BrowserTab>>>,
+    tabs:           Arc<Mutex<TabSet>>,
+    thumbnails:     Arc<Mutex<ThumbnailCache>>,
+    domain_zoom:    HashMap<String, u32>,
+    viewport:       RenderEngine,
     active_tab_id:  Option<u64>,
     next_tab_id:    u64,
+    next_group_id:  u64,
+    history:        Vec<PageEntry>,
+    bookmarks:      Vec<PageEntry>,
+    /// Logical clock for history and bookmark recency.
+    clock:          u64,
     stream_active:  bool,
     stream_id:      Option<u64>,
     next_stream_id: u64,
+    frame_sequence: u64,
+    frame_senders:  Arc<Mutex<Vec<SyncSender<RenderFrame>>>>,
     devtools_open:  bool,
 }
 
@@ -135,7 +390,7 @@ impl BrowserFlexForgeIntegration {
     /// Create a new FlexForge integration instance
     #[must_use]
     pub fn new() -> Self {
-        let mut tabs = HashMap::new();
+        let mut tabs = TabSet::default();
         let initial_tab = BrowserTab { id: 1, ..Default::default() };
         tabs.insert(1, initial_tab);
 
@@ -143,77 +398,525 @@ impl BrowserFlexForgeIntegration {
             config:         Arc::new(Mutex::new(BrowserFlexForgeConfig::default())),
             metrics:        Arc::new(Mutex::new(BrowserMetrics::default())),
             tabs:           Arc::new(Mutex::new(tabs)),
+            thumbnails:     Arc::new(Mutex::new(HashMap::new())),
+            domain_zoom:    HashMap::new(),
+            viewport:       RenderEngine::default(),
             active_tab_id:  Some(1),
             next_tab_id:    2,
+            next_group_id:  1,
+            history:        Vec::new(),
+            bookmarks:      Vec::new(),
+            clock:          0,
             stream_active:  false,
             stream_id:      None,
             next_stream_id: 1,
+            frame_sequence: 0,
+            frame_senders:  Arc::new(Mutex::new(Vec::new())),
             devtools_open:  false,
         }
     }
 
     fn config(&self) -> BrowserFlexForgeConfig {
-        self.config.lock().map(|c| c.clone()).unwrap_or_default()
+        recover_lock(&self.config).clone()
     }
 
-    fn set_config(&self, config: BrowserFlexForgeConfig) {
-        if let Ok(mut guard) = self.config.lock() {
-            *guard = config;
-        }
+    fn set_config(&self, config: BrowserFlexForgeConfig) -> Result<(), String> {
+        *checked_lock(&self.config, "config")? = config;
+        Ok(())
+    }
+
+    fn privacy_mode(&self) -> PrivacyMode {
+        PrivacyMode::parse(&self.config().privacy_mode).unwrap_or_default()
     }
 
     /// Creates a new tab and returns its ID.
-    pub fn create_tab(&mut self, url: Option<&str>) -> u64 {
-        let tab_id = self.next_tab_id;
-        self.next_tab_id = self.next_tab_id.wrapping_add(1);
+    pub fn create_tab(&mut self, url: Option<&str>) -> Result<u64, String> {
+        let tab_id = self.insert_tab(url)?;
+        self.active_tab_id = Some(tab_id);
+        Ok(tab_id)
+    }
+
+    /// Creates a new tab without making it active, for links opened in the
+    /// background. Returns the new tab's ID.
+    pub fn create_background_tab(&mut self, url: Option<&str>) -> Result<u64, String> {
+        let tab_id = self.insert_tab(url)?;
+        if self.active_tab_id.is_none() {
+            self.active_tab_id = Some(tab_id);
+        }
+        Ok(tab_id)
+    }
+
+    fn insert_tab(&mut self, url: Option<&str>) -> Result<u64, String> {
+        let tab_id = {
+            let tabs = recover_lock(&self.tabs);
+            allocate_id(&mut self.next_tab_id, tabs.len(), |id| tabs.get(&id).is_some())?
+        };
 
+        let tab_url = url.map_or_else(|| self.config().new_tab_url, str::to_string);
         let tab = BrowserTab {
             id: tab_id,
-            url: url.unwrap_or("about:blank").to_string(),
+            zoom_level: self.zoom_for(&tab_url),
+            url: tab_url,
             loading: url.is_some(),
             ..Default::default()
         };
 
-        if let Ok(mut tabs) = self.tabs.lock() {
-            tabs.insert(tab_id, tab);
-        }
+        recover_lock(&self.tabs).insert(tab_id, tab);
 
-        self.active_tab_id = Some(tab_id);
         self.update_tab_metrics();
-        tab_id
+        Ok(tab_id)
     }
 
     /// Closes a tab by ID.
     pub fn close_tab(&mut self, tab_id: u64) -> Result<(), String> {
-        if let Ok(mut tabs) = self.tabs.lock() {
+        {
+            let mut tabs = checked_lock(&self.tabs, "tabs")?;
             if tabs.len() <= 1 {
                 return Err("Cannot close last tab".to_string());
             }
+            let position = tabs.order.iter().position(|id| *id == tab_id);
             tabs.remove(&tab_id);
 
-            // Switch to another tab if this was active
+            // Switch to the neighbouring tab if this was active
             if self.active_tab_id == Some(tab_id) {
-                self.active_tab_id = tabs.keys().next().copied();
+                let next = position.unwrap_or(0).min(tabs.order.len() - 1);
+                self.active_tab_id = tabs.order.get(next).copied();
+            }
+        }
+        self.invalidate_thumbnails(tab_id);
+        self.update_tab_metrics();
+        Ok(())
+    }
+
+    /// Returns all tabs in tab strip order.
+    pub fn all_tabs(&self) -> Vec<BrowserTab> {
+        recover_lock(&self.tabs).values().cloned().collect()
+    }
+
+    /// Snapshot of the open tabs in tab strip order, for saving.
+    pub fn session(&self) -> BrowserSession {
+        let tabs = recover_lock(&self.tabs);
+        let tabs = tabs
+            .values()
+            .map(|tab| SessionTab {
+                id:     tab.id,
+                url:    tab.url.clone(),
+                title:  tab.title.clone(),
+                pinned: tab.pinned,
+                zoom:   tab.zoom_level,
+                active: self.active_tab_id == Some(tab.id),
+            })
+            .collect();
+        BrowserSession { tabs }
+    }
+
+    /// Replaces the open tabs with those of a saved session, keeping their
+    /// order, IDs and active tab. Documents are not loaded.
+    ///
+    /// Nothing changes if the session has no tabs, its tab IDs are zero or
+    /// repeated, or a zoom level is outside 25–500%.
+    pub fn restore_session(&mut self, session: &BrowserSession) -> Result<(), String> {
+        if session.tabs.is_empty() {
+            return Err("Session has no tabs".to_string());
+        }
+        let mut restored = TabSet::default();
+        for saved in &session.tabs {
+            if saved.id == 0 || restored.get(&saved.id).is_some() {
+                return Err(format!("Session has an invalid or repeated tab ID {}", saved.id));
+            }
+            if !ZOOM_RANGE.contains(&saved.zoom) {
+                return Err(format!(
+                    "Session tab {} has zoom {}%; zoom must be between 25% and 500%",
+                    saved.id, saved.zoom
+                ));
             }
+            let tab = BrowserTab {
+                id: saved.id,
+                url: saved.url.clone(),
+                title: saved.title.clone(),
+                pinned: saved.pinned,
+                zoom_level: saved.zoom,
+                ..Default::default()
+            };
+            restored.insert(saved.id, tab);
         }
+
+        let active = session.tabs.iter().find(|tab| tab.active).unwrap_or(&session.tabs[0]);
+        let max_id = restored.order.iter().copied().max().unwrap_or(0);
+        *checked_lock(&self.tabs, "tabs")? = restored;
+        recover_lock(&self.thumbnails).clear();
+        self.active_tab_id = Some(active.id);
+        self.next_tab_id = max_id.wrapping_add(1).max(1);
         self.update_tab_metrics();
         Ok(())
     }
 
+    /// Moves a tab to `index` in the tab strip.
+    ///
+    /// Grouped tabs stay contiguous: the members of a group are gathered
+    /// next to whichever member comes first after the move.
+    pub fn move_tab(&mut self, tab_id: u64, index: usize) -> Result<(), String> {
+        if checked_lock(&self.tabs, "tabs")?.move_to(tab_id, index) {
+            Ok(())
+        } else {
+            Err("Unknown tab".to_string())
+        }
+    }
+
+    /// Creates an empty tab group and returns its ID.
+    pub fn create_group(&mut self, name: &str, color: &str) -> Result<u64, String> {
+        let mut tabs = checked_lock(&self.tabs, "tabs")?;
+        let groups = &tabs.groups;
+        let group_id =
+            allocate_id(&mut self.next_group_id, groups.len(), |id| groups.contains_key(&id))?;
+        let group = TabGroup { id: group_id, name: name.to_string(), color: color.to_string() };
+        tabs.groups.insert(group_id, group);
+        Ok(group_id)
+    }
+
+    /// Moves a tab into a group, placing it after the group's other tabs.
+    /// The first tab added to a group keeps its position.
+    pub fn add_to_group(&mut self, tab_id: u64, group_id: u64) -> Result<(), String> {
+        let mut tabs = checked_lock(&self.tabs, "tabs")?;
+        if !tabs.groups.contains_key(&group_id) {
+            return Err("Unknown tab group".to_string());
+        }
+        let tab = tabs.get_mut(&tab_id).ok_or("Unknown tab")?;
+        tab.group_id = Some(group_id);
+        let position = tabs.order.iter().position(|id| *id == tab_id).unwrap_or_default();
+        tabs.order.remove(position);
+        let last_member = tabs
+            .order
+            .iter()
+            .rposition(|id| tabs.get(id).is_some_and(|tab| tab.group_id == Some(group_id)));
+        let index = last_member.map_or(position, |member| member + 1);
+        tabs.order.insert(index, tab_id);
+        Ok(())
+    }
+
+    /// Removes a group, leaving its tabs open and ungrouped in place.
+    pub fn remove_group(&mut self, group_id: u64) -> Result<(), String> {
+        let mut tabs = checked_lock(&self.tabs, "tabs")?;
+        tabs.groups.remove(&group_id).ok_or("Unknown tab group")?;
+        for tab in tabs.tabs.values_mut().filter(|tab| tab.group_id == Some(group_id)) {
+            tab.group_id = None;
+        }
+        Ok(())
+    }
+
+    /// Returns the tab groups, ordered by ID.
+    pub fn groups(&self) -> Vec<TabGroup> {
+        let mut groups: Vec<_> = recover_lock(&self.tabs).groups.values().cloned().collect();
+        groups.sort_by_key(|group| group.id);
+        groups
+    }
+
     /// Navigates the active tab to a URL.
     pub fn navigate(&mut self, url: &str) -> Result<(), String> {
         let tab_id = self.active_tab_id.ok_or("No active tab")?;
 
-        if let Ok(mut tabs) = self.tabs.lock() {
-            if let Some(tab) = tabs.get_mut(&tab_id) {
-                tab.url = url.to_string();
+        let zoom_level = self.zoom_for(url);
+        if let Some(tab) = checked_lock(&self.tabs, "tabs")?.get_mut(&tab_id) {
+            tab.url = url.to_string();
+            tab.zoom_level = zoom_level;
+            tab.loading = true;
+            tab.can_go_back = true;
+            tab.document = None;
+            tab.crashed = false;
+        }
+        self.invalidate_thumbnails(tab_id);
+        if self.privacy_mode() != PrivacyMode::Private {
+            self.record_visit(url);
+        }
+        Ok(())
+    }
+
+    /// Navigates the active tab to the configured home page.
+    pub fn go_home(&mut self) -> Result<(), String> {
+        let home_url = self.config().home_url;
+        self.navigate(&home_url)
+    }
+
+    /// Stores the loaded document for a tab and marks loading as finished.
+    pub fn set_tab_document(&mut self, tab_id: u64, document: Document) -> Result<(), String> {
+        {
+            let mut tabs = checked_lock(&self.tabs, "tabs")?;
+            let tab = tabs.get_mut(&tab_id).ok_or("Unknown tab")?;
+            if let Some(entry) = self.history.iter_mut().find(|entry| entry.url == tab.url) {
+                entry.title = document.title.clone();
+            }
+            tab.title = document.title.clone();
+            tab.loading = false;
+            tab.document = Some(document);
+            tab.crashed = false;
+        }
+        self.invalidate_thumbnails(tab_id);
+        Ok(())
+    }
+
+    /// Bookmarks a page, replacing any existing bookmark for the URL.
+    pub fn add_bookmark(&mut self, url: &str, title: &str) {
+        self.clock += 1;
+        self.bookmarks.retain(|bookmark| bookmark.url != url);
+        self.bookmarks.push(PageEntry {
+            url:       url.to_string(),
+            title:     title.to_string(),
+            last_used: self.clock,
+        });
+    }
+
+    /// Address bar suggestions for `input` from history, bookmarks and open
+    /// tabs, best first.
+    ///
+    /// Prefix matches rank above substring matches, then more recently used
+    /// pages come first. Each URL is suggested once. History is left out in
+    /// private mode.
+    pub fn suggest(&self, input: &str, limit: usize) -> Vec<Suggestion> {
+        let input = input.trim().to_lowercase();
+        if input.is_empty() {
+            return Vec::new();
+        }
+
+        let history: &[PageEntry] = if self.privacy_mode() == PrivacyMode::Private {
+            &[]
+        } else {
+            &self.history
+        };
+        let tabs: Vec<PageEntry> = recover_lock(&self.tabs)
+            .values()
+            .map(|tab| PageEntry {
+                url:       tab.url.clone(),
+                title:     tab.title.clone(),
+                last_used: history
+                    .iter()
+                    .find(|entry| entry.url == tab.url)
+                    .map_or(0, |entry| entry.last_used),
+            })
+            .collect();
+
+        let mut ranked: Vec<(u8, u64, Suggestion)> = Vec::new();
+        let sources = [
+            (SuggestionSource::OpenTab, tabs.as_slice()),
+            (SuggestionSource::Bookmark, self.bookmarks.as_slice()),
+            (SuggestionSource::History, history),
+        ];
+        for (source, entries) in sources {
+            for entry in entries {
+                let Some(quality) = match_quality(entry, &input) else {
+                    continue;
+                };
+                if ranked.iter().any(|(_, _, suggestion)| suggestion.url == entry.url) {
+                    continue;
+                }
+                let suggestion =
+                    Suggestion { url: entry.url.clone(), title: entry.title.clone(), source };
+                ranked.push((quality, entry.last_used, suggestion));
+            }
+        }
+        ranked.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+        ranked.into_iter().take(limit).map(|(_, _, suggestion)| suggestion).collect()
+    }
+
+    /// Reloads a crashed tab from its URL, clearing the crashed state.
+    pub fn recover_tab(&mut self, tab_id: u64) -> Result<(), String> {
+        let mut crashed = false;
+        self.update_tab(tab_id, |tab| {
+            crashed = tab.crashed;
+            if tab.crashed {
+                tab.crashed = false;
                 tab.loading = true;
-                tab.can_go_back = true;
+                tab.document = None;
             }
+        })?;
+        if !crashed {
+            return Err("Tab has not crashed".to_string());
+        }
+        self.invalidate_thumbnails(tab_id);
+        Ok(())
+    }
+
+    /// Runs render work for a tab, containing failures to that tab.
+    ///
+    /// A panic or `None` result marks the tab crashed instead of unwinding
+    /// into the integration. Crashed tabs are not rendered until recovered.
+    fn render_isolated<T>(&self, tab_id: u64, render: impl FnOnce() -> Option<T>) -> Option<T> {
+        if recover_lock(&self.tabs).get(&tab_id)?.crashed {
+            return None;
+        }
+        let result = panic::catch_unwind(AssertUnwindSafe(render)).ok().flatten();
+        if result.is_none() {
+            let _ = self.update_tab(tab_id, |tab| tab.crashed = true);
+            self.invalidate_thumbnails(tab_id);
+        }
+        result
+    }
+
+    /// Scrolls the active viewport to `y`; smooth scrolls advance with each
+    /// streamed frame.
+    pub fn scroll_to(&mut self, y: f32, smooth: bool) {
+        self.viewport.scroll_to(y, smooth);
+    }
+
+    /// Current vertical scroll offset of the active viewport (px).
+    pub fn scroll_offset(&self) -> f32 {
+        self.viewport.scroll_offset()
+    }
+
+    /// Returns a receiver of the frames rendered while streaming.
+    ///
+    /// Each streamed `render_frame` rasterizes the active tab and sends the
+    /// result to every receiver. Frames are only rendered while at least one
+    /// receiver is alive; dropping a receiver unsubscribes it.
+    pub fn frame_receiver(&self) -> Receiver<RenderFrame> {
+        let (sender, receiver) = mpsc::sync_channel(FRAME_QUEUE_DEPTH);
+        recover_lock(&self.frame_senders).push(sender);
+        receiver
+    }
+
+    /// Renders the active tab's viewport and sends it to the frame
+    /// receivers, dropping receivers that have disconnected.
+    fn publish_frame(&mut self, stream_id: u64) {
+        if recover_lock(&self.frame_senders).is_empty() {
+            return;
+        }
+        let Some(tab_id) = self.active_tab_id else {
+            return;
+        };
+        let document = recover_lock(&self.tabs).get(&tab_id).and_then(|tab| tab.document.clone());
+        let rendered = match document {
+            Some(document) => self.render_isolated(tab_id, || {
+                let (_, display_list, timing) =
+                    self.viewport.render_timed(&document, &Cascade::new()).ok()?;
+                recover_lock(&self.metrics).render_timing = timing;
+                Some(self.viewport.rasterize(&display_list))
+            }),
+            None => {
+                let (width, height) = self.viewport.viewport_size();
+                let (width, height) = (width as u32, height as u32);
+                rgba_len(width, height)
+                    .map(|len| (width, height, [255, 255, 255, 255].repeat(len / 4)))
+            },
+        };
+        let Some((width, height, pixels)) = rendered else {
+            return;
+        };
+
+        let frame = RenderFrame { stream_id, sequence: self.frame_sequence, width, height, pixels };
+        self.frame_sequence += 1;
+        recover_lock(&self.frame_senders).retain(|sender| {
+            !matches!(sender.try_send(frame.clone()), Err(TrySendError::Disconnected(_)))
+        });
+    }
+
+    /// Sets a tab's zoom level (percent) and remembers it for the tab's
+    /// domain, so later navigations to that domain in any tab reuse it.
+    pub fn set_zoom(&mut self, tab_id: u64, zoom: u32) -> Result<(), String> {
+        if !ZOOM_RANGE.contains(&zoom) {
+            return Err("Zoom must be between 25% and 500%".to_string());
+        }
+        let mut domain = None;
+        self.update_tab(tab_id, |tab| {
+            tab.zoom_level = zoom;
+            domain = url::host(&tab.url);
+        })?;
+        if let Some(domain) = domain {
+            self.domain_zoom.insert(domain, zoom);
         }
         Ok(())
     }
 
+    /// Mutes or unmutes a tab's media output.
+    pub fn set_muted(&mut self, tab_id: u64, muted: bool) -> Result<(), String> {
+        self.update_tab(tab_id, |tab| tab.muted = muted)
+    }
+
+    /// Records whether a tab is currently playing media, for speaker indicators.
+    pub fn set_playing_media(&mut self, tab_id: u64, playing: bool) -> Result<(), String> {
+        self.update_tab(tab_id, |tab| tab.playing_media = playing)
+    }
+
+    /// Renders a downscaled RGBA thumbnail of a tab for the tab switcher.
+    ///
+    /// The thumbnail keeps the viewport aspect ratio with its longest side
+    /// equal to `max_dim`. Tabs without a document yield a solid white fill;
+    /// crashed tabs yield `None`.
+    /// Results are cached until the tab navigates or receives a new document.
+    pub fn tab_thumbnail(&self, tab_id: u64, max_dim: u32) -> Option<Vec<u8>> {
+        if max_dim == 0 {
+            return None;
+        }
+        if let Some(cached) = recover_lock(&self.thumbnails).get(&(tab_id, max_dim)).cloned() {
+            return Some(cached);
+        }
+
+        let document = {
+            let tabs = recover_lock(&self.tabs);
+            let tab = tabs.get(&tab_id).filter(|tab| !tab.crashed)?;
+            tab.document.clone()
+        };
+
+        let engine = RenderEngine::default();
+        let (viewport_width, viewport_height) = engine.viewport_size();
+        let scale = max_dim as f32 / viewport_width.max(viewport_height);
+        let thumb_width = (viewport_width * scale).round().max(1.0) as u32;
+        let thumb_height = (viewport_height * scale).round().max(1.0) as u32;
+
+        let thumbnail = match document {
+            Some(document) => self.render_isolated(tab_id, || {
+                let mut tree = engine.build_render_tree(&document).ok()?;
+                engine.layout(&mut tree);
+                let (width, height, pixels) = engine.render_to_rgba(&tree);
+                Some(downscale_rgba(&pixels, width, height, thumb_width, thumb_height))
+            })?,
+            None => [255, 255, 255, 255].repeat(rgba_len(thumb_width, thumb_height)? / 4),
+        };
+
+        recover_lock(&self.thumbnails).insert((tab_id, max_dim), thumbnail.clone());
+        Some(thumbnail)
+    }
+
+    /// Dispatches a keyboard shortcut to its toolbar action.
+    ///
+    /// Returns the id of the triggered action, or `None` if the combination is
+    /// unbound or the action is currently disabled. Actions the integration
+    /// cannot perform on its own (history, AI summary) are still reported so
+    /// the host can act on them.
+    pub fn handle_shortcut(&mut self, combo: &str) -> Option<&'static str> {
+        let normalized = normalize_shortcut(combo)?;
+        let action_id = SHORTCUT_ACTIONS
+            .iter()
+            .find(|(shortcut, _)| *shortcut == normalized)
+            .map(|(_, id)| *id)?;
+
+        let enabled = self.get_toolbar_actions().iter().any(|a| a.id == action_id && a.enabled);
+        if !enabled {
+            return None;
+        }
+
+        match action_id {
+            "browser_new_tab" => {
+                let _ = self.create_tab(None);
+            },
+            "browser_close_tab" => {
+                if let Some(tab_id) = self.active_tab_id {
+                    let _ = self.close_tab(tab_id);
+                }
+            },
+            "browser_refresh" => {
+                if let Some(tab_id) = self.active_tab_id {
+                    let _ = self.update_tab(tab_id, |tab| tab.loading = true);
+                }
+            },
+            "browser_home" => {
+                let _ = self.go_home();
+            },
+            "browser_devtools" => self.devtools_open = !self.devtools_open,
+            _ => {},
+        }
+
+        Some(action_id)
+    }
+
     /// Returns panel info with full capabilities.
     #[must_use]
     pub fn panel_info(&self) -> FlexForgePanelInfo {
@@ -232,16 +935,46 @@ impl BrowserFlexForgeIntegration {
         }
     }
 
-    fn update_tab_metrics(&self) {
-        if let (Ok(tabs), Ok(mut metrics)) = (self.tabs.lock(), self.metrics.lock()) {
-            metrics.open_tabs = tabs.len() as u32;
+    fn update_tab(&self, tab_id: u64, f: impl FnOnce(&mut BrowserTab)) -> Result<(), String> {
+        let mut tabs = checked_lock(&self.tabs, "tabs")?;
+        let tab = tabs.get_mut(&tab_id).ok_or("Unknown tab")?;
+        f(tab);
+        Ok(())
+    }
+
+    /// Zoom level for a URL: the remembered zoom of its domain, or the
+    /// configured default.
+    fn zoom_for(&self, url: &str) -> u32 {
+        url::host(url)
+            .and_then(|domain| self.domain_zoom.get(&domain).copied())
+            .unwrap_or_else(|| self.config().default_zoom)
+    }
+
+    /// Adds a visit to the history; titles are filled in when the page loads.
+    fn record_visit(&mut self, url: &str) {
+        self.clock += 1;
+        match self.history.iter_mut().find(|entry| entry.url == url) {
+            Some(entry) => entry.last_used = self.clock,
+            None => self.history.push(PageEntry {
+                url:       url.to_string(),
+                title:     String::new(),
+                last_used: self.clock,
+            }),
         }
     }
 
-    fn next_stream(&mut self) -> u64 {
-        let id = self.next_stream_id;
-        self.next_stream_id = self.next_stream_id.wrapping_add(1);
-        id
+    fn invalidate_thumbnails(&self, tab_id: u64) {
+        recover_lock(&self.thumbnails).retain(|(id, _), _| *id != tab_id);
+    }
+
+    fn update_tab_metrics(&self) {
+        let open_tabs = recover_lock(&self.tabs).len() as u32;
+        recover_lock(&self.metrics).open_tabs = open_tabs;
+    }
+
+    fn next_stream(&mut self) -> Result<u64, String> {
+        let live = self.stream_id;
+        allocate_id(&mut self.next_stream_id, usize::from(live.is_some()), |id| live == Some(id))
     }
 }
 
@@ -292,11 +1025,7 @@ impl FlexForgeIntegration for BrowserFlexForgeIntegration {
 
     fn on_refresh(&mut self) -> bool {
         // Refresh if any tab is loading
-        if let Ok(tabs) = self.tabs.lock() {
-            tabs.values().any(|t| t.loading)
-        } else {
-            false
-        }
+        recover_lock(&self.tabs).values().any(|t| t.loading)
     }
 }
 
@@ -378,6 +1107,17 @@ impl UiConfigurable for BrowserFlexForgeIntegration {
                     .with_description("Force dark mode on websites")
                     .with_group("Appearance"),
             )
+            // Navigation
+            .with_field(
+                ConfigField::text("home_url", "Home Page")
+                    .with_description("Page opened by the Home button")
+                    .with_group("Navigation"),
+            )
+            .with_field(
+                ConfigField::text("new_tab_url", "New Tab Page")
+                    .with_description("Page shown in newly opened tabs")
+                    .with_group("Navigation"),
+            )
             // AI Features
             .with_field(
                 ConfigField::toggle("ai_content_summary", "AI Page Summary", true)
@@ -401,7 +1141,11 @@ impl UiConfigurable for BrowserFlexForgeIntegration {
         match key {
             "enable_javascript" => config.enable_javascript = value == "true",
             "enable_cookies" => config.enable_cookies = value == "true",
-            "privacy_mode" => config.privacy_mode = value.to_string(),
+            "privacy_mode" => {
+                PrivacyMode::parse(value)
+                    .ok_or_else(|| format!("Unknown privacy mode: {value}"))?;
+                config.privacy_mode = value.to_string();
+            },
             "block_trackers" => config.block_trackers = value == "true",
             "https_only" => config.https_only = value == "true",
             "max_tabs" => {
@@ -415,19 +1159,20 @@ impl UiConfigurable for BrowserFlexForgeIntegration {
             "user_agent" => config.user_agent = value.to_string(),
             "default_zoom" => {
                 let zoom: u32 = value.parse().map_err(|_| "Invalid number")?;
-                if !(25..=500).contains(&zoom) {
+                if !ZOOM_RANGE.contains(&zoom) {
                     return Err("Zoom must be between 25% and 500%".to_string());
                 }
                 config.default_zoom = zoom;
             },
             "dark_mode" => config.dark_mode = value == "true",
+            "home_url" => config.home_url = value.to_string(),
+            "new_tab_url" => config.new_tab_url = value.to_string(),
             "ai_content_summary" => config.ai_content_summary = value == "true",
             "ai_translation" => config.ai_translation = value == "true",
             "ai_reading_mode" => config.ai_reading_mode = value == "true",
             _ => return Err(format!("Unknown key: {}", key)),
         }
-        self.set_config(config);
-        Ok(())
+        self.set_config(config)
     }
 
     fn apply_config(&mut self, config: &[(String, String)]) -> Result<(), String> {
@@ -470,6 +1215,8 @@ impl UiConfigurable for BrowserFlexForgeIntegration {
             ("user_agent".to_string(), config.user_agent),
             ("default_zoom".to_string(), config.default_zoom.to_string()),
             ("dark_mode".to_string(), config.dark_mode.to_string()),
+            ("home_url".to_string(), config.home_url),
+            ("new_tab_url".to_string(), config.new_tab_url),
             (
                 "ai_content_summary".to_string(),
                 config.ai_content_summary.to_string(),
@@ -486,7 +1233,8 @@ impl UiConfigurable for BrowserFlexForgeIntegration {
     }
 
     fn reset_to_defaults(&mut self) {
-        self.set_config(BrowserFlexForgeConfig::default());
+        // Replacing the whole config is safe even over a poisoned lock.
+        *recover_lock(&self.config) = BrowserFlexForgeConfig::default();
     }
 }
 
@@ -504,34 +1252,37 @@ impl EditorPresentable for BrowserFlexForgeIntegration {
             String::from("text/html"),
             String::from("application/xhtml+xml"),
             String::from("text/plain"),
-            String::from("application/pdf"),
             String::from("image/*"),
             String::from("essentia/browser-session"),
         ]
     }
 
+    /// Loads content into the active tab according to its type.
+    ///
+    /// HTML types navigate to the URL in `content_id`; for `text/plain`,
+    /// `content_id` is the text itself, shown verbatim. Images are shown
+    /// centered on their own page. For `essentia/browser-session`,
+    /// `content_id` is a session saved by `save_content`, which replaces the
+    /// open tabs.
     fn load_content(&mut self, content_id: &str, content_type: &str) -> Result<(), String> {
-        match content_type {
+        let mime = content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+        let document = match mime.as_str() {
             "essentia/browser-session" => {
-                // Load saved browser session
-                // content_id would be a session file path
-                Ok(())
+                return self.restore_session(&BrowserSession::from_json(content_id)?);
             },
-            _ => {
-                // Navigate to URL
-                self.navigate(content_id)
-            },
-        }
+            "text/html" | "application/xhtml+xml" => return self.navigate(content_id),
+            "text/plain" => pages::text_document("about:blank", content_id),
+            _ if mime.starts_with("image/") => pages::image_document(content_id),
+            _ => return Err(format!("Unsupported content type: {content_type}")),
+        };
+        self.navigate(&document.url)?;
+        let tab_id = self.active_tab_id.ok_or("No active tab")?;
+        self.set_tab_document(tab_id, document)
     }
 
+    /// Saves the open tabs as an `essentia/browser-session` JSON document.
     fn save_content(&self) -> Result<String, String> {
-        // Serialize current tabs as session
-        if let Ok(tabs) = self.tabs.lock() {
-            let urls: Vec<&str> = tabs.values().map(|t| t.url.as_str()).collect();
-            Ok(urls.join(";"))
-        } else {
-            Err("Failed to access tabs".to_string())
-        }
+        Ok(self.session().to_json())
     }
 
     fn has_unsaved_changes(&self) -> bool {
@@ -542,20 +1293,12 @@ impl EditorPresentable for BrowserFlexForgeIntegration {
 
     fn get_toolbar_actions(&self) -> Vec<EditorAction> {
         let has_active = self.active_tab_id.is_some();
-        let can_go_back = self
-            .tabs
-            .lock()
-            .ok()
-            .and_then(|tabs| self.active_tab_id.and_then(|id| tabs.get(&id).map(|t| t.can_go_back)))
-            .unwrap_or(false);
-        let can_go_forward = self
-            .tabs
-            .lock()
-            .ok()
-            .and_then(|tabs| {
-                self.active_tab_id.and_then(|id| tabs.get(&id).map(|t| t.can_go_forward))
+        let (can_go_back, can_go_forward) = self
+            .active_tab_id
+            .and_then(|id| {
+                recover_lock(&self.tabs).get(&id).map(|t| (t.can_go_back, t.can_go_forward))
             })
-            .unwrap_or(false);
+            .unwrap_or((false, false));
 
         vec![
             EditorAction {
@@ -632,9 +1375,10 @@ impl StreamingCapable for BrowserFlexForgeIntegration {
             return Err("Stream already active".to_string());
         }
 
-        let stream_id = self.next_stream();
+        let stream_id = self.next_stream()?;
         self.stream_id = Some(stream_id);
         self.stream_active = true;
+        self.frame_sequence = 0;
 
         Ok(stream_id)
     }
@@ -659,15 +1403,16 @@ impl StreamingCapable for BrowserFlexForgeIntegration {
         60
     }
 
-    fn render_frame(&mut self, stream_id: u64, _delta_ms: f64) -> bool {
+    fn render_frame(&mut self, stream_id: u64, delta_ms: f64) -> bool {
         if !self.stream_active || self.stream_id != Some(stream_id) {
             return false;
         }
 
+        self.viewport.advance_scroll(delta_ms);
+        self.publish_frame(stream_id);
+
         // Update render metrics
-        if let Ok(mut metrics) = self.metrics.lock() {
-            metrics.render_fps = 60.0; // Would come from actual renderer
-        }
+        recover_lock(&self.metrics).render_fps = 60.0; // Would come from actual renderer
 
         true
     }
@@ -715,7 +1460,7 @@ mod tests {
         assert_eq!(integration.active_tab_id, Some(1));
 
         // Create new tab
-        let tab_id = integration.create_tab(Some("https://example.com"));
+        let tab_id = integration.create_tab(Some("https://example.com")).unwrap();
         assert_eq!(integration.active_tab_id, Some(tab_id));
 
         // Close tab
@@ -796,4 +1541,481 @@ mod tests {
         // Invalid zoom (too high)
         assert!(integration.on_config_changed("default_zoom", "600").is_err());
     }
+
+    #[test]
+    fn test_shortcut_opens_new_tab_case_insensitive() {
+        let mut integration = BrowserFlexForgeIntegration::new();
+
+        assert_eq!(integration.handle_shortcut("ctrl+t"), Some("browser_new_tab"));
+        assert_eq!(integration.handle_shortcut("Ctrl+T"), Some("browser_new_tab"));
+
+        let open_tabs = integration.tabs.lock().map(|t| t.len()).unwrap_or(0);
+        assert_eq!(open_tabs, 3);
+    }
+
+    #[test]
+    fn test_shortcut_modifier_order_normalized() {
+        assert_eq!(normalize_shortcut("shift+CTRL+s").as_deref(), Some("Ctrl+Shift+S"));
+        assert_eq!(normalize_shortcut("alt + left").as_deref(), Some("Alt+Left"));
+        assert_eq!(normalize_shortcut("f12").as_deref(), Some("F12"));
+        assert_eq!(normalize_shortcut("ctrl+"), None);
+    }
+
+    #[test]
+    fn test_shortcut_table_matches_toolbar() {
+        let integration = BrowserFlexForgeIntegration::new();
+        for action in integration.get_toolbar_actions() {
+            let shortcut = action.shortcut.as_deref().and_then(normalize_shortcut).unwrap();
+            assert!(SHORTCUT_ACTIONS.contains(&(shortcut.as_str(), action.id.as_str())));
+        }
+    }
+
+    #[test]
+    fn test_shortcut_disabled_or_unbound() {
+        let mut integration = BrowserFlexForgeIntegration::new();
+
+        // Back is disabled until the tab has navigated
+        assert_eq!(integration.handle_shortcut("Alt+Left"), None);
+        assert_eq!(integration.handle_shortcut("Ctrl+Q"), None);
+
+        assert_eq!(integration.handle_shortcut("F12"), Some("browser_devtools"));
+        assert!(integration.devtools_open);
+    }
+
+    #[test]
+    fn test_tab_thumbnail_dimensions() {
+        let mut integration = BrowserFlexForgeIntegration::new();
+        let document = crate::parser::HtmlParser::parse("<html></html>", "https://essentia.dev")
+            .expect("Should parse");
+        integration.set_tab_document(1, document).unwrap();
+
+        let thumbnail = integration.tab_thumbnail(1, 64).expect("Should render");
+        assert_eq!(thumbnail.len(), 64 * 36 * 4);
+    }
+
+    #[test]
+    fn test_blank_tab_thumbnail_is_solid() {
+        let integration = BrowserFlexForgeIntegration::new();
+
+        let thumbnail = integration.tab_thumbnail(1, 32).expect("Should render");
+        assert_eq!(thumbnail.len(), 32 * 18 * 4);
+        assert!(thumbnail.chunks(4).all(|p| p == [255, 255, 255, 255]));
+        assert!(integration.tab_thumbnail(99, 32).is_none());
+    }
+
+    #[test]
+    fn test_oversized_blank_frames_are_skipped() {
+        let mut integration = BrowserFlexForgeIntegration::new();
+        assert!(integration.tab_thumbnail(1, u32::MAX).is_none());
+
+        integration.viewport = RenderEngine::new(u32::MAX as f32, u32::MAX as f32);
+        let receiver = integration.frame_receiver();
+        let stream_id = integration.start_stream().unwrap();
+        integration.render_frame(stream_id, 16.0);
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_tab_thumbnail_invalidated_on_navigation() {
+        let mut integration = BrowserFlexForgeIntegration::new();
+        integration.tab_thumbnail(1, 16).unwrap();
+        assert!(integration.thumbnails.lock().unwrap().contains_key(&(1, 16)));
+
+        integration.navigate("https://essentia.dev").unwrap();
+        assert!(integration.thumbnails.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_tab_mute_state_persists() {
+        let mut integration = BrowserFlexForgeIntegration::new();
+        integration.set_playing_media(1, true).unwrap();
+        integration.set_muted(1, true).unwrap();
+
+        let tab = integration.tabs.lock().unwrap().get(&1).cloned().unwrap();
+        assert!(tab.muted);
+        assert!(tab.playing_media);
+
+        integration.set_muted(1, false).unwrap();
+        assert!(!integration.tabs.lock().unwrap().get(&1).unwrap().muted);
+        assert!(integration.set_muted(42, true).is_err());
+    }
+
+    #[test]
+    fn test_background_tab_keeps_active_tab() {
+        let mut integration = BrowserFlexForgeIntegration::new();
+
+        let tab_id = integration.create_background_tab(Some("https://example.com")).unwrap();
+        assert_eq!(integration.active_tab_id, Some(1));
+
+        let tab = integration.tabs.lock().unwrap().get(&tab_id).cloned().unwrap();
+        assert_eq!(tab.url, "https://example.com");
+        assert!(tab.loading);
+        assert_eq!(integration.metrics.lock().unwrap().open_tabs, 2);
+    }
+
+    #[test]
+    fn test_go_home_uses_configured_url() {
+        let mut integration = BrowserFlexForgeIntegration::new();
+        integration.on_config_changed("home_url", "https://essentia.dev/start").unwrap();
+
+        integration.go_home().unwrap();
+        let tab = integration.tabs.lock().unwrap().get(&1).cloned().unwrap();
+        assert_eq!(tab.url, "https://essentia.dev/start");
+
+        integration.on_config_changed("home_url", "https://example.com").unwrap();
+        assert_eq!(integration.handle_shortcut("Alt+Home"), Some("browser_home"));
+        let tab = integration.tabs.lock().unwrap().get(&1).cloned().unwrap();
+        assert_eq!(tab.url, "https://example.com");
+    }
+
+    #[test]
+    fn test_new_tab_uses_new_tab_url() {
+        let mut integration = BrowserFlexForgeIntegration::new();
+        integration.on_config_changed("new_tab_url", "about:newtab").unwrap();
+
+        let tab_id = integration.create_tab(None).unwrap();
+        let tab = integration.tabs.lock().unwrap().get(&tab_id).cloned().unwrap();
+        assert_eq!(tab.url, "about:newtab");
+        assert!(!tab.loading);
+    }
+
+    #[test]
+    fn test_tabs_keep_creation_order() {
+        let mut integration = BrowserFlexForgeIntegration::new();
+        for url in ["https://a.test/", "https://b.test/", "https://c.test/"] {
+            integration.create_tab(Some(url)).unwrap();
+        }
+
+        let urls = |integration: &BrowserFlexForgeIntegration| {
+            integration.session().tabs.into_iter().map(|tab| tab.url).collect::<Vec<_>>()
+        };
+        let saved = integration.save_content().unwrap();
+        for _ in 0..5 {
+            assert_eq!(integration.save_content().unwrap(), saved);
+        }
+        assert_eq!(
+            urls(&integration),
+            [
+                "about:blank",
+                "https://a.test/",
+                "https://b.test/",
+                "https://c.test/"
+            ]
+        );
+        let ids: Vec<u64> = integration.all_tabs().iter().map(|t| t.id).collect();
+        assert_eq!(ids, [1, 2, 3, 4]);
+
+        integration.close_tab(3).unwrap();
+        integration.create_tab(Some("https://d.test/")).unwrap();
+        integration.close_tab(5).unwrap();
+        assert_eq!(integration.active_tab_id, Some(4));
+        assert_eq!(urls(&integration), ["about:blank", "https://a.test/", "https://c.test/"]);
+    }
+
+    #[test]
+    fn test_poisoned_config_lock_reports_error() {
+        let mut integration = BrowserFlexForgeIntegration::new();
+        integration.on_config_changed("max_tabs", "10").unwrap();
+
+        let config = Arc::clone(&integration.config);
+        let tabs = Arc::clone(&integration.tabs);
+        let _ = std::thread::spawn(move || {
+            let _config = config.lock().unwrap();
+            let _tabs = tabs.lock().unwrap();
+            panic!("poison browser locks");
+        })
+        .join();
+
+        assert!(integration.on_config_changed("max_tabs", "20").is_err());
+        assert!(integration.navigate("https://example.com").is_err());
+
+        // Reads recover the last written state instead of defaults.
+        let current = integration.get_current_config();
+        assert!(current.contains(&("max_tabs".to_string(), "10".to_string())));
+        let session = BrowserSession::from_json(&integration.save_content().unwrap()).unwrap();
+        assert_eq!(session.tabs.len(), 1);
+        assert_eq!(session.tabs[0].url, "about:blank");
+    }
+
+    #[test]
+    fn test_zoom_remembered_per_domain() {
+        let mut integration = BrowserFlexForgeIntegration::new();
+        integration.on_config_changed("default_zoom", "110").unwrap();
+        let first = integration.create_tab(Some("https://example.com/a")).unwrap();
+        integration.set_zoom(first, 150).unwrap();
+        assert!(integration.set_zoom(first, 1000).is_err());
+
+        let same = integration.create_tab(Some("https://EXAMPLE.com:443/b")).unwrap();
+        let other = integration.create_tab(Some("https://other.org/")).unwrap();
+        let zoom = |integration: &BrowserFlexForgeIntegration, id| {
+            integration.tabs.lock().unwrap().get(&id).unwrap().zoom_level
+        };
+        assert_eq!(zoom(&integration, same), 150);
+        assert_eq!(zoom(&integration, other), 110);
+
+        integration.navigate("https://example.com/c").unwrap();
+        assert_eq!(zoom(&integration, other), 150);
+    }
+
+    #[test]
+    fn test_load_content_dispatches_on_content_type() {
+        let mut integration = BrowserFlexForgeIntegration::new();
+        let tab_id = integration.active_tab_id.unwrap();
+        let root = |integration: &BrowserFlexForgeIntegration| {
+            integration
+                .tabs
+                .lock()
+                .unwrap()
+                .get(&tab_id)
+                .unwrap()
+                .document
+                .clone()
+                .unwrap()
+                .root
+        };
+
+        integration
+            .load_content("line one\n  <b>two</b>", "text/plain; charset=utf-8")
+            .unwrap();
+        let pre = &root(&integration).children[0].children[0];
+        assert_eq!(pre.tag, "pre");
+        assert_eq!(pre.text_content.as_deref(), Some("line one\n  <b>two</b>"));
+
+        integration.load_content("https://example.com/cat.png", "image/png").unwrap();
+        let html = root(&integration);
+        let img = &html.children[1].children[0];
+        assert_eq!(img.attribute("src"), Some("https://example.com/cat.png"));
+        assert_eq!(integration.tabs.lock().unwrap().get(&tab_id).unwrap().title, "cat.png");
+
+        let error = integration.load_content("doc.pdf", "application/pdf").unwrap_err();
+        assert_eq!(error, "Unsupported content type: application/pdf");
+    }
+
+    #[test]
+    fn test_smooth_scroll_advances_with_frames() {
+        let mut integration = BrowserFlexForgeIntegration::new();
+        let stream_id = integration.start_stream().unwrap();
+
+        integration.scroll_to(600.0, true);
+        assert_eq!(integration.scroll_offset(), 0.0);
+        integration.render_frame(stream_id, 100.0);
+        assert!(integration.scroll_offset() > 0.0 && integration.scroll_offset() < 600.0);
+        for _ in 0..10 {
+            integration.render_frame(stream_id, 100.0);
+        }
+        assert_eq!(integration.scroll_offset(), 600.0);
+    }
+
+    #[test]
+    fn test_tab_crash_is_isolated() {
+        let mut integration = BrowserFlexForgeIntegration::new();
+        let healthy = integration.create_tab(Some("https://example.com/ok")).unwrap();
+        let broken = integration.create_tab(Some("https://example.com/broken")).unwrap();
+
+        let result: Option<()> =
+            integration.render_isolated(broken, || panic!("simulated renderer failure"));
+        assert!(result.is_none());
+
+        let tabs = integration.all_tabs();
+        let crashed: Vec<u64> = tabs.iter().filter(|t| t.crashed).map(|t| t.id).collect();
+        assert_eq!(crashed, [broken]);
+        assert!(integration.tab_thumbnail(broken, 32).is_none());
+        let document = Document {
+            title: "Ok".into(),
+            root:  crate::types::Element::new("html"),
+            url:   "https://example.com/ok".into(),
+        };
+        integration.set_tab_document(healthy, document).unwrap();
+        assert!(integration.tab_thumbnail(healthy, 32).is_some());
+        integration.active_tab_id = Some(healthy);
+        integration.navigate("https://example.com/next").unwrap();
+
+        assert!(integration.recover_tab(healthy).is_err());
+        integration.recover_tab(broken).unwrap();
+        let tabs = integration.all_tabs();
+        let recovered = tabs.iter().find(|t| t.id == broken).unwrap();
+        assert!(!recovered.crashed && recovered.loading);
+    }
+
+    #[test]
+    fn test_grouped_tabs_stay_adjacent() {
+        let mut integration = BrowserFlexForgeIntegration::new();
+        for _ in 0..4 {
+            integration.create_tab(None).unwrap();
+        }
+        let group = integration.create_group("Research", "blue").unwrap();
+        integration.add_to_group(2, group).unwrap();
+        integration.add_to_group(4, group).unwrap();
+        let order = |integration: &BrowserFlexForgeIntegration| {
+            integration.all_tabs().iter().map(|tab| tab.id).collect::<Vec<_>>()
+        };
+        assert_eq!(order(&integration), vec![1, 2, 4, 3, 5]);
+
+        // Dropping an ungrouped tab between members pushes it past the group.
+        integration.move_tab(5, 2).unwrap();
+        assert_eq!(order(&integration), vec![1, 2, 4, 5, 3]);
+
+        integration.move_tab(2, 4).unwrap();
+        assert_eq!(order(&integration), vec![1, 4, 2, 5, 3]);
+        assert!(integration.move_tab(99, 0).is_err());
+        assert!(integration.add_to_group(1, 99).is_err());
+    }
+
+    #[test]
+    fn test_removing_group_ungroups_tabs() {
+        let mut integration = BrowserFlexForgeIntegration::new();
+        let tab_id = integration.create_tab(None).unwrap();
+        let group = integration.create_group("Work", "red").unwrap();
+        integration.add_to_group(tab_id, group).unwrap();
+        assert_eq!(integration.groups()[0].name, "Work");
+
+        integration.remove_group(group).unwrap();
+        assert!(integration.groups().is_empty());
+        assert!(integration.all_tabs().iter().all(|tab| tab.group_id.is_none()));
+        assert_eq!(integration.all_tabs().len(), 2);
+        assert!(integration.remove_group(group).is_err());
+    }
+
+    #[test]
+    fn test_suggestions_rank_by_match_and_recency() {
+        let mut integration = BrowserFlexForgeIntegration::new();
+        integration.navigate("https://docs.rust-lang.org/").unwrap();
+        integration.navigate("https://www.rust-lang.org/").unwrap();
+        integration.navigate("https://crates.io/search?q=rust").unwrap();
+        integration.add_bookmark("https://rustacean.net/", "Ferris");
+
+        let urls: Vec<String> =
+            integration.suggest("rust", 10).into_iter().map(|s| s.url).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://rustacean.net/",
+                "https://www.rust-lang.org/",
+                "https://crates.io/search?q=rust",
+                "https://docs.rust-lang.org/",
+            ]
+        );
+        let open_tab = &integration.suggest("crates", 1)[0];
+        assert_eq!(open_tab.source, SuggestionSource::OpenTab);
+        assert!(integration.suggest("", 10).is_empty());
+    }
+
+    #[test]
+    fn test_private_mode_excludes_history_suggestions() {
+        let mut integration = BrowserFlexForgeIntegration::new();
+        integration.navigate("https://example.com/").unwrap();
+        integration.navigate("about:blank").unwrap();
+        integration.add_bookmark("https://example.org/", "Example");
+        assert_eq!(integration.suggest("example", 10).len(), 2);
+
+        integration.on_config_changed("privacy_mode", "private").unwrap();
+        let suggestions = integration.suggest("example", 10);
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].source, SuggestionSource::Bookmark);
+    }
+
+    #[test]
+    fn test_frame_receiver_streams_until_dropped() {
+        let mut integration = BrowserFlexForgeIntegration::new();
+        let receiver = integration.frame_receiver();
+        let stream_id = integration.start_stream().unwrap();
+
+        assert!(integration.render_frame(stream_id, 16.0));
+        let frame = receiver.try_recv().unwrap();
+        assert_eq!((frame.stream_id, frame.sequence), (stream_id, 0));
+        assert_eq!(frame.pixels.len(), (frame.width * frame.height * 4) as usize);
+
+        // The next frame finds the receiver gone and unsubscribes it; after
+        // that, frames are no longer rendered.
+        drop(receiver);
+        integration.render_frame(stream_id, 16.0);
+        assert!(recover_lock(&integration.frame_senders).is_empty());
+        integration.render_frame(stream_id, 16.0);
+        assert_eq!(integration.frame_sequence, 2);
+    }
+
+    #[test]
+    fn test_streamed_frame_records_render_timing() {
+        let mut integration = BrowserFlexForgeIntegration::new();
+        let html = "<p>one</p>".repeat(100);
+        let document =
+            crate::parser::HtmlParser::parse(&html, "https://essentia.dev").expect("Should parse");
+        integration.set_tab_document(1, document).unwrap();
+        let _receiver = integration.frame_receiver();
+        let stream_id = integration.start_stream().unwrap();
+        assert_eq!(integration.metrics.lock().unwrap().render_timing, RenderTiming::default());
+
+        assert!(integration.render_frame(stream_id, 16.0));
+        let timing = integration.metrics.lock().unwrap().render_timing;
+        assert!(timing.style_ms >= 0.0 && timing.layout_ms >= 0.0 && timing.paint_ms >= 0.0);
+        assert!(timing.style_ms + timing.layout_ms + timing.paint_ms > 0.0);
+    }
+
+    #[test]
+    fn test_tab_ids_skip_live_tabs_after_wraparound() {
+        let mut integration = BrowserFlexForgeIntegration::new();
+        integration.update_tab(1, |tab| tab.url = "https://first.test/".into()).unwrap();
+        integration.next_tab_id = u64::MAX - 1;
+
+        assert_eq!(integration.create_tab(None).unwrap(), u64::MAX - 1);
+        assert_eq!(integration.create_tab(None).unwrap(), u64::MAX);
+        // 0 is never handed out and 1 is still open.
+        assert_eq!(integration.create_tab(None).unwrap(), 2);
+
+        let tabs = integration.all_tabs();
+        assert_eq!(tabs.len(), 4);
+        assert_eq!(tabs[0].url, "https://first.test/");
+
+        let mut next = 7;
+        assert!(allocate_id(&mut next, 3, |_| true).is_err());
+
+        let first_group = integration.create_group("First", "blue").unwrap();
+        integration.next_group_id = u64::MAX;
+        assert_eq!(integration.create_group("Last", "red").unwrap(), u64::MAX);
+        // Wrapping around skips 0 and the still-existing first group.
+        assert_eq!(integration.create_group("Next", "green").unwrap(), first_group + 1);
+        assert_eq!(integration.groups().len(), 3);
+    }
+
+    #[test]
+    fn test_session_round_trips_into_fresh_integration() {
+        let mut integration = BrowserFlexForgeIntegration::new();
+        integration.navigate("https://a.test/").unwrap();
+        let pinned = integration.create_tab(Some("https://b.test/")).unwrap();
+        integration.set_zoom(pinned, 150).unwrap();
+        recover_lock(&integration.tabs).get_mut(&pinned).unwrap().pinned = true;
+        integration.create_tab(Some("https://c.test/")).unwrap();
+        integration.active_tab_id = Some(pinned);
+        let saved = integration.save_content().unwrap();
+
+        let mut restored = BrowserFlexForgeIntegration::new();
+        restored.load_content(&saved, "essentia/browser-session").unwrap();
+        assert_eq!(restored.session(), integration.session());
+        assert_eq!(restored.active_tab_id, Some(pinned));
+        let tabs = restored.all_tabs();
+        assert_eq!(
+            tabs.iter().map(|t| (t.url.as_str(), t.pinned)).collect::<Vec<_>>(),
+            [
+                ("https://a.test/", false),
+                ("https://b.test/", true),
+                ("https://c.test/", false)
+            ]
+        );
+        assert_eq!(tabs[1].zoom_level, 150);
+        assert_eq!(restored.create_tab(None).unwrap(), 4);
+
+        for corrupt in [
+            "",
+            "{\"tabs\":[]}",
+            "{\"tabs\":[{\"id\":1,\"url\":\"x\"},{\"id\":1,\"url\":\"y\"}]}",
+            "{\"tabs\":[{\"id\":1,\"url\":\"x\",\"zoom\":5000}]}",
+            "not json",
+        ] {
+            assert!(
+                restored.load_content(corrupt, "essentia/browser-session").is_err(),
+                "{corrupt}"
+            );
+        }
+        assert_eq!(restored.all_tabs().len(), 4);
+    }
 }
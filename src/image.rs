@@ -0,0 +1,14 @@
+//! Image metadata contract.
+//!
+//! The engine does not decode images; embedders that do can supply an
+//! [`ImageProvider`] so layout reserves the right space for `<img>` boxes.
+
+/// Size used for images whose dimensions are unknown (px).
+pub(crate) const PLACEHOLDER_IMAGE_SIZE: (f32, f32) = (16.0, 16.0);
+
+/// Source of intrinsic image dimensions.
+pub trait ImageProvider: Send + Sync {
+    /// Intrinsic `(width, height)` in pixels of the image at a resolved URL,
+    /// or `None` if it is not known (yet).
+    fn intrinsic_size(&self, url: &str) -> Option<(f32, f32)>;
+}
@@ -0,0 +1,558 @@
+//! JavaScript interpreter.
+//!
+//! Currently an expression evaluator: number, string, boolean, `null` and
+//! `undefined` literals combined with arithmetic, comparison and logical
+//! operators. A [`JsContext`] adds the `document` host object.
+
+use std::{cmp::Ordering, fmt};
+
+use crate::{
+    errors::{BrowserError, BrowserResult},
+    types::{Document, JsValue},
+};
+
+/// Deepest nesting of parentheses and unary operators an expression may
+/// use, so hostile input cannot exhaust the stack.
+const MAX_EXPRESSION_DEPTH: usize = 128;
+
+/// Evaluate a single JavaScript expression.
+pub fn eval_expression(src: &str) -> BrowserResult<JsValue> {
+    let tokens = tokenize(src)?;
+    let mut parser = Parser { tokens, position: 0, depth: 0, document: None };
+    let value = parser.expression()?;
+    match parser.peek() {
+        None => Ok(value),
+        Some(token) => Err(unexpected(token)),
+    }
+}
+
+/// Script execution context bound to a document.
+///
+/// Scripts see the document as the `document` global, whose `title` can be
+/// read and assigned and whose `URL` can be read.
+#[derive(Debug)]
+pub struct JsContext<'a> {
+    document: &'a mut Document,
+}
+
+impl<'a> JsContext<'a> {
+    /// Create a context whose scripts act on `document`.
+    pub fn new(document: &'a mut Document) -> Self {
+        Self { document }
+    }
+
+    /// Run `;`-separated statements, returning the value of the last one.
+    ///
+    /// Each statement is an expression or an assignment to a `document`
+    /// property.
+    pub fn eval(&mut self, src: &str) -> BrowserResult<JsValue> {
+        let tokens = tokenize(src)?;
+        let mut parser =
+            Parser { tokens, position: 0, depth: 0, document: Some(&mut *self.document) };
+        let mut value = JsValue::Undefined;
+        while parser.peek().is_some() {
+            if parser.take(&[";"]).is_some() {
+                continue;
+            }
+            value = parser.statement()?;
+            match parser.peek() {
+                None | Some(Token::Punctuator(";")) => {},
+                Some(token) => return Err(unexpected(token)),
+            }
+        }
+        Ok(value)
+    }
+}
+
+impl JsValue {
+    /// JavaScript `ToBoolean`.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Self::Undefined | Self::Null => false,
+            Self::Boolean(b) => *b,
+            Self::Number(n) => *n != 0.0 && !n.is_nan(),
+            Self::String(s) => !s.is_empty(),
+        }
+    }
+
+    /// JavaScript `ToNumber`.
+    pub fn to_number(&self) -> f64 {
+        match self {
+            Self::Undefined => f64::NAN,
+            Self::Null => 0.0,
+            Self::Boolean(b) => f64::from(u8::from(*b)),
+            Self::Number(n) => *n,
+            Self::String(s) => match s.trim() {
+                "" => 0.0,
+                "Infinity" | "+Infinity" => f64::INFINITY,
+                "-Infinity" => f64::NEG_INFINITY,
+                // Rust also accepts spellings such as "inf" and "NaN" that
+                // JavaScript does not.
+                s if s.chars().any(|c| c.is_ascii_alphabetic() && !matches!(c, 'e' | 'E')) => {
+                    f64::NAN
+                },
+                s => s.parse().unwrap_or(f64::NAN),
+            },
+        }
+    }
+
+    /// JavaScript `typeof`.
+    pub fn type_of(&self) -> &'static str {
+        match self {
+            Self::Undefined => "undefined",
+            Self::Null => "object",
+            Self::Boolean(_) => "boolean",
+            Self::Number(_) => "number",
+            Self::String(_) => "string",
+        }
+    }
+
+    /// `===`.
+    fn strict_equals(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Undefined, Self::Undefined) | (Self::Null, Self::Null) => true,
+            (Self::Boolean(a), Self::Boolean(b)) => a == b,
+            (Self::Number(a), Self::Number(b)) => a == b,
+            (Self::String(a), Self::String(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    /// `==`, with the primitive type coercions.
+    fn loose_equals(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Undefined | Self::Null, Self::Undefined | Self::Null) => true,
+            (Self::Undefined | Self::Null, _) | (_, Self::Undefined | Self::Null) => false,
+            (Self::String(a), Self::String(b)) => a == b,
+            _ => self.to_number() == other.to_number(),
+        }
+    }
+}
+
+/// JavaScript `ToString`.
+impl fmt::Display for JsValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Undefined => f.write_str("undefined"),
+            Self::Null => f.write_str("null"),
+            Self::Boolean(b) => write!(f, "{b}"),
+            Self::Number(n) if n.is_nan() => f.write_str("NaN"),
+            Self::Number(n) if n.is_infinite() => f.write_str(if *n > 0.0 {
+                "Infinity"
+            } else {
+                "-Infinity"
+            }),
+            // Also prints -0 as "0".
+            Self::Number(n) if *n == n.trunc() && n.abs() < 1e21 => write!(f, "{}", *n as i128),
+            Self::Number(n) => write!(f, "{n}"),
+            Self::String(s) => f.write_str(s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    String(String),
+    Identifier(String),
+    Punctuator(&'static str),
+}
+
+/// Punctuators, longest first so the tokenizer matches greedily.
+const PUNCTUATORS: &[&str] = &[
+    "===", "!==", "==", "!=", "<=", ">=", "&&", "||", "+", "-", "*", "/", "%", "<", ">", "!", "=",
+    "(", ")", ".", ";",
+];
+
+fn tokenize(src: &str) -> BrowserResult<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = src;
+    loop {
+        rest = rest.trim_start();
+        let Some(c) = rest.chars().next() else {
+            return Ok(tokens);
+        };
+        if c.is_ascii_digit() || (c == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit())) {
+            let (number, tail) = take_number(rest)?;
+            tokens.push(Token::Number(number));
+            rest = tail;
+        } else if c == '"' || c == '\'' {
+            let (string, tail) = take_string(rest, c)?;
+            tokens.push(Token::String(string));
+            rest = tail;
+        } else if c.is_alphabetic() || c == '_' || c == '$' {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Identifier(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else if let Some(punctuator) = PUNCTUATORS.iter().find(|p| rest.starts_with(**p)) {
+            tokens.push(Token::Punctuator(punctuator));
+            rest = &rest[punctuator.len()..];
+        } else {
+            return Err(BrowserError::Script(format!("Unexpected character '{c}'")));
+        }
+    }
+}
+
+fn take_number(src: &str) -> BrowserResult<(f64, &str)> {
+    let mut end = src.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(src.len());
+    if src[end..].starts_with(['e', 'E']) {
+        let exponent = &src[end + 1..];
+        let digits_start = usize::from(exponent.starts_with(['+', '-']));
+        let digits = exponent[digits_start..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(exponent.len() - digits_start);
+        if digits > 0 {
+            end += 1 + digits_start + digits;
+        }
+    }
+    let literal = &src[..end];
+    if src[end..].starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+        return Err(BrowserError::Script(format!("Invalid number literal near '{literal}'")));
+    }
+    let number = literal
+        .parse()
+        .map_err(|_| BrowserError::Script(format!("Invalid number literal '{literal}'")))?;
+    Ok((number, &src[end..]))
+}
+
+fn take_string(src: &str, quote: char) -> BrowserResult<(String, &str)> {
+    let mut string = String::new();
+    let mut chars = src.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            c if c == quote => return Ok((string, &src[i + 1..])),
+            '\\' => match chars.next().map(|(_, c)| c) {
+                Some('n') => string.push('\n'),
+                Some('t') => string.push('\t'),
+                Some('r') => string.push('\r'),
+                Some('0') => string.push('\0'),
+                Some(escaped) => string.push(escaped),
+                None => break,
+            },
+            '\n' => break,
+            c => string.push(c),
+        }
+    }
+    Err(BrowserError::Script("Unterminated string literal".into()))
+}
+
+fn unexpected(token: &Token) -> BrowserError {
+    let token = match token {
+        Token::Number(n) => JsValue::Number(*n).to_string(),
+        Token::String(s) => format!("{s:?}"),
+        Token::Identifier(name) => name.clone(),
+        Token::Punctuator(p) => (*p).to_string(),
+    };
+    BrowserError::Script(format!("Unexpected token '{token}'"))
+}
+
+/// Recursive-descent evaluator, one method per precedence level.
+struct Parser<'d> {
+    tokens:   Vec<Token>,
+    position: usize,
+    /// Current nesting of unary operands, bounded by
+    /// [`MAX_EXPRESSION_DEPTH`].
+    depth:    usize,
+    /// Document exposed as the `document` global, if any.
+    document: Option<&'d mut Document>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> BrowserResult<Token> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| BrowserError::Script("Unexpected end of input".into()))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    /// Consume the next token if it is one of `punctuators`.
+    fn take(&mut self, punctuators: &[&str]) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Punctuator(p)) if punctuators.contains(p) => {
+                let p = *p;
+                self.position += 1;
+                Some(p)
+            },
+            _ => None,
+        }
+    }
+
+    /// An assignment to a `document` property, or an expression.
+    fn statement(&mut self) -> BrowserResult<JsValue> {
+        if let [
+            Token::Identifier(object),
+            Token::Punctuator("."),
+            Token::Identifier(property),
+            Token::Punctuator("="),
+        ] = &self.tokens[self.position..self.tokens.len().min(self.position + 4)]
+            && object == "document"
+        {
+            let property = property.clone();
+            self.position += 4;
+            let value = self.expression()?;
+            let document = self.document()?;
+            match property.as_str() {
+                "title" => document.title = value.to_string(),
+                // Read-only properties ignore assignments, as in sloppy mode.
+                "URL" => {},
+                _ => {
+                    return Err(BrowserError::Script(format!(
+                        "Unsupported document property '{property}'"
+                    )));
+                },
+            }
+            return Ok(value);
+        }
+        self.expression()
+    }
+
+    fn document(&mut self) -> BrowserResult<&mut Document> {
+        self.document
+            .as_deref_mut()
+            .ok_or_else(|| BrowserError::Script("document is not defined".into()))
+    }
+
+    fn expression(&mut self) -> BrowserResult<JsValue> {
+        self.logical_or()
+    }
+
+    fn logical_or(&mut self) -> BrowserResult<JsValue> {
+        let mut left = self.logical_and()?;
+        while self.take(&["||"]).is_some() {
+            let right = self.logical_and()?;
+            left = if left.is_truthy() {
+                left
+            } else {
+                right
+            };
+        }
+        Ok(left)
+    }
+
+    fn logical_and(&mut self) -> BrowserResult<JsValue> {
+        let mut left = self.equality()?;
+        while self.take(&["&&"]).is_some() {
+            let right = self.equality()?;
+            left = if left.is_truthy() {
+                right
+            } else {
+                left
+            };
+        }
+        Ok(left)
+    }
+
+    fn equality(&mut self) -> BrowserResult<JsValue> {
+        let mut left = self.relational()?;
+        while let Some(operator) = self.take(&["===", "!==", "==", "!="]) {
+            let right = self.relational()?;
+            left = JsValue::Boolean(match operator {
+                "===" => left.strict_equals(&right),
+                "!==" => !left.strict_equals(&right),
+                "==" => left.loose_equals(&right),
+                _ => !left.loose_equals(&right),
+            });
+        }
+        Ok(left)
+    }
+
+    fn relational(&mut self) -> BrowserResult<JsValue> {
+        let mut left = self.additive()?;
+        while let Some(operator) = self.take(&["<=", ">=", "<", ">"]) {
+            let right = self.additive()?;
+            let ordering = match (&left, &right) {
+                (JsValue::String(a), JsValue::String(b)) => Some(a.cmp(b)),
+                _ => left.to_number().partial_cmp(&right.to_number()),
+            };
+            // Comparisons involving NaN are always false.
+            left = JsValue::Boolean(ordering.is_some_and(|ordering| match operator {
+                "<" => ordering == Ordering::Less,
+                ">" => ordering == Ordering::Greater,
+                "<=" => ordering != Ordering::Greater,
+                _ => ordering != Ordering::Less,
+            }));
+        }
+        Ok(left)
+    }
+
+    fn additive(&mut self) -> BrowserResult<JsValue> {
+        let mut left = self.multiplicative()?;
+        while let Some(operator) = self.take(&["+", "-"]) {
+            let right = self.multiplicative()?;
+            left = match (operator, &left, &right) {
+                ("+", JsValue::String(_), _) | ("+", _, JsValue::String(_)) => {
+                    JsValue::String(format!("{left}{right}"))
+                },
+                ("+", ..) => JsValue::Number(left.to_number() + right.to_number()),
+                _ => JsValue::Number(left.to_number() - right.to_number()),
+            };
+        }
+        Ok(left)
+    }
+
+    fn multiplicative(&mut self) -> BrowserResult<JsValue> {
+        let mut left = self.unary()?;
+        while let Some(operator) = self.take(&["*", "/", "%"]) {
+            let (a, b) = (left.to_number(), self.unary()?.to_number());
+            left = JsValue::Number(match operator {
+                "*" => a * b,
+                "/" => a / b,
+                _ => a % b,
+            });
+        }
+        Ok(left)
+    }
+
+    /// A unary expression. Every level of nesting, parenthesized or not,
+    /// passes through here, so this is where depth is limited.
+    fn unary(&mut self) -> BrowserResult<JsValue> {
+        if self.depth >= MAX_EXPRESSION_DEPTH {
+            return Err(BrowserError::Script("expression nested too deeply".into()));
+        }
+        self.depth += 1;
+        let value = self.unary_operand();
+        self.depth -= 1;
+        value
+    }
+
+    fn unary_operand(&mut self) -> BrowserResult<JsValue> {
+        if let Some(operator) = self.take(&["-", "+", "!"]) {
+            let operand = self.unary()?;
+            return Ok(match operator {
+                "-" => JsValue::Number(-operand.to_number()),
+                "+" => JsValue::Number(operand.to_number()),
+                _ => JsValue::Boolean(!operand.is_truthy()),
+            });
+        }
+        if matches!(self.peek(), Some(Token::Identifier(name)) if name == "typeof") {
+            self.position += 1;
+            return Ok(JsValue::String(self.unary()?.type_of().to_string()));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> BrowserResult<JsValue> {
+        match self.next()? {
+            Token::Number(n) => Ok(JsValue::Number(n)),
+            Token::String(s) => Ok(JsValue::String(s)),
+            Token::Punctuator("(") => {
+                let value = self.expression()?;
+                match self.next()? {
+                    Token::Punctuator(")") => Ok(value),
+                    token => Err(unexpected(&token)),
+                }
+            },
+            Token::Identifier(name) => match name.as_str() {
+                "true" => Ok(JsValue::Boolean(true)),
+                "false" => Ok(JsValue::Boolean(false)),
+                "null" => Ok(JsValue::Null),
+                "undefined" => Ok(JsValue::Undefined),
+                "NaN" => Ok(JsValue::Number(f64::NAN)),
+                "Infinity" => Ok(JsValue::Number(f64::INFINITY)),
+                "document" => {
+                    if self.take(&["."]).is_none() {
+                        return Err(BrowserError::Script(
+                            "document can only be used through its properties".into(),
+                        ));
+                    }
+                    let property = match self.next()? {
+                        Token::Identifier(property) => property,
+                        token => return Err(unexpected(&token)),
+                    };
+                    let document = self.document()?;
+                    Ok(match property.as_str() {
+                        "title" => JsValue::String(document.title.clone()),
+                        "URL" => JsValue::String(document.url.clone()),
+                        _ => JsValue::Undefined,
+                    })
+                },
+                _ => Err(BrowserError::Script(format!("{name} is not defined"))),
+            },
+            token => Err(unexpected(&token)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arithmetic_and_comparison() {
+        assert_eq!(eval_expression("1 + 2 * 3").unwrap(), JsValue::Number(7.0));
+        assert_eq!(eval_expression("(1 + 2) * 3 % 4").unwrap(), JsValue::Number(1.0));
+        assert_eq!(eval_expression("-2 - -3 / 2").unwrap(), JsValue::Number(-0.5));
+        assert_eq!(eval_expression("1.5e2 >= 150 && 2 < 10").unwrap(), JsValue::Boolean(true));
+        assert_eq!(eval_expression("'10' == 10").unwrap(), JsValue::Boolean(true));
+        assert_eq!(eval_expression("'10' === 10").unwrap(), JsValue::Boolean(false));
+        assert_eq!(eval_expression("null == undefined").unwrap(), JsValue::Boolean(true));
+        assert_eq!(eval_expression("'b' > 'abc'").unwrap(), JsValue::Boolean(true));
+        assert_eq!(eval_expression("0 || 'fallback'").unwrap(), JsValue::String("fallback".into()));
+        assert_eq!(eval_expression("1 / 0").unwrap().to_string(), "Infinity");
+        assert_eq!(eval_expression("typeof !1").unwrap(), JsValue::String("boolean".into()));
+    }
+
+    #[test]
+    fn test_string_concatenation() {
+        assert_eq!(
+            eval_expression(r#""Hello, " + 'world' + "!""#).unwrap(),
+            JsValue::String("Hello, world!".into())
+        );
+        assert_eq!(eval_expression("'n=' + 1 + 2").unwrap(), JsValue::String("n=12".into()));
+        assert_eq!(eval_expression("1 + 2 + 'px'").unwrap(), JsValue::String("3px".into()));
+        assert_eq!(eval_expression("'0.5' * 2 + true").unwrap(), JsValue::Number(2.0));
+        assert_eq!(eval_expression(r"'tab\there'").unwrap(), JsValue::String("tab\there".into()));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for src in [
+            "1 +", "(1 + 2", "1 2", "'open", "3 @ 4", "", "foo + 1", "12abc",
+        ] {
+            assert!(matches!(eval_expression(src), Err(BrowserError::Script(_))), "{src}");
+        }
+    }
+
+    #[test]
+    fn test_document_title_assignment() {
+        use crate::types::Element;
+
+        let mut document = Document {
+            title: "Old".into(),
+            root:  Element::new("html"),
+            url:   "https://example.com/".into(),
+        };
+        let mut context = JsContext::new(&mut document);
+        let value = context.eval("document.title = 'New ' + document.title; 1 + 1;").unwrap();
+        assert_eq!(value, JsValue::Number(2.0));
+        assert_eq!(
+            context.eval("document.URL").unwrap(),
+            JsValue::String("https://example.com/".into())
+        );
+        assert!(matches!(context.eval("document.body = 1"), Err(BrowserError::Script(_))));
+        assert_eq!(document.title, "New Old");
+
+        assert!(matches!(eval_expression("document.title"), Err(BrowserError::Script(_))));
+    }
+
+    #[test]
+    fn test_deep_nesting_is_an_error() {
+        let nested = format!("{}1{}", "(".repeat(200_000), ")".repeat(200_000));
+        let too_deep = |result: BrowserResult<JsValue>| matches!(result, Err(BrowserError::Script(message)) if message == "expression nested too deeply");
+        assert!(too_deep(eval_expression(&nested)));
+        assert!(too_deep(eval_expression(&format!("{}x", "!".repeat(200_000)))));
+        assert_eq!(
+            eval_expression(&format!("{}1{}", "(".repeat(50), ")".repeat(50))).unwrap(),
+            JsValue::Number(1.0)
+        );
+    }
+}
@@ -88,24 +88,57 @@
 #![allow(clippy::manual_string_new)]
 #![allow(clippy::self_only_used_in_recursion)]
 
+mod accessibility;
+mod cache;
 mod config;
 mod consciousness;
+mod css;
+mod diff;
+mod dom;
 mod errors;
+mod fetch;
 mod flexforge;
+mod image;
+mod js;
+mod observer;
+mod pages;
 mod parser;
 mod plugin;
+mod policy;
 mod renderer;
+mod selector;
+mod serialize;
+mod session;
+mod style;
 mod types;
+mod url;
 
 pub use config::BrowserConfig;
 pub use consciousness::ConsciousnessLayer;
+pub use css::CssParser;
+pub use dom::DEFAULT_WORDS_PER_MINUTE;
 pub use errors::{BrowserError, BrowserResult};
+#[cfg(feature = "async")]
+pub use fetch::{AsyncContentFetcher, FetchFuture};
+pub use fetch::{BlankFetcher, ContentFetcher, FetchRequest, FetchResponse, ResourceKind};
 pub use flexforge::BrowserFlexForgeIntegration;
+pub use image::ImageProvider;
+pub use js::{JsContext, eval_expression};
+pub use observer::{NavigationObserver, PluginObserver};
 pub use parser::HtmlParser;
 pub use plugin::BrowserPlugin;
+pub use policy::{PermissionStore, ScriptPolicy};
 pub use renderer::RenderEngine;
+pub use selector::{Selector, matches_selector, specificity};
+pub use session::{BrowserSession, SessionTab};
+pub use style::{Cascade, StyleOrigin};
 pub use types::{
-    BrowserTab, Document, Element, NavigationState, PageMetrics, RenderTree, StyleSheet,
+    AxNode, AxRole, BorderStyle, BoxEdges, BrowserTab, DisplayItem, Document, DomPatch, Element,
+    FindMatch, FindState, FingerprintProfile, FlexDirection, FontStyle, HistoryEntry, JsValue,
+    MixedContentPolicy, NavigationState, PageMetrics, ParseStats, Permission, PluginEvent,
+    PrivacyMode, ReadingStats, ReferrerPolicy, RenderTiming, RenderTree, ResourceHint,
+    ResourceHintKind, RobotsDirectives, StyleSheet, TextAlign, TextPosition, ViewportMeta,
+    ViewportWidth, Visibility, WhiteSpace,
 };
 
 #[cfg(test)]
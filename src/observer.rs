@@ -0,0 +1,40 @@
+//! Navigation lifecycle hooks.
+
+use crate::{
+    errors::BrowserError,
+    fetch::FetchResponse,
+    types::{Document, PluginEvent},
+};
+
+/// Receives callbacks as a navigation progresses.
+///
+/// All methods default to no-ops so observers implement only what they need.
+/// A successful navigation reports, in order: start, content received (unless
+/// served from cache), parse complete, render complete. A failed one reports
+/// start and then an error.
+pub trait NavigationObserver: Send + Sync {
+    /// Navigation to `url` has begun.
+    fn on_navigation_start(&self, _url: &str) {}
+
+    /// A response for `url` arrived from the fetcher.
+    fn on_content_received(&self, _url: &str, _response: &FetchResponse) {}
+
+    /// The document for `url` has been parsed.
+    fn on_parse_complete(&self, _url: &str, _document: &Document) {}
+
+    /// The page at `url` has been styled and laid out.
+    fn on_render_complete(&self, _url: &str) {}
+
+    /// Navigation to `url` failed.
+    fn on_error(&self, _url: &str, _error: &BrowserError) {}
+}
+
+/// Receives tab, title and loading events from a
+/// [`BrowserPlugin`](crate::BrowserPlugin).
+///
+/// A navigation reports `LoadStarted`, then `TitleChanged` if the new page
+/// has a different title, then `LoadFinished` or `Error`.
+pub trait PluginObserver: Send + Sync {
+    /// Handle an event.
+    fn on_event(&self, event: &PluginEvent);
+}
@@ -0,0 +1,108 @@
+//! Documents generated by the browser itself.
+
+use crate::{
+    errors::BrowserError,
+    types::{CssRule, Document, Element, StyleSheet},
+};
+
+/// Stylesheet applied to reader mode pages. Colors come from the theme
+/// stylesheet.
+const READER_STYLESHEET: &str = "body { max-width: 680px; margin: 0 auto; padding: 24px; \
+                                 font-size: 18px; line-height: 1.6 } \
+                                 h1 { font-size: 32px } \
+                                 img { max-width: 100% }";
+
+/// Built-in stylesheet giving generated pages a light or dark theme.
+pub(crate) fn theme_stylesheet(dark: bool) -> StyleSheet {
+    let (background, text, link) = if dark {
+        ("#1e1e1e", "#e6e6e6", "#8ab4f8")
+    } else {
+        ("#ffffff", "#1f1f1f", "#0b57d0")
+    };
+    let rule = |selector: &str, declarations: &[(&str, &str)]| CssRule {
+        selector:     selector.to_string(),
+        declarations: declarations
+            .iter()
+            .map(|(property, value)| (property.to_string(), value.to_string()))
+            .collect(),
+    };
+    StyleSheet {
+        rules: vec![
+            rule("html, body", &[("background-color", background), ("color", text)]),
+            rule("a[href]", &[("color", link)]),
+        ],
+    }
+}
+
+/// Build the page shown in a tab whose navigation to `url` failed.
+pub(crate) fn error_document(url: &str, error: &BrowserError) -> Document {
+    let heading = match error {
+        BrowserError::Blocked(_) => "This page has been blocked",
+        BrowserError::Network(_) => "This site can't be reached",
+        BrowserError::Parse(_) => "This page couldn't be displayed",
+        _ => "Something went wrong",
+    };
+    let head = Element::new("head").with_child(Element::new("title").with_text(heading));
+    let body = Element::new("body")
+        .with_child(Element::new("h1").with_text(heading))
+        .with_child(Element::new("p").with_attribute("class", "url").with_text(url))
+        .with_child(
+            Element::new("p").with_attribute("class", "reason").with_text(error.to_string()),
+        );
+    Document {
+        title: heading.to_string(),
+        root:  Element::new("html").with_child(head).with_child(body),
+        url:   url.to_string(),
+    }
+}
+
+/// Whether a URL names a page generated by the browser itself.
+pub(crate) fn is_internal_url(url: &str) -> bool {
+    url.get(..6).is_some_and(|scheme| scheme.eq_ignore_ascii_case("about:"))
+}
+
+/// Build the reader mode view of a document.
+///
+/// The extracted article is placed under a heading with the page title and
+/// wrapped in a minimal readable stylesheet. Returns `None` if no article
+/// content could be found.
+pub(crate) fn reader_document(document: &Document) -> Option<Document> {
+    let article = document.extract_article()?;
+
+    let head = Element::new("head")
+        .with_child(Element::new("title").with_text(document.title.clone()))
+        .with_child(Element::new("style").with_text(READER_STYLESHEET));
+    let body = Element::new("body")
+        .with_child(Element::new("h1").with_text(document.title.clone()))
+        .with_child(article);
+
+    Some(Document {
+        title: document.title.clone(),
+        root:  Element::new("html").with_child(head).with_child(body),
+        url:   document.url.clone(),
+    })
+}
+
+/// Wrap plain text in a document that shows it verbatim in a `<pre>`.
+pub(crate) fn text_document(url: &str, text: &str) -> Document {
+    let body = Element::new("body").with_child(Element::new("pre").with_text(text));
+    Document {
+        title: url.to_string(),
+        root:  Element::new("html").with_child(body),
+        url:   url.to_string(),
+    }
+}
+
+/// Build a document showing a single image centered on the page.
+pub(crate) fn image_document(url: &str) -> Document {
+    let name = url.rsplit('/').find(|segment| !segment.is_empty()).unwrap_or(url);
+    let head = Element::new("head")
+        .with_child(Element::new("style").with_text("body { margin: 0; text-align: center }"));
+    let body = Element::new("body")
+        .with_child(Element::new("img").with_attribute("src", url).with_attribute("alt", name));
+    Document {
+        title: name.to_string(),
+        root:  Element::new("html").with_child(head).with_child(body),
+        url:   url.to_string(),
+    }
+}
@@ -2,48 +2,257 @@
 
 use crate::{
     errors::{BrowserError, BrowserResult},
-    types::{Document, Element},
+    types::{Document, Element, ParseStats},
 };
 
+/// Default limit on element nesting depth.
+pub(crate) const DEFAULT_MAX_PARSE_DEPTH: usize = 512;
+
+/// Elements that never have content and need no end tag.
+pub(crate) const VOID_TAGS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Elements whose content is raw text, up to their end tag.
+pub(crate) const RAW_TEXT_TAGS: &[&str] = &["script", "style"];
+
+/// Longest character reference name looked for after `&`, in bytes.
+const MAX_REFERENCE_LENGTH: usize = 32;
+
 /// HTML parser.
-pub struct HtmlParser;
+///
+/// Parsing uses an explicit stack of open elements, so nesting depth is
+/// bounded by [`with_max_depth`](Self::with_max_depth) rather than by the
+/// call stack.
+#[derive(Debug, Clone)]
+pub struct HtmlParser {
+    max_depth:     usize,
+    keep_comments: bool,
+}
+
+impl Default for HtmlParser {
+    fn default() -> Self {
+        Self { max_depth: DEFAULT_MAX_PARSE_DEPTH, keep_comments: false }
+    }
+}
 
 impl HtmlParser {
-    /// Parse HTML string into a document.
+    /// Create a parser with default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit how deeply elements may nest before parsing fails.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Keep comments in the tree as `#comment` elements holding the comment
+    /// body as text, instead of skipping them. Comments outside a single root
+    /// element are still dropped.
+    pub fn with_comments(mut self, keep_comments: bool) -> Self {
+        self.keep_comments = keep_comments;
+        self
+    }
+
+    /// Parse HTML string into a document with default options.
     pub fn parse(html: &str, url: &str) -> BrowserResult<Document> {
+        Self::default().parse_document(html, url)
+    }
+
+    /// Parse HTML string into a document.
+    pub fn parse_document(&self, html: &str, url: &str) -> BrowserResult<Document> {
+        self.parse_with_stats(html, url).map(|(document, _)| document)
+    }
+
+    /// Parse HTML string into a document, also returning statistics about
+    /// the markup.
+    pub fn parse_with_stats(&self, html: &str, url: &str) -> BrowserResult<(Document, ParseStats)> {
         if html.is_empty() {
             return Err(BrowserError::Parse("Empty HTML".into()));
         }
 
-        let root = Self::parse_element(html)?;
-        let title = Self::extract_title(&root);
+        let tree = self.build_tree(html);
+        if tree.depth_exceeded {
+            return Err(BrowserError::Parse("max depth exceeded".into()));
+        }
+        Ok((Self::document(tree.root, url), tree.stats))
+    }
 
-        Ok(Document { title, root, url: url.to_string() })
+    /// Parse untrusted HTML, never failing.
+    ///
+    /// Malformed markup is recovered from and described in the returned
+    /// diagnostics. Elements nested beyond the depth limit are dropped.
+    pub fn parse_with_diagnostics(html: &str, url: &str) -> (Document, Vec<String>) {
+        let mut tree = Self::default().build_tree(html);
+        if html.trim().is_empty() {
+            tree.diagnostics.push("empty document".into());
+        }
+        (Self::document(tree.root, url), tree.diagnostics)
     }
 
-    /// Parse a single element (simplified).
-    fn parse_element(html: &str) -> BrowserResult<Element> {
+    fn document(root: Element, url: &str) -> Document {
+        let title = Self::extract_title(&root);
+        Document { title, root, url: url.to_string() }
+    }
+
+    /// Build the element tree. Unclosed elements are closed at the end of
+    /// input; end tags without a matching open element are ignored.
+    fn build_tree(&self, html: &str) -> ParsedTree {
         // Simplified parser - production would use full HTML5 spec
-        let html = html.trim();
+        let mut open: Vec<Element> = Vec::new();
+        let mut roots: Vec<Element> = Vec::new();
+        let mut diagnostics = Vec::new();
+        // Start tags dropped for exceeding the depth limit, whose end tags
+        // must be ignored too.
+        let mut dropped = 0usize;
+        let mut depth_exceeded = false;
+        let mut stats = ParseStats::default();
+        let mut offset = 0;
+        // Start of the text since the last tag, which may include stray '<'.
+        let mut text_start = 0;
 
-        if html.starts_with("<!DOCTYPE") || html.starts_with("<!doctype") {
-            // Skip doctype
-            if let Some(pos) = html.find('>') {
-                return Self::parse_element(&html[pos + 1..]);
+        while let Some(found) = html[offset..].find('<') {
+            let start = offset + found;
+            if html[offset..start].contains('>') {
+                diagnostics.push(format!("stray '>' before byte {start}"));
             }
-        }
 
-        // Find first tag
-        if let Some(start) = html.find('<')
-            && let Some(end) = html[start..].find('>')
-        {
-            let tag_content = &html[start + 1..start + end];
-            let tag_name = tag_content.split_whitespace().next().unwrap_or("div");
+            let next = html[start + 1..].chars().next();
+            if !next.is_some_and(|c| c.is_ascii_alphabetic() || matches!(c, '/' | '!' | '?')) {
+                diagnostics.push(format!("stray '<' at byte {start}"));
+                offset = start + 1;
+                continue;
+            }
+            if html[start..].starts_with("<!--") {
+                stats.text_bytes += html[text_start..start].trim().len();
+                append_text(&mut open, &html[text_start..start]);
+                stats.comment_count += 1;
+                let body_start = start + 4;
+                let (body, resume) = match html[body_start..].find("-->") {
+                    Some(len) => (&html[body_start..body_start + len], body_start + len + 3),
+                    None => {
+                        diagnostics.push(format!("unterminated comment at byte {start}"));
+                        (&html[body_start..], html.len())
+                    },
+                };
+                if self.keep_comments {
+                    open.push(Element::new("#comment").with_text(body));
+                    close_element(&mut open, &mut roots);
+                }
+                offset = resume;
+                text_start = offset;
+                continue;
+            }
+            let Some(end) = html[start..].find('>').map(|end| start + end) else {
+                diagnostics.push(format!("unterminated tag at byte {start}"));
+                stats.text_bytes += html[text_start..start].trim().len();
+                append_text(&mut open, &html[text_start..start]);
+                text_start = html.len();
+                offset = html.len();
+                break;
+            };
+            let tag_content = &html[start + 1..end];
+            stats.text_bytes += html[text_start..start].trim().len();
+            append_text(&mut open, &html[text_start..start]);
+            offset = end + 1;
+            text_start = offset;
 
-            return Ok(Element::new(tag_name));
+            if tag_content.starts_with('!') || tag_content.starts_with('?') {
+                // Doctype or processing instruction
+                continue;
+            }
+
+            if let Some(name) = tag_content.strip_prefix('/') {
+                let name = name.trim().to_ascii_lowercase();
+                if dropped > 0 {
+                    dropped -= 1;
+                } else if let Some(pos) = open.iter().rposition(|e| e.tag == name) {
+                    while open.len() > pos + 1 {
+                        let unclosed = open.last().map(|e| e.tag.clone()).unwrap_or_default();
+                        diagnostics.push(format!("<{unclosed}> closed by </{name}>"));
+                        close_element(&mut open, &mut roots);
+                    }
+                    close_element(&mut open, &mut roots);
+                } else {
+                    diagnostics.push(format!("unexpected end tag </{name}>"));
+                }
+                continue;
+            }
+
+            let name_end = tag_content
+                .find(|c: char| c.is_whitespace() || c == '/')
+                .unwrap_or(tag_content.len());
+            let tag_name = tag_content[..name_end].to_ascii_lowercase();
+            if has_unterminated_quote(tag_content) {
+                diagnostics.push(format!("unterminated attribute value in <{tag_name}>"));
+            }
+
+            let (attributes, self_closing) = parse_attributes(&tag_content[name_end..]);
+            let void = VOID_TAGS.contains(&tag_name.as_str());
+            if open.len() >= self.max_depth {
+                if !depth_exceeded {
+                    diagnostics.push("max depth exceeded".into());
+                }
+                depth_exceeded = true;
+                // Void and self-closing tags have no end tag to ignore.
+                if !void && !self_closing {
+                    dropped += 1;
+                    if RAW_TEXT_TAGS.contains(&tag_name.as_str()) {
+                        // The body of a dropped raw text element is not markup.
+                        offset = find_end_tag(&html[offset..], &tag_name)
+                            .map_or(html.len(), |len| offset + len);
+                        text_start = offset;
+                    }
+                }
+                continue;
+            }
+            open.push(Element { attributes, ..Element::new(tag_name) });
+            stats.element_count += 1;
+            stats.max_depth = stats.max_depth.max(open.len());
+            if void || self_closing {
+                close_element(&mut open, &mut roots);
+            } else if let Some(element) = open.last_mut()
+                && RAW_TEXT_TAGS.contains(&element.tag.as_str())
+            {
+                let close = find_end_tag(&html[offset..], &element.tag)
+                    .map_or(html.len(), |len| offset + len);
+                let body = &html[offset..close];
+                if !body.is_empty() {
+                    element.text_content = Some(body.to_string());
+                }
+                stats.text_bytes += body.trim().len();
+                offset = close;
+                text_start = close;
+            }
         }
 
-        Ok(Element::new("div"))
+        stats.text_bytes += html[text_start..].trim().len();
+        append_text(&mut open, &html[text_start..]);
+        if html[offset..].contains('>') {
+            diagnostics.push(format!("stray '>' after byte {offset}"));
+        }
+        while let Some(unclosed) = open.last() {
+            if !matches!(unclosed.tag.as_str(), "html" | "body" | "head") {
+                diagnostics.push(format!("unclosed <{}> at end of input", unclosed.tag));
+            }
+            close_element(&mut open, &mut roots);
+        }
+
+        // Comments around the root element (`<!-- saved from ... --><html>`)
+        // have no parent to keep them in and must not turn it into a fragment.
+        if roots.iter().filter(|root| root.tag != "#comment").count() == 1 {
+            roots.retain(|root| root.tag != "#comment");
+        }
+        let root = match roots.len() {
+            0 => Element::new("div"),
+            1 => roots.remove(0),
+            _ => roots.into_iter().fold(Element::new("html"), Element::with_child),
+        };
+        stats.recovered_errors = diagnostics.len();
+        ParsedTree { root, diagnostics, depth_exceeded, stats }
     }
 
     /// Extract title from document.
@@ -63,3 +272,453 @@ impl HtmlParser {
         String::from("Untitled")
     }
 }
+
+/// Result of building an element tree.
+struct ParsedTree {
+    root:           Element,
+    diagnostics:    Vec<String>,
+    depth_exceeded: bool,
+    stats:          ParseStats,
+}
+
+/// Split the part of a start tag after its name into attribute pairs, and
+/// tell whether the tag is self-closing (ends in `/`).
+///
+/// Values may be double-quoted, single-quoted or unquoted; attributes
+/// without a value get an empty one. Names are lowercased, and only the
+/// first occurrence of a name is kept.
+fn parse_attributes(source: &str) -> (Vec<(String, String)>, bool) {
+    let mut attributes: Vec<(String, String)> = Vec::new();
+    let mut rest = source;
+    loop {
+        let trimmed = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        let Some(first) = trimmed.chars().next() else {
+            return (attributes, rest.trim_end().ends_with('/'));
+        };
+        rest = trimmed;
+        // A leading '=' belongs to the name rather than starting a value.
+        let name_end = rest[first.len_utf8()..]
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+            .map_or(rest.len(), |end| end + first.len_utf8());
+        let name = rest[..name_end].to_ascii_lowercase();
+        rest = rest[name_end..].trim_start();
+
+        let mut value = String::new();
+        if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            match after.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let body = &after[1..];
+                    let end = body.find(quote).unwrap_or(body.len());
+                    value = decode_entities(&body[..end]);
+                    rest = body.get(end + 1..).unwrap_or_default();
+                },
+                _ => {
+                    let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                    value = decode_entities(&after[..end]);
+                    rest = &after[end..];
+                },
+            }
+        }
+        if !attributes.iter().any(|(existing, _)| *existing == name) {
+            attributes.push((name, value));
+        }
+    }
+}
+
+/// Replace character references: `&amp;`, `&lt;`, `&gt;`, `&quot;`,
+/// `&apos;` and decimal or hex numeric references. Unknown or invalid
+/// references, and names longer than [`MAX_REFERENCE_LENGTH`], are left as
+/// written.
+fn decode_entities(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let name_len = rest[1..]
+            .bytes()
+            .take(MAX_REFERENCE_LENGTH)
+            .take_while(|b| b.is_ascii_alphanumeric() || *b == b'#')
+            .count();
+        let semi = Some(1 + name_len).filter(|&semi| rest.as_bytes().get(semi) == Some(&b';'));
+        let decoded = semi.and_then(|semi| {
+            let c = match &rest[1..semi] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                reference => {
+                    let number = reference.strip_prefix('#')?;
+                    let code = match number.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => number.parse().ok()?,
+                    };
+                    char::from_u32(code).filter(|c| *c != '\0')?
+                },
+            };
+            Some((c, semi + 1))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            },
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            },
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Byte offset of the first `</tag` in `html` that ends the raw text element
+/// `tag`: matched case-insensitively and followed by `>`, `/` or whitespace.
+fn find_end_tag(html: &str, tag: &str) -> Option<usize> {
+    let bytes = html.as_bytes();
+    let mut from = 0;
+    while let Some(found) = html[from..].find("</") {
+        let start = from + found;
+        let name_end = start + 2 + tag.len();
+        if bytes
+            .get(start + 2..name_end)
+            .is_some_and(|name| name.eq_ignore_ascii_case(tag.as_bytes()))
+            && bytes
+                .get(name_end)
+                .is_some_and(|&b| b == b'>' || b == b'/' || b.is_ascii_whitespace())
+        {
+            return Some(start);
+        }
+        from = start + 2;
+    }
+    None
+}
+
+/// Check whether a tag's content ends inside a quoted attribute value.
+fn has_unterminated_quote(tag_content: &str) -> bool {
+    let mut quote = None;
+    for c in tag_content.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            None if c == '"' || c == '\'' => quote = Some(c),
+            _ => {},
+        }
+    }
+    quote.is_some()
+}
+
+/// Add a run of text to the innermost open element's text content.
+///
+/// Whitespace is collapsed to single spaces and trimmed, and runs are joined
+/// with a space, except inside `<pre>` and `<textarea>`, whose text is kept
+/// as written. Text outside any element is dropped.
+fn append_text(open: &mut [Element], text: &str) {
+    let Some(element) = open.last_mut() else {
+        return;
+    };
+    let preformatted = matches!(element.tag.as_str(), "pre" | "textarea");
+    let text = if preformatted {
+        decode_entities(text)
+    } else {
+        decode_entities(&text.split_whitespace().collect::<Vec<_>>().join(" "))
+    };
+    if text.is_empty() {
+        return;
+    }
+    match &mut element.text_content {
+        Some(existing) if !existing.is_empty() => {
+            if !preformatted
+                && !existing.ends_with(char::is_whitespace)
+                && !text.starts_with(char::is_whitespace)
+            {
+                existing.push(' ');
+            }
+            existing.push_str(&text);
+        },
+        content => *content = Some(text),
+    }
+}
+
+/// Pop the innermost open element and attach it to its parent, or to the
+/// top-level elements if it has none.
+fn close_element(open: &mut Vec<Element>, roots: &mut Vec<Element>) {
+    if let Some(element) = open.pop() {
+        match open.last_mut() {
+            Some(parent) => parent.children.push(element),
+            None => roots.push(element),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deep_nesting_is_rejected() {
+        let depth = 10_000;
+        let html = format!("{}{}", "<div>".repeat(depth), "</div>".repeat(depth));
+
+        let result = HtmlParser::parse(&html, "https://example.com");
+        assert!(matches!(result, Err(BrowserError::Parse(msg)) if msg == "max depth exceeded"));
+
+        let shallow = "<div>".repeat(100);
+        let document = HtmlParser::new()
+            .with_max_depth(100)
+            .parse_document(&shallow, "about:blank")
+            .unwrap();
+        let mut depth = 1;
+        let mut element = &document.root;
+        while let Some(child) = element.children.first() {
+            depth += 1;
+            element = child;
+        }
+        assert_eq!(depth, 100);
+        assert!(HtmlParser::new().with_max_depth(99).parse_document(&shallow, "").is_err());
+    }
+
+    #[test]
+    fn test_elements_dropped_at_depth_limit_keep_later_end_tags() {
+        let parser = HtmlParser::new().with_max_depth(3);
+        let tree = parser.build_tree("<div><div><div><br><img src=x/></div><p>after</p>");
+        assert!(tree.depth_exceeded);
+        assert_eq!(
+            HtmlParser::document(tree.root, "").to_html(),
+            "<div><div><div></div><p>after</p></div></div>"
+        );
+
+        let html = r#"<div><div><div><script>if (a<b) { s = "<p>"; }</script></div><p>after</p>"#;
+        let tree = parser.build_tree(html);
+        assert_eq!(
+            HtmlParser::document(tree.root, "").to_html(),
+            "<div><div><div></div><p>after</p></div></div>"
+        );
+    }
+
+    #[test]
+    fn test_malformed_input_yields_partial_tree_and_diagnostics() {
+        let (document, diagnostics) =
+            HtmlParser::parse_with_diagnostics("<html><body><p>1 < 2 > 0</p><div", "about:blank");
+        assert_eq!(document.root.tag, "html");
+        assert_eq!(document.root.children[0].children[0].tag, "p");
+        assert!(diagnostics.iter().any(|d| d.starts_with("stray '<'")));
+        assert!(diagnostics.iter().any(|d| d.starts_with("stray '>'")));
+        assert!(diagnostics.iter().any(|d| d.starts_with("unterminated tag")));
+
+        let (document, diagnostics) =
+            HtmlParser::parse_with_diagnostics("<a href=\"x>link</a></b>", "about:blank");
+        assert_eq!(document.root.tag, "a");
+        assert!(diagnostics.contains(&"unterminated attribute value in <a>".to_string()));
+        assert!(diagnostics.contains(&"unexpected end tag </b>".to_string()));
+
+        let (_, diagnostics) = HtmlParser::parse_with_diagnostics("<ul><li>one</ul>", "");
+        assert_eq!(diagnostics, ["<li> closed by </ul>"]);
+    }
+
+    #[test]
+    fn test_diagnostics_never_panic_on_garbage() {
+        let deep = "<i>".repeat(DEFAULT_MAX_PARSE_DEPTH + 10);
+        let inputs = [
+            "",
+            "<",
+            ">",
+            "<<>>",
+            "</",
+            "<!",
+            "<a b='",
+            "é<ü>ß</",
+            "<\u{0}>",
+            &deep,
+        ];
+        for input in inputs {
+            let (document, _) = HtmlParser::parse_with_diagnostics(input, "about:blank");
+            assert!(!document.root.tag.is_empty());
+        }
+        let (_, diagnostics) = HtmlParser::parse_with_diagnostics(&deep, "about:blank");
+        assert!(diagnostics.contains(&"max depth exceeded".to_string()));
+    }
+
+    #[test]
+    fn test_parse_stats_for_small_document() {
+        let html = "<!DOCTYPE html><html><!-- nav --><body>\n  <p>Hello</p>\n  \
+                    <ul><li>a</li><li>b</b></li></ul>\n</body></html>";
+        let (_, stats) = HtmlParser::new().parse_with_stats(html, "about:blank").unwrap();
+        assert_eq!(
+            stats,
+            ParseStats {
+                element_count:    6,
+                max_depth:        4,
+                text_bytes:       7,
+                comment_count:    1,
+                recovered_errors: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_nested_tree_structure() {
+        let html = "<!DOCTYPE html><html><body><p>hi</p><div><span>x</span></div></body></html>";
+        let (document, stats) = HtmlParser::new().parse_with_stats(html, "about:blank").unwrap();
+        let root = &document.root;
+        assert_eq!(root.tag, "html");
+        let body = &root.children[0];
+        assert_eq!(body.tag, "body");
+        let tags: Vec<_> = body.children.iter().map(|c| c.tag.as_str()).collect();
+        assert_eq!(tags, ["p", "div"]);
+        assert_eq!(body.children[1].children[0].tag, "span");
+        assert_eq!(stats.max_depth, 4);
+
+        // Unclosed elements are closed innermost first at end of input.
+        let document = HtmlParser::parse("<html><body><div><span>x", "about:blank").unwrap();
+        let div = &document.root.children[0].children[0];
+        assert_eq!((div.tag.as_str(), div.children[0].tag.as_str()), ("div", "span"));
+    }
+
+    #[test]
+    fn test_attributes_are_parsed() {
+        let document =
+            HtmlParser::parse("<a href=\"x\" class='y'  disabled>link</a>", "about:blank").unwrap();
+        let pairs = |attributes: &[(String, String)]| {
+            attributes.iter().map(|(n, v)| (n.clone(), v.clone())).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            pairs(&document.root.attributes),
+            [
+                ("href".to_string(), "x".to_string()),
+                ("class".to_string(), "y".to_string()),
+                ("disabled".to_string(), String::new()),
+            ]
+        );
+
+        let document = HtmlParser::parse(
+            "<img SRC = a.png alt=\"a 'b'\" src=other.png data-x=1/>",
+            "about:blank",
+        )
+        .unwrap();
+        let img = &document.root;
+        assert_eq!(img.attribute("src"), Some("a.png"));
+        assert_eq!(img.attribute("alt"), Some("a 'b'"));
+        assert_eq!(img.attribute("data-x"), Some("1/"));
+        assert_eq!(img.attributes.len(), 3);
+    }
+
+    #[test]
+    fn test_text_content_and_title() {
+        let html = "<html><head><title>My Page</title></head><body>\n  <p>Hello \n  world</p>\
+                    <div>before <b>bold</b> after</div><pre> a\n  b</pre></body></html>";
+        let document = HtmlParser::parse(html, "about:blank").unwrap();
+        assert_eq!(document.title, "My Page");
+
+        let body = &document.root.children[1];
+        assert_eq!(body.text_content, None);
+        assert_eq!(body.children[0].text_content.as_deref(), Some("Hello world"));
+        let div = &body.children[1];
+        assert_eq!(div.text_content.as_deref(), Some("before after"));
+        assert_eq!(div.children[0].text_content.as_deref(), Some("bold"));
+        assert_eq!(body.children[2].text_content.as_deref(), Some(" a\n  b"));
+    }
+
+    #[test]
+    fn test_preformatted_text_runs_join_without_separator() {
+        let document = HtmlParser::parse("<pre>a<b>x</b>b</pre>", "about:blank").unwrap();
+        assert_eq!(document.root.text_content.as_deref(), Some("ab"));
+        assert_eq!(document.root.children[0].text_content.as_deref(), Some("x"));
+
+        let document = HtmlParser::parse("<p>a<b>x</b>b</p>", "about:blank").unwrap();
+        assert_eq!(document.root.text_content.as_deref(), Some("a b"));
+    }
+
+    #[test]
+    fn test_void_and_self_closing_tags() {
+        let document = HtmlParser::parse("<p>a<br>b</p>", "about:blank").unwrap();
+        let p = &document.root;
+        assert_eq!(p.text_content.as_deref(), Some("a b"));
+        assert_eq!(p.children.len(), 1);
+        assert!(p.children[0].tag == "br" && p.children[0].children.is_empty());
+
+        let html = "<div><img src=\"logo.png\"/><span/>after<br/></div>";
+        let document = HtmlParser::parse(html, "about:blank").unwrap();
+        let tags: Vec<_> = document.root.children.iter().map(|c| c.tag.as_str()).collect();
+        assert_eq!(tags, ["img", "span", "br"]);
+        let img = &document.root.children[0];
+        assert_eq!(img.attribute("src"), Some("logo.png"));
+        assert!(img.children.is_empty());
+        assert_eq!(document.root.text_content.as_deref(), Some("after"));
+    }
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(decode_entities("Tom &amp; Jerry &#60;3 &#x2764;"), "Tom & Jerry <3 \u{2764}");
+        assert_eq!(decode_entities("&quot;a&quot; &#39;b&apos; &lt;&gt;"), "\"a\" 'b' <>");
+        assert_eq!(
+            decode_entities("&copy; &#xZZ; &#0; &amp fish & chips"),
+            "&copy; &#xZZ; &#0; &amp fish & chips"
+        );
+        // Unterminated references do not scan ahead for a distant ';'.
+        let ampersands = format!("{};", "&".repeat(200_000));
+        assert_eq!(decode_entities(&ampersands), ampersands);
+        let long = format!("&#{}65;", "0".repeat(MAX_REFERENCE_LENGTH));
+        assert_eq!(decode_entities(&long), long);
+
+        let document =
+            HtmlParser::parse("<a title=\"a &amp; b\">x &lt; y</a>", "about:blank").unwrap();
+        assert_eq!(document.root.attribute("title"), Some("a & b"));
+        assert_eq!(document.root.text_content.as_deref(), Some("x < y"));
+    }
+
+    #[test]
+    fn test_comments_are_skipped_or_kept() {
+        let html = "<div><p>one</p><!-- <p>not</p> a > b --><p>two</p></div>";
+        let document = HtmlParser::parse(html, "about:blank").unwrap();
+        let tags: Vec<_> = document.root.children.iter().map(|c| c.tag.as_str()).collect();
+        assert_eq!(tags, ["p", "p"]);
+        assert_eq!(document.root.children[1].text_content.as_deref(), Some("two"));
+
+        let document = HtmlParser::new().with_comments(true).parse_document(html, "").unwrap();
+        let comment = &document.root.children[1];
+        assert_eq!(comment.tag, "#comment");
+        assert_eq!(comment.text_content.as_deref(), Some(" <p>not</p> a > b "));
+        assert_eq!(document.to_html(), html);
+
+        let (document, diagnostics) =
+            HtmlParser::parse_with_diagnostics("<p>x</p><!-- never <b>closed", "");
+        assert_eq!(document.root.tag, "p");
+        assert_eq!(diagnostics, ["unterminated comment at byte 8"]);
+    }
+
+    #[test]
+    fn test_comments_outside_the_root_element_are_dropped() {
+        let html = "<!DOCTYPE html><!-- saved from url=(0014)about:internet -->\
+                    <html><body><!-- kept --><p>x</p></body></html><!-- trailer -->";
+        let document = HtmlParser::new().with_comments(true).parse_document(html, "").unwrap();
+        assert_eq!(document.root.tag, "html");
+        assert_eq!(document.root.children.len(), 1);
+        let body = &document.root.children[0];
+        let tags: Vec<_> = body.children.iter().map(|c| c.tag.as_str()).collect();
+        assert_eq!(tags, ["#comment", "p"]);
+
+        // Fragments with several roots keep their comments.
+        let fragment = "<p>a</p><!-- between --><p>b</p>";
+        let document = HtmlParser::new().with_comments(true).parse_document(fragment, "").unwrap();
+        assert_eq!(document.root.children.len(), 3);
+    }
+
+    #[test]
+    fn test_script_and_style_bodies_are_raw_text() {
+        let script = "if (a<b) { s = \"</div></scripts>\"; } // &amp;";
+        let style = "ul > li { color: red } a[href^='<'] {}";
+        let html = format!(
+            "<html><head><SCRIPT>{script}</script><style>{style}</STYLE></head><body><p>x</p></body></html>"
+        );
+        let (document, diagnostics) = HtmlParser::parse_with_diagnostics(&html, "about:blank");
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+        let head = &document.root.children[0];
+        assert_eq!(head.children[0].text_content.as_deref(), Some(script));
+        assert!(head.children[0].children.is_empty());
+        assert_eq!(head.children[1].text_content.as_deref(), Some(style));
+        assert_eq!(document.root.children[1].children[0].tag, "p");
+    }
+}
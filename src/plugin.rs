@@ -1,36 +1,192 @@
 //! Browser plugin implementation.
 
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
 use crate::{
+    cache::{CacheLookup, DocumentCache},
     config::BrowserConfig,
     consciousness::ConsciousnessLayer,
-    errors::BrowserResult,
+    css::CssParser,
+    dom::StyleSource,
+    errors::{BrowserError, BrowserResult},
+    fetch::{
+        self, BlankFetcher, ConnectionLimiter, ContentFetcher, FetchRequest, FetchResponse,
+        ResourceKind,
+    },
+    image::ImageProvider,
+    js::JsContext,
+    observer::{NavigationObserver, PluginObserver},
+    pages,
     parser::HtmlParser,
+    policy::{self, MixedContent, PermissionStore, ReferrerSource, ScriptPolicy},
     renderer::RenderEngine,
-    types::{BrowserTab, NavigationState},
+    style::{self, Cascade, StyleOrigin},
+    types::{
+        BrowserTab, DisplayItem, Document, Element, FindState, FingerprintProfile, HistoryEntry,
+        JsValue, MixedContentPolicy, NavigationState, PageMetrics, Permission, PluginEvent,
+        RenderNode, RenderTiming, RenderTree, StyleSheet, TextPosition,
+    },
+    url,
 };
 
+/// Distance below the viewport (px) within which lazy images are fetched.
+const LAZY_LOAD_MARGIN: f32 = 256.0;
+
+/// Outcome of checking the document cache before a navigation fetch.
+enum DocumentLookup {
+    /// A fresh cached copy that can be shown without fetching.
+    Cached(Document),
+    /// The request to send, with validators for a stale entry.
+    Fetch(FetchRequest),
+}
+
 /// Main browser plugin interface.
 pub struct BrowserPlugin {
-    config:        BrowserConfig,
-    renderer:      RenderEngine,
-    consciousness: ConsciousnessLayer,
-    tabs:          Vec<BrowserTab>,
-    active_tab:    usize,
-    next_tab_id:   u64,
+    config:                 BrowserConfig,
+    renderer:               RenderEngine,
+    consciousness:          ConsciousnessLayer,
+    fetcher:                Arc<dyn ContentFetcher>,
+    #[cfg(feature = "async")]
+    async_fetcher:          Option<Arc<dyn crate::fetch::AsyncContentFetcher>>,
+    connections:            ConnectionLimiter,
+    cache:                  DocumentCache,
+    permissions:            PermissionStore,
+    /// Number of new-window requests refused by the popup permission.
+    blocked_popups:         u64,
+    mixed_content_upgraded: u64,
+    mixed_content_blocked:  u64,
+    observers:              Vec<Arc<dyn NavigationObserver>>,
+    event_observers:        Vec<Arc<dyn PluginObserver>>,
+    tabs:                   Vec<BrowserTab>,
+    active_tab:             usize,
+    next_tab_id:            u64,
+    /// When each tab was last in the foreground.
+    last_active:            HashMap<u64, Instant>,
+    page_metrics:           PageMetrics,
 }
 
 impl BrowserPlugin {
     /// Create a new browser plugin.
     pub fn new(config: BrowserConfig) -> Self {
         let consciousness = ConsciousnessLayer::new(config.enable_consciousness);
+        let mut renderer = RenderEngine::default();
+        renderer.set_device_width(config.device_width);
+        let cache =
+            DocumentCache::new(config.cache_size_mb as usize * 1024 * 1024, config.cache_ttl);
+        let connections = ConnectionLimiter::new(config.max_connections);
+        let permissions = PermissionStore::from_config(&config);
 
         Self {
             config,
-            renderer: RenderEngine::default(),
+            renderer,
             consciousness,
+            fetcher: Arc::new(BlankFetcher),
+            #[cfg(feature = "async")]
+            async_fetcher: None,
+            connections,
+            cache,
+            permissions,
+            blocked_popups: 0,
+            mixed_content_upgraded: 0,
+            mixed_content_blocked: 0,
+            observers: Vec::new(),
+            event_observers: Vec::new(),
             tabs: Vec::new(),
             active_tab: 0,
             next_tab_id: 1,
+            last_active: HashMap::new(),
+            page_metrics: PageMetrics::default(),
+        }
+    }
+
+    /// Use the given fetcher for documents and subresources.
+    pub fn with_fetcher(mut self, fetcher: Arc<dyn ContentFetcher>) -> Self {
+        self.fetcher = fetcher;
+        self
+    }
+
+    /// Fetch documents for [`navigate_async`](Self::navigate_async) with the
+    /// given async fetcher. Subresources still use the synchronous fetcher.
+    #[cfg(feature = "async")]
+    pub fn with_async_fetcher(
+        mut self,
+        fetcher: Arc<dyn crate::fetch::AsyncContentFetcher>,
+    ) -> Self {
+        self.async_fetcher = Some(fetcher);
+        self
+    }
+
+    /// Size `<img>` boxes from the intrinsic dimensions reported by `provider`.
+    pub fn with_image_provider(mut self, provider: Arc<dyn ImageProvider>) -> Self {
+        self.renderer.set_image_provider(Some(provider));
+        self
+    }
+
+    /// Register an observer for navigation lifecycle events.
+    pub fn add_navigation_observer(&mut self, observer: Arc<dyn NavigationObserver>) {
+        self.observers.push(observer);
+    }
+
+    fn notify(&self, event: impl Fn(&dyn NavigationObserver)) {
+        for observer in &self.observers {
+            event(observer.as_ref());
+        }
+    }
+
+    /// Register an observer for tab, title and loading events.
+    pub fn add_event_observer(&mut self, observer: Arc<dyn PluginObserver>) {
+        self.event_observers.push(observer);
+    }
+
+    fn emit(&self, event: PluginEvent) {
+        for observer in &self.event_observers {
+            observer.on_event(&event);
+        }
+    }
+
+    fn active_tab_id(&self) -> u64 {
+        self.tabs.get(self.active_tab).map_or(0, |tab| tab.id)
+    }
+
+    /// Report the start of a load in the active tab.
+    fn start_load(&self, url: &str) {
+        self.notify(|o| o.on_navigation_start(url));
+        self.emit(PluginEvent::LoadStarted {
+            tab_id: self.active_tab_id(),
+            url:    url.to_string(),
+        });
+    }
+
+    /// Report the outcome of a load in the active tab.
+    fn finish_load(&self, url: &str, result: &BrowserResult<()>) {
+        let tab_id = self.active_tab_id();
+        match result {
+            Ok(()) => {
+                self.notify(|o| o.on_render_complete(url));
+                self.emit(PluginEvent::LoadFinished { tab_id, url: url.to_string() });
+            },
+            Err(e) => {
+                self.notify(|o| o.on_error(url, e));
+                self.emit(PluginEvent::Error {
+                    tab_id,
+                    url: url.to_string(),
+                    message: e.to_string(),
+                });
+            },
+        }
+    }
+
+    /// Set the active tab's title, reporting it if it changed.
+    fn set_active_title(&mut self, title: String) {
+        let tab = &mut self.tabs[self.active_tab];
+        if tab.title != title {
+            tab.title = title.clone();
+            let tab_id = tab.id;
+            self.emit(PluginEvent::TitleChanged { tab_id, title });
         }
     }
 
@@ -39,57 +195,1002 @@ impl BrowserPlugin {
         &self.config
     }
 
+    /// What the browser currently reveals about itself to servers and pages.
+    pub fn fingerprint_profile(&self) -> FingerprintProfile {
+        FingerprintProfile::new(&self.config, self.renderer.viewport_size(), self.renderer.zoom())
+    }
+
+    /// Per-origin permissions, kept across navigations.
+    pub fn permissions(&self) -> &PermissionStore {
+        &self.permissions
+    }
+
+    /// Per-origin permissions, for granting or denying capabilities.
+    pub fn permissions_mut(&mut self) -> &mut PermissionStore {
+        &mut self.permissions
+    }
+
     /// Open a new tab.
     pub fn new_tab(&mut self) -> u64 {
         let tab_id = self.next_tab_id;
         self.next_tab_id += 1;
 
         self.tabs.push(BrowserTab {
-            id:               tab_id,
-            url:              String::from("about:blank"),
-            title:            String::from("New Tab"),
-            navigation_state: NavigationState::Idle,
-            document:         None,
+            id:                 tab_id,
+            url:                self.config.new_tab_url.clone(),
+            title:              String::from("New Tab"),
+            navigation_state:   NavigationState::Idle,
+            document:           None,
+            reader_original:    None,
+            favicon:            None,
+            loaded_images:      HashSet::new(),
+            failed_images:      HashSet::new(),
+            linked_stylesheets: HashMap::new(),
+            blocked_urls:       HashSet::new(),
+            history:            Vec::new(),
+            history_index:      0,
+            hibernated:         false,
+            find:               None,
         });
 
+        self.save_scroll_position();
+        self.mark_active(Instant::now());
         self.active_tab = self.tabs.len() - 1;
+        self.mark_active(Instant::now());
+        self.emit(PluginEvent::TabCreated { tab_id });
         tab_id
     }
 
+    /// Handle a page's request to open `url` in a new window, such as a
+    /// `target="_blank"` link or `window.open`.
+    ///
+    /// The window opens as a new active tab if `origin` (the requesting
+    /// page) has the popup permission; otherwise the request is counted in
+    /// [`blocked_popups`](Self::blocked_popups) and `None` is returned.
+    /// Load failures show in the new tab like any other navigation.
+    pub fn request_new_window(&mut self, url: &str, origin: &str) -> Option<u64> {
+        if !self.permissions.query(origin, Permission::Popups) {
+            self.blocked_popups += 1;
+            return None;
+        }
+        let referrer = self.referrer_source();
+        let tab_id = self.new_tab();
+        let _ = self.navigate_from(url, Some(referrer));
+        Some(tab_id)
+    }
+
+    /// Number of new-window requests blocked so far.
+    pub fn blocked_popups(&self) -> u64 {
+        self.blocked_popups
+    }
+
+    /// Media in the active tab's document to start playing once it loads:
+    /// its [`autoplay_media`](Document::autoplay_media), less the unmuted
+    /// ones while `block_autoplay` is set.
+    pub fn autoplay_media(&self) -> Vec<&Element> {
+        let Some(document) = self.active_tab().and_then(|tab| tab.document.as_ref()) else {
+            return Vec::new();
+        };
+        let mut media = document.autoplay_media();
+        if self.config.block_autoplay {
+            media.retain(|element| element.attribute("muted").is_some());
+        }
+        media
+    }
+
+    /// Media in the active tab's document held back by autoplay blocking,
+    /// so the embedder can offer to play them.
+    pub fn autoplay_blocked_media(&self) -> Vec<&Element> {
+        match self.active_tab().and_then(|tab| tab.document.as_ref()) {
+            Some(document) if self.config.block_autoplay => document.unmuted_autoplay_media(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Number of mixed-content subresources upgraded to `https:` so far.
+    pub fn mixed_content_upgraded(&self) -> u64 {
+        self.mixed_content_upgraded
+    }
+
+    /// Number of mixed-content subresources blocked so far.
+    pub fn mixed_content_blocked(&self) -> u64 {
+        self.mixed_content_blocked
+    }
+
+    /// Performance metrics of the active page, including the stage timing of
+    /// its latest render.
+    pub fn page_metrics(&self) -> &PageMetrics {
+        &self.page_metrics
+    }
+
+    /// Abandon a tab's in-progress navigation, leaving the tab in the error
+    /// state. Returns `false` if the tab is not loading.
+    pub fn cancel_navigation(&mut self, tab_id: u64) -> bool {
+        let Some(tab) = self.tabs.iter_mut().find(|t| t.id == tab_id) else {
+            return false;
+        };
+        if !matches!(tab.navigation_state, NavigationState::Loading) {
+            return false;
+        }
+        let error = BrowserError::Navigation("Navigation cancelled".into());
+        tab.document = Some(pages::error_document(&tab.url, &error));
+        tab.favicon = None;
+        tab.navigation_state = NavigationState::Error;
+        true
+    }
+
     /// Close a tab.
     pub fn close_tab(&mut self, tab_id: u64) -> bool {
         if let Some(pos) = self.tabs.iter().position(|t| t.id == tab_id) {
             self.tabs.remove(pos);
+            self.last_active.remove(&tab_id);
             if self.active_tab >= self.tabs.len() && !self.tabs.is_empty() {
                 self.active_tab = self.tabs.len() - 1;
             }
+            self.emit(PluginEvent::TabClosed { tab_id });
             true
         } else {
             false
         }
     }
 
+    /// Bring a tab to the foreground, restoring its scroll position.
+    ///
+    /// A hibernated tab's document is reloaded first.
+    pub fn activate_tab(&mut self, tab_id: u64) -> BrowserResult<()> {
+        let index = self
+            .tabs
+            .iter()
+            .position(|t| t.id == tab_id)
+            .ok_or_else(|| BrowserError::Navigation(format!("No tab with id {tab_id}")))?;
+        let now = Instant::now();
+        self.save_scroll_position();
+        self.mark_active(now);
+        self.active_tab = index;
+        self.mark_active(now);
+
+        let tab = &mut self.tabs[index];
+        let scroll_y = tab.history.get(tab.history_index).map_or(0.0, |entry| entry.scroll_y);
+        if !tab.hibernated {
+            self.renderer.clear_selection();
+            return self.scroll_to(scroll_y);
+        }
+        tab.hibernated = false;
+        let url = tab.url.clone();
+        self.start_load(&url);
+        let result = self.navigate_active_tab(&url, None).and_then(|()| self.scroll_to(scroll_y));
+        self.finish_load(&url, &result);
+        result
+    }
+
+    /// Drop a background tab's document to save memory, keeping its URL,
+    /// title and history. The document is reloaded when the tab is
+    /// activated.
+    ///
+    /// Returns `false` for the active tab, unknown ids and tabs without a
+    /// document.
+    pub fn hibernate_tab(&mut self, tab_id: u64) -> bool {
+        let Some(index) = self.tabs.iter().position(|t| t.id == tab_id) else {
+            return false;
+        };
+        let tab = &mut self.tabs[index];
+        if index == self.active_tab || tab.document.is_none() {
+            return false;
+        }
+        tab.document = None;
+        tab.reader_original = None;
+        tab.loaded_images.clear();
+        tab.failed_images.clear();
+        tab.linked_stylesheets.clear();
+        tab.blocked_urls.clear();
+        tab.find = None;
+        tab.hibernated = true;
+        true
+    }
+
+    /// Whether a tab is hibernated.
+    pub fn is_hibernated(&self, tab_id: u64) -> bool {
+        self.tabs.iter().any(|t| t.id == tab_id && t.hibernated)
+    }
+
+    /// Approximate memory held by the tabs' documents (bytes); hibernated
+    /// tabs hold none.
+    pub fn memory_usage(&self) -> usize {
+        self.tabs.iter().map(tab_memory_usage).sum()
+    }
+
+    /// Record the active tab as in the foreground at `now`.
+    fn mark_active(&mut self, now: Instant) {
+        if let Some(tab) = self.tabs.get(self.active_tab) {
+            self.last_active.insert(tab.id, now);
+        }
+    }
+
+    /// Hibernate background tabs idle for longer than `hibernate_after`,
+    /// then the least recently used ones while the documents exceed
+    /// `max_memory`.
+    fn hibernate_idle_tabs(&mut self, now: Instant) {
+        let active_id = self.active_tab_id();
+        let mut background: Vec<_> = self
+            .tabs
+            .iter()
+            .filter(|tab| tab.id != active_id && tab.document.is_some())
+            .map(|tab| {
+                let last_active = self.last_active.get(&tab.id).copied().unwrap_or(now);
+                (last_active, tab.id, tab_memory_usage(tab))
+            })
+            .collect();
+        background.sort();
+
+        let mut usage = self.memory_usage();
+        for (last_active, tab_id, size) in background {
+            let idle = self
+                .config
+                .hibernate_after
+                .is_some_and(|after| now.saturating_duration_since(last_active) >= after);
+            if (idle || usage > self.config.max_memory) && self.hibernate_tab(tab_id) {
+                usage -= size;
+            }
+        }
+    }
+
     /// Navigate to URL.
+    ///
+    /// If the URL has a `#fragment`, the page is scrolled so the element with
+    /// that id is at the top. Navigating to a fragment of the current page
+    /// only scrolls, without reloading.
+    ///
+    /// Input without a scheme that contains spaces is treated as a search
+    /// query and sent to the configured `search_template`.
+    ///
+    /// The URL is treated as typed by the user, so no `Referer` is sent;
+    /// use [`follow_link`](Self::follow_link) for links on the page.
     pub fn navigate(&mut self, url: &str) -> BrowserResult<()> {
+        self.navigate_from(url, None)
+    }
+
+    /// Navigate the active tab to a link followed from its page, sending the
+    /// page as the `Referer` as far as its referrer policy allows.
+    pub fn follow_link(&mut self, url: &str) -> BrowserResult<()> {
+        let referrer = self.referrer_source();
+        self.navigate_from(url, Some(referrer))
+    }
+
+    /// [`navigate`](Self::navigate) with the page the navigation was
+    /// started from, if any.
+    fn navigate_from(&mut self, url: &str, referrer: Option<ReferrerSource>) -> BrowserResult<()> {
+        if self.tabs.is_empty() {
+            self.new_tab();
+        }
+
+        let search = url::search_url(url, &self.config.search_template);
+        let url = search.as_deref().unwrap_or(url);
+        self.push_history(url);
+        self.start_load(url);
+        let result = self.navigate_active_tab(url, referrer.as_ref());
+        self.finish_load(url, &result);
+        result
+    }
+
+    /// Go back one page in the active tab's history, restoring the scroll
+    /// position the page was left at.
+    pub fn go_back(&mut self) -> BrowserResult<()> {
+        self.traverse_history(-1)
+    }
+
+    /// Go forward one page in the active tab's history, restoring the scroll
+    /// position the page was left at.
+    pub fn go_forward(&mut self) -> BrowserResult<()> {
+        self.traverse_history(1)
+    }
+
+    /// Reload the active tab's page, keeping its history entry and scroll
+    /// position.
+    ///
+    /// A normal reload may be served from the document cache while the
+    /// entry is fresh; a `hard` reload drops the cached copy first so the
+    /// page is fetched again.
+    pub fn reload(&mut self, hard: bool) -> BrowserResult<()> {
+        let url = self
+            .active_tab()
+            .map(|tab| tab.url.clone())
+            .ok_or_else(|| BrowserError::Navigation("No active tab".into()))?;
+        let page_url = split_fragment(&url).0;
+        if hard {
+            self.cache.remove(page_url);
+        }
+        self.save_scroll_position();
+        let scroll_y = self.renderer.scroll_offset();
+        self.tabs[self.active_tab].navigation_state = NavigationState::Loading;
+
+        self.start_load(&url);
+        let loaded = self
+            .check_navigation_allowed(page_url)
+            .and_then(|()| self.load_document(page_url, None, Instant::now()));
+        let result = self.commit_navigation(&url, loaded).and_then(|()| self.scroll_to(scroll_y));
+        self.finish_load(&url, &result);
+        result
+    }
+
+    /// Whether the active tab has an earlier history entry to go back to.
+    pub fn can_go_back(&self) -> bool {
+        self.active_tab().is_some_and(|tab| tab.history_index > 0)
+    }
+
+    /// Whether the active tab has a later history entry to go forward to.
+    pub fn can_go_forward(&self) -> bool {
+        self.active_tab().is_some_and(|tab| tab.history_index + 1 < tab.history.len())
+    }
+
+    fn traverse_history(&mut self, delta: isize) -> BrowserResult<()> {
+        let tab = self
+            .tabs
+            .get(self.active_tab)
+            .ok_or_else(|| BrowserError::Navigation("No active tab".into()))?;
+        let index = tab
+            .history_index
+            .checked_add_signed(delta)
+            .filter(|index| *index < tab.history.len())
+            .ok_or_else(|| BrowserError::Navigation("No history entry".into()))?;
+        self.save_scroll_position();
+        let tab = &mut self.tabs[self.active_tab];
+        tab.history_index = index;
+        let HistoryEntry { url, scroll_y } = tab.history[index].clone();
+
+        self.start_load(&url);
+        let result = self.navigate_active_tab(&url, None).and_then(|()| self.scroll_to(scroll_y));
+        self.finish_load(&url, &result);
+        result
+    }
+
+    /// Remember the active page's scroll offset and add `url` as the newest
+    /// history entry, dropping any entries ahead of the current one.
+    /// Navigating to the current page again reloads it without a new entry.
+    fn push_history(&mut self, url: &str) {
+        self.save_scroll_position();
+        let tab = &mut self.tabs[self.active_tab];
+        if let Some(current) = tab.history.get_mut(tab.history_index)
+            && url::equivalent(&current.url, url)
+        {
+            *current = HistoryEntry { url: url.to_string(), scroll_y: 0.0 };
+            return;
+        }
+        if !tab.history.is_empty() {
+            tab.history.truncate(tab.history_index + 1);
+        }
+        tab.history.push(HistoryEntry { url: url.to_string(), scroll_y: 0.0 });
+        tab.history_index = tab.history.len() - 1;
+    }
+
+    fn save_scroll_position(&mut self) {
+        let scroll_y = self.renderer.scroll_offset();
+        if let Some(tab) = self.tabs.get_mut(self.active_tab)
+            && let Some(entry) = tab.history.get_mut(tab.history_index)
+        {
+            entry.scroll_y = scroll_y;
+        }
+    }
+
+    /// Navigate to URL without blocking on the document fetch.
+    ///
+    /// Behaves like [`navigate`](Self::navigate), but the document is
+    /// fetched through the fetcher given to
+    /// [`with_async_fetcher`](Self::with_async_fetcher), if any. Parsing,
+    /// layout and subresource loads still run synchronously.
+    #[cfg(feature = "async")]
+    pub async fn navigate_async(&mut self, url: &str) -> BrowserResult<()> {
         if self.tabs.is_empty() {
             self.new_tab();
         }
 
+        let search = url::search_url(url, &self.config.search_template);
+        let url = search.as_deref().unwrap_or(url);
+        self.push_history(url);
+        self.start_load(url);
+        let (page_url, fragment) = split_fragment(url);
+        let result = if self.begin_navigation(url) {
+            let loaded = match self.check_navigation_allowed(page_url) {
+                Ok(()) => self.load_document_async(page_url, None, Instant::now()).await,
+                Err(e) => Err(e),
+            };
+            self.commit_navigation(url, loaded)
+        } else {
+            Ok(())
+        }
+        .and_then(|()| self.scroll_to_fragment(fragment));
+        self.finish_load(url, &result);
+        result
+    }
+
+    fn navigate_active_tab(
+        &mut self,
+        url: &str,
+        referrer: Option<&ReferrerSource>,
+    ) -> BrowserResult<()> {
+        let (page_url, fragment) = split_fragment(url);
+        if self.begin_navigation(url) {
+            let loaded = self
+                .check_navigation_allowed(page_url)
+                .and_then(|()| self.load_document(page_url, referrer, Instant::now()));
+            self.commit_navigation(url, loaded)?;
+        }
+        self.scroll_to_fragment(fragment)
+    }
+
+    /// Point the active tab at `url`. Returns whether its document has to be
+    /// loaded, which is not the case for a fragment of the current page.
+    fn begin_navigation(&mut self, url: &str) -> bool {
+        let (page_url, fragment) = split_fragment(url);
         let tab = &mut self.tabs[self.active_tab];
+        let same_document =
+            fragment.is_some() && tab.document.is_some() && split_fragment(&tab.url).0 == page_url;
         tab.url = url.to_string();
-        tab.navigation_state = NavigationState::Loading;
+        if !same_document {
+            tab.navigation_state = NavigationState::Loading;
+        }
+        !same_document
+    }
 
-        // In production, would fetch URL content via essentia_net_plugin
-        // For now, create empty document
-        let html = "<!DOCTYPE html><html><body></body></html>";
-        let document = HtmlParser::parse(html, url)?;
+    /// Show a loaded document, or an error page for a failed load, in the
+    /// active tab.
+    fn commit_navigation(
+        &mut self,
+        url: &str,
+        loaded: BrowserResult<Document>,
+    ) -> BrowserResult<()> {
+        let (document, state, error) = match loaded {
+            Ok(document) => (document, NavigationState::Loaded, None),
+            Err(e) => (pages::error_document(url, &e), NavigationState::Error, Some(e)),
+        };
+        self.set_active_title(document.title.clone());
 
+        let tab = &mut self.tabs[self.active_tab];
+        tab.favicon = if error.is_none() {
+            document.favicon_url()
+        } else {
+            None
+        };
         tab.document = Some(document);
-        tab.navigation_state = NavigationState::Loaded;
+        tab.hibernated = false;
+        tab.find = None;
+        tab.reader_original = None;
+        tab.loaded_images.clear();
+        tab.failed_images.clear();
+        tab.linked_stylesheets.clear();
+        tab.blocked_urls.clear();
+        tab.navigation_state = state;
+        self.renderer.clear_selection();
+        self.hibernate_idle_tabs(Instant::now());
+        if let Some(e) = error {
+            // The error page starts at the top; a layout failure must not
+            // mask the navigation error.
+            let _ = self.scroll_to_fragment(None);
+            return Err(e);
+        }
+        self.load_stylesheets();
+        Ok(())
+    }
+
+    /// Refuse navigations to blocked tracker hosts and, in HTTPS-only mode,
+    /// to plain `http:` URLs.
+    fn check_navigation_allowed(&self, url: &str) -> BrowserResult<()> {
+        if let Some(host) = url::host(url)
+            && let Some(blocked) = self
+                .config
+                .blocked_hosts
+                .iter()
+                .find(|blocked| url::host_matches(&host, blocked))
+        {
+            return Err(BrowserError::Blocked(format!(
+                "{host} is on the tracker block list ({blocked})"
+            )));
+        }
+        if self.config.https_only && url.get(..5).is_some_and(|s| s.eq_ignore_ascii_case("http:")) {
+            return Err(BrowserError::Blocked(format!("HTTPS-only mode refused {url}")));
+        }
+        Ok(())
+    }
+
+    /// Load a document, serving it from the cache while fresh and
+    /// revalidating stale entries with conditional headers. `referrer` is
+    /// the page the navigation was started from, if any.
+    fn load_document(
+        &mut self,
+        url: &str,
+        referrer: Option<&ReferrerSource>,
+        now: Instant,
+    ) -> BrowserResult<Document> {
+        match self.lookup_document(url, referrer, now) {
+            DocumentLookup::Cached(document) => Ok(document),
+            DocumentLookup::Fetch(request) => {
+                let response = fetch::fetch_with_timeout(&self.fetcher, &request)?;
+                self.receive_document(url, response, now)
+            },
+        }
+    }
+
+    /// [`load_document`](Self::load_document) through the async fetcher,
+    /// falling back to the synchronous one.
+    #[cfg(feature = "async")]
+    async fn load_document_async(
+        &mut self,
+        url: &str,
+        referrer: Option<&ReferrerSource>,
+        now: Instant,
+    ) -> BrowserResult<Document> {
+        let Some(fetcher) = self.async_fetcher.clone() else {
+            return self.load_document(url, referrer, now);
+        };
+        match self.lookup_document(url, referrer, now) {
+            DocumentLookup::Cached(document) => Ok(document),
+            DocumentLookup::Fetch(request) => {
+                let response = fetcher.fetch(&request).await?;
+                self.receive_document(url, response, now)
+            },
+        }
+    }
+
+    /// Referrer for requests made from the active tab's document: its
+    /// subresources and the links followed from it.
+    fn referrer_source(&self) -> ReferrerSource {
+        let document = self.tabs.get(self.active_tab).and_then(|tab| tab.document.as_ref());
+        ReferrerSource::new(&self.config, document)
+    }
+
+    /// Check the cache for a document, building the request to send unless
+    /// a fresh copy is cached.
+    fn lookup_document(
+        &mut self,
+        url: &str,
+        referrer: Option<&ReferrerSource>,
+        now: Instant,
+    ) -> DocumentLookup {
+        let kind = ResourceKind::Document;
+        let mut request = fetch_request(&self.config, &self.permissions, referrer, url, kind);
+        match self.cache.lookup(url, now) {
+            CacheLookup::Fresh(document) => {
+                self.notify(|o| o.on_parse_complete(url, &document));
+                return DocumentLookup::Cached(document);
+            },
+            CacheLookup::Stale { etag, last_modified } => {
+                if let Some(etag) = etag {
+                    request = request.with_header("If-None-Match", etag);
+                }
+                if let Some(last_modified) = last_modified {
+                    request = request.with_header("If-Modified-Since", last_modified);
+                }
+            },
+            CacheLookup::Miss => {},
+        }
+        DocumentLookup::Fetch(request)
+    }
+
+    /// Parse and cache a fetched document, or reuse the cached copy on
+    /// `304 Not Modified`. A `304` without a cached copy to reuse is an
+    /// error.
+    fn receive_document(
+        &mut self,
+        url: &str,
+        response: FetchResponse,
+        now: Instant,
+    ) -> BrowserResult<Document> {
+        self.notify(|o| o.on_content_received(url, &response));
+        if response.status == 304 {
+            let Some(document) = self.cache.revalidate(url, &response, now) else {
+                return Err(BrowserError::Network(format!(
+                    "HTTP 304 for {url} with no cached copy"
+                )));
+            };
+            self.notify(|o| o.on_parse_complete(url, &document));
+            return Ok(document);
+        }
+        if response.status >= 400 {
+            return Err(BrowserError::Network(format!("HTTP {} for {url}", response.status)));
+        }
+        if response.body.len() > self.config.max_document_bytes {
+            return Err(BrowserError::Network("document too large".into()));
+        }
+
+        let document = HtmlParser::new()
+            .with_max_depth(self.config.max_parse_depth)
+            .parse_document(&response.text(), url)?;
+        self.notify(|o| o.on_parse_complete(url, &document));
+        self.cache.insert(url, document.clone(), &response, now);
+        Ok(document)
+    }
+
+    /// Navigate the active tab to the configured home page.
+    pub fn go_home(&mut self) -> BrowserResult<()> {
+        let home_url = self.config.home_url.clone();
+        self.navigate(&home_url)
+    }
+
+    /// Scroll the active tab so the element with the given id is at the top,
+    /// or as far as the content allows, or to the top of the page if there is
+    /// no such element.
+    fn scroll_to_fragment(&mut self, fragment: Option<&str>) -> BrowserResult<()> {
+        let Some(tree) = self.layout_active_document()? else {
+            self.renderer.set_scroll_offset(0.0);
+            return Ok(());
+        };
+
+        let target_y = fragment
+            .and_then(|id| tree.find_by_id(id).map(|node| node.layout.y))
+            .unwrap_or(0.0);
+        self.renderer
+            .set_scroll_offset(target_y.min(self.renderer.max_scroll_offset(&tree)));
+        self.load_images(&tree);
+        Ok(())
+    }
+
+    /// Scroll the active tab to a vertical offset (px), clamped to the end
+    /// of its content, fetching any lazy images that come into view.
+    pub fn scroll_to(&mut self, y: f32) -> BrowserResult<()> {
+        match self.layout_active_document()? {
+            Some(tree) => {
+                self.renderer.set_scroll_offset(y.min(self.renderer.max_scroll_offset(&tree)));
+                self.load_images(&tree);
+            },
+            None => self.renderer.set_scroll_offset(y),
+        }
+        Ok(())
+    }
+
+    /// Fetch the laid-out document's images, preloaded images, background
+    /// images and favicon that have not been fetched yet and that the configuration allows, at
+    /// most `max_connections` at a time.
+    ///
+    /// Images with `loading="lazy"` are deferred until they are within
+    /// [`LAZY_LOAD_MARGIN`] of the visible viewport. `http:` images of an
+    /// `https:` page are upgraded or blocked by the mixed-content policy.
+    ///
+    /// Images that fail to load are reported as
+    /// [`PluginEvent::ResourceFailed`] once and not retried until the next
+    /// navigation or reload; they never fail the navigation.
+    fn load_images(&mut self, tree: &RenderTree) {
+        let top = self.renderer.scroll_offset();
+        let bottom = top + self.renderer.viewport_size().1 + LAZY_LOAD_MARGIN;
+        let referrer = self.referrer_source();
+        let Some(tab) = self.tabs.get_mut(self.active_tab) else {
+            return;
+        };
+        let base = tab.document.as_ref().map_or_else(|| tab.url.clone(), |d| d.base_url());
+        let page_url = tab.document.as_ref().map_or(&tab.url, |d| &d.url).clone();
+        let images = self.permissions.query(&tab.url, Permission::Images);
+        let mut pending = match &tab.document {
+            Some(document) if images => document.preloads("image"),
+            _ => Vec::new(),
+        };
+        collect_images(&tree.root, &self.config, images, &base, top, bottom, &mut pending);
+        let mut pending: Vec<_> =
+            pending.into_iter().map(|src| (src, ResourceKind::Image)).collect();
+        if self.config.load_favicons
+            && let Some(favicon) = &tab.favicon
+        {
+            pending.push((favicon.clone(), ResourceKind::Favicon));
+        }
+
+        let mut seen = HashSet::new();
+        pending.retain(|(src, _)| {
+            !tab.loaded_images.contains(src)
+                && !tab.failed_images.contains(src)
+                && !tab.blocked_urls.contains(src)
+                && seen.insert(src.clone())
+        });
+        let policy = MixedContentPolicy::for_config(&self.config);
+        let mut sources = Vec::new();
+        let mut requests = Vec::new();
+        for (src, kind) in pending {
+            let url = match policy.check(&page_url, &src) {
+                MixedContent::Allowed => src.clone(),
+                MixedContent::Upgraded(url) => {
+                    self.mixed_content_upgraded += 1;
+                    url
+                },
+                MixedContent::Blocked => {
+                    self.mixed_content_blocked += 1;
+                    tab.blocked_urls.insert(src);
+                    continue;
+                },
+            };
+            let referrer = Some(&referrer);
+            requests.push(fetch_request(&self.config, &self.permissions, referrer, &url, kind));
+            sources.push(src);
+        }
+
+        let results = self.connections.fetch_all(&self.fetcher, &requests);
+        let tab_id = tab.id;
+        let mut failures = Vec::new();
+        for (src, result) in sources.into_iter().zip(results) {
+            match result {
+                Ok(response) if response.status < 400 => {
+                    tab.loaded_images.insert(src);
+                },
+                Ok(response) => {
+                    let message = format!("HTTP {} for {src}", response.status);
+                    failures.push((src, BrowserError::Network(message)));
+                },
+                Err(e) => failures.push((src, e)),
+            }
+        }
+        tab.failed_images.extend(failures.iter().map(|(src, _)| src.clone()));
+        for (url, e) in failures {
+            self.emit(PluginEvent::ResourceFailed { tab_id, url, message: e.to_string() });
+        }
+    }
+
+    /// Fetch the active document's linked and preloaded stylesheets that
+    /// have not been fetched yet, at most `max_connections` at a time.
+    ///
+    /// Sheets that fail to load are left out of the cascade, as browsers do;
+    /// they never fail the navigation. `http:` sheets of an `https:` page are
+    /// upgraded or blocked by the mixed-content policy.
+    fn load_stylesheets(&mut self) {
+        if !self.config.enable_css {
+            return;
+        }
+        let referrer = self.referrer_source();
+        let Some(tab) = self.tabs.get_mut(self.active_tab) else {
+            return;
+        };
+        let Some(document) = &tab.document else {
+            return;
+        };
+        let mut seen = HashSet::new();
+        let pending: Vec<_> = document
+            .preloads("style")
+            .into_iter()
+            .chain(document.style_sources().into_iter().filter_map(|source| match source {
+                StyleSource::Linked(url) => Some(url),
+                StyleSource::Inline(_) => None,
+            }))
+            .filter(|url| {
+                !tab.linked_stylesheets.contains_key(url)
+                    && !tab.blocked_urls.contains(url)
+                    && seen.insert(url.clone())
+            })
+            .collect();
+        let policy = MixedContentPolicy::for_config(&self.config);
+        let page_url = document.url.clone();
+        let mut sources = Vec::new();
+        let mut requests = Vec::new();
+        for src in pending {
+            let url = match policy.check(&page_url, &src) {
+                MixedContent::Allowed => src.clone(),
+                MixedContent::Upgraded(url) => {
+                    self.mixed_content_upgraded += 1;
+                    url
+                },
+                MixedContent::Blocked => {
+                    self.mixed_content_blocked += 1;
+                    tab.blocked_urls.insert(src);
+                    continue;
+                },
+            };
+            let (kind, referrer) = (ResourceKind::Stylesheet, Some(&referrer));
+            requests.push(fetch_request(&self.config, &self.permissions, referrer, &url, kind));
+            sources.push(src);
+        }
+
+        let results = self.connections.fetch_all(&self.fetcher, &requests);
+        for (src, result) in sources.into_iter().zip(results) {
+            if let Ok(response) = result
+                && response.status < 400
+                && let Ok(sheet) = CssParser::parse(&response.text())
+            {
+                tab.linked_stylesheets.insert(src, sheet);
+            }
+        }
+    }
+
+    /// Author stylesheets of the active tab in document order: inline
+    /// `<style>` sheets and the linked sheets fetched so far.
+    fn author_stylesheets(&self) -> Vec<StyleSheet> {
+        let Some(tab) = self.active_tab() else {
+            return Vec::new();
+        };
+        let Some(document) = tab.document.as_ref().filter(|_| self.config.enable_css) else {
+            return Vec::new();
+        };
+        document
+            .style_sources()
+            .into_iter()
+            .filter_map(|source| match source {
+                StyleSource::Inline(css) => CssParser::parse(&css).ok(),
+                StyleSource::Linked(url) => tab.linked_stylesheets.get(&url).cloned(),
+            })
+            .collect()
+    }
+
+    /// Style and lay out the active tab's document.
+    fn layout_active_document(&mut self) -> BrowserResult<Option<RenderTree>> {
+        let Some(document) = self.active_tab().and_then(|tab| tab.document.as_ref()) else {
+            return Ok(None);
+        };
+        let (meta, base_url) = (document.viewport_meta(), document.base_url());
+        self.renderer.set_viewport_meta(meta);
+        self.renderer.set_base_url(Some(base_url));
+        let Some((tree, timing)) = self.render_active_document()? else {
+            return Ok(None);
+        };
+        self.page_metrics.render_timing = timing;
+        Ok(Some(tree))
+    }
+
+    /// Style, lay out and paint the active tab's document with the current
+    /// viewport, timing each stage.
+    fn render_active_document(&self) -> BrowserResult<Option<(RenderTree, RenderTiming)>> {
+        let Some(document) = self.active_tab().and_then(|tab| tab.document.as_ref()) else {
+            return Ok(None);
+        };
+
+        let theme = self.internal_page_theme();
+        let author_sheets = self.author_stylesheets();
+        let mut cascade = self.style_cascade(&author_sheets).with_base_url(document.base_url());
+        if let Some(theme) = &theme {
+            cascade = cascade.with_sheet(StyleOrigin::UserAgent, theme);
+        }
+        let (tree, _, timing) = self.renderer.render_timed(document, &cascade)?;
+        Ok(Some((tree, timing)))
+    }
+
+    /// Select text in the active page between two positions in its render
+    /// tree.
+    pub fn set_selection(&mut self, start: TextPosition, end: TextPosition) {
+        self.renderer.set_selection(start, end);
+    }
+
+    /// Start finding `query` in the active page, making the first match
+    /// current and scrolling it into view. Returns the number of matches.
+    pub fn find_start(&mut self, query: &str) -> BrowserResult<usize> {
+        let matches = match self.layout_active_document()? {
+            Some(tree) => self.renderer.find(&tree, query),
+            None => Vec::new(),
+        };
+        let count = matches.len();
+        let tab = self
+            .tabs
+            .get_mut(self.active_tab)
+            .ok_or_else(|| BrowserError::Navigation("No active tab".into()))?;
+        tab.find = Some(FindState { query: query.to_string(), matches, current: 0 });
+        self.reveal_current_match()?;
+        Ok(count)
+    }
+
+    /// Move to the next find match, wrapping after the last one, and scroll
+    /// it into view. Returns the new current index, or `None` without
+    /// matches.
+    pub fn find_next(&mut self) -> BrowserResult<Option<usize>> {
+        self.step_find(true)
+    }
+
+    /// Move to the previous find match, wrapping before the first one, and
+    /// scroll it into view. Returns the new current index, or `None` without
+    /// matches.
+    pub fn find_prev(&mut self) -> BrowserResult<Option<usize>> {
+        self.step_find(false)
+    }
+
+    /// End the active tab's find session.
+    pub fn find_stop(&mut self) {
+        if let Some(tab) = self.tabs.get_mut(self.active_tab) {
+            tab.find = None;
+        }
+    }
+
+    /// Highlight rects for the active tab's find matches, the current one
+    /// accented.
+    pub fn find_highlights(&self) -> Vec<DisplayItem> {
+        self.active_tab()
+            .and_then(|tab| tab.find.as_ref())
+            .map(|find| self.renderer.highlight(&find.matches))
+            .unwrap_or_default()
+    }
+
+    fn step_find(&mut self, forward: bool) -> BrowserResult<Option<usize>> {
+        let Some(find) = self.tabs.get_mut(self.active_tab).and_then(|tab| tab.find.as_mut())
+        else {
+            return Ok(None);
+        };
+        let len = find.matches.len();
+        if len == 0 {
+            return Ok(None);
+        }
+        find.matches[find.current].current = false;
+        find.current = if forward {
+            (find.current + 1) % len
+        } else {
+            (find.current + len - 1) % len
+        };
+        find.matches[find.current].current = true;
+        let current = find.current;
+        self.reveal_current_match()?;
+        Ok(Some(current))
+    }
+
+    /// Scroll the current find match to the top of the viewport unless it is
+    /// already fully visible.
+    fn reveal_current_match(&mut self) -> BrowserResult<()> {
+        let Some(layout) = self
+            .active_tab()
+            .and_then(|tab| tab.find.as_ref())
+            .and_then(|find| find.matches.get(find.current))
+            .map(|m| m.layout.clone())
+        else {
+            return Ok(());
+        };
+        let top = self.renderer.scroll_offset();
+        let bottom = top + self.renderer.viewport_size().1;
+        if layout.y < top || layout.y + layout.height > bottom {
+            self.scroll_to(layout.y)?;
+        }
+        Ok(())
+    }
+
+    /// Plain text of the current selection, for copying to the clipboard.
+    pub fn copy_selection(&self) -> Option<String> {
+        let (tree, _) = self.render_active_document().ok()??;
+        self.renderer.selected_text(&tree)
+    }
+
+    /// Theme stylesheet for the active tab if it shows a browser-generated
+    /// page (an `about:` page, error page or reader mode), following
+    /// `dark_mode`.
+    fn internal_page_theme(&self) -> Option<StyleSheet> {
+        let tab = self.active_tab()?;
+        (pages::is_internal_url(&tab.url)
+            || tab.reader_original.is_some()
+            || matches!(tab.navigation_state, NavigationState::Error))
+        .then(|| pages::theme_stylesheet(self.config.dark_mode))
+    }
 
+    /// Current vertical scroll offset of the viewport (px).
+    pub fn scroll_offset(&self) -> f32 {
+        self.renderer.scroll_offset()
+    }
+
+    /// Replace the active tab's document with a simplified reader view.
+    ///
+    /// The original document is kept so [`exit_reader_mode`](Self::exit_reader_mode)
+    /// can restore it. Entering reader mode twice is a no-op.
+    pub fn enter_reader_mode(&mut self) -> BrowserResult<()> {
+        let tab = self
+            .tabs
+            .get_mut(self.active_tab)
+            .ok_or_else(|| BrowserError::Navigation("No active tab".into()))?;
+        if tab.reader_original.is_some() {
+            return Ok(());
+        }
+
+        let document = tab
+            .document
+            .as_ref()
+            .ok_or_else(|| BrowserError::Navigation("No document".into()))?;
+        let reader = pages::reader_document(document)
+            .ok_or_else(|| BrowserError::Parse("No article content found".into()))?;
+
+        tab.reader_original = tab.document.replace(reader);
+        Ok(())
+    }
+
+    /// Restore the original document after reader mode.
+    pub fn exit_reader_mode(&mut self) -> BrowserResult<()> {
+        let tab = self
+            .tabs
+            .get_mut(self.active_tab)
+            .ok_or_else(|| BrowserError::Navigation("No active tab".into()))?;
+
+        if let Some(original) = tab.reader_original.take() {
+            tab.document = Some(original);
+        }
         Ok(())
     }
 
+    /// Check whether the active tab is showing its reader view.
+    pub fn is_reader_mode(&self) -> bool {
+        self.active_tab().is_some_and(|tab| tab.reader_original.is_some())
+    }
+
     /// Get active tab.
     pub fn active_tab(&self) -> Option<&BrowserTab> {
         self.tabs.get(self.active_tab)
@@ -105,12 +1206,132 @@ impl BrowserPlugin {
         self.renderer.resize(width, height);
     }
 
+    /// Build the style cascade for a page's author stylesheets, layering the
+    /// built-in user-agent sheet and the configured user stylesheet beneath
+    /// them.
+    pub fn style_cascade<'a>(&'a self, author_sheets: &'a [StyleSheet]) -> Cascade<'a> {
+        let mut cascade =
+            Cascade::new().with_sheet(StyleOrigin::UserAgent, style::user_agent_stylesheet());
+        if let Some(user) = &self.config.user_stylesheet {
+            cascade = cascade.with_sheet(StyleOrigin::User, user);
+        }
+        author_sheets
+            .iter()
+            .fold(cascade, |cascade, sheet| cascade.with_sheet(StyleOrigin::Author, sheet))
+    }
+
+    /// Script policy derived from the current configuration.
+    pub fn script_policy(&self) -> ScriptPolicy {
+        ScriptPolicy::from_config(&self.config)
+    }
+
+    /// Whether the active tab's document may run scripts: its origin's
+    /// permission if set, the [script policy](Self::script_policy) otherwise.
+    pub fn scripts_enabled(&self) -> bool {
+        self.active_tab()
+            .is_some_and(|tab| self.permissions.query(&tab.url, Permission::Scripts))
+    }
+
+    /// Run a script against the active tab's document, if
+    /// [scripts are enabled](Self::scripts_enabled) for it. Title changes
+    /// made by the script are reflected in the tab.
+    pub fn run_script(&mut self, src: &str) -> BrowserResult<JsValue> {
+        let tab = self
+            .tabs
+            .get_mut(self.active_tab)
+            .ok_or_else(|| BrowserError::Navigation("No active tab".into()))?;
+        if !self.permissions.query(&tab.url, Permission::Scripts) {
+            return Err(BrowserError::Script(format!("Scripts are disabled for {}", tab.url)));
+        }
+        let document = tab
+            .document
+            .as_mut()
+            .ok_or_else(|| BrowserError::Script("No document loaded".into()))?;
+        let value = JsContext::new(document).eval(src);
+        let title = document.title.clone();
+        self.set_active_title(title);
+        value
+    }
+
     /// Get consciousness coherence score.
     pub fn coherence_score(&self) -> f64 {
         self.consciousness.coherence_score()
     }
 }
 
+/// A request carrying the reported user agent, the referrer allowed by the
+/// referrer policy of the page it is made from (none without one), the
+/// configured timeout and the cookie permission of its origin.
+fn fetch_request(
+    config: &BrowserConfig,
+    permissions: &PermissionStore,
+    referrer: Option<&ReferrerSource>,
+    url: &str,
+    kind: ResourceKind,
+) -> FetchRequest {
+    let mut request =
+        FetchRequest::new(url, kind).with_header("User-Agent", policy::reported_user_agent(config));
+    if let Some(referrer) = referrer.and_then(|referrer| referrer.referrer(url)) {
+        request = request.with_header("Referer", referrer);
+    }
+    request.cookies = permissions.query(url, Permission::Cookies);
+    match config.request_timeout_ms {
+        0 => request,
+        ms => request.with_timeout(Duration::from_millis(ms)),
+    }
+}
+
+/// Collect resolved URLs of images and background images that `config`
+/// allows fetching for a viewport spanning `top..bottom`. `<img>` images are
+/// only collected when `images` is set.
+fn collect_images(
+    node: &RenderNode,
+    config: &BrowserConfig,
+    images: bool,
+    base: &str,
+    top: f32,
+    bottom: f32,
+    out: &mut Vec<String>,
+) {
+    let element = &node.element;
+    if config.load_background_images
+        && let Some(background) = &node.computed_style.background_image
+    {
+        out.push(background.clone());
+    }
+    if images
+        && element.tag.eq_ignore_ascii_case("img")
+        && let Some(src) = element.attribute("src")
+    {
+        let lazy = element.attribute("loading").is_some_and(|v| v.eq_ignore_ascii_case("lazy"));
+        let layout = &node.layout;
+        let near_viewport = layout.y <= bottom && layout.y + layout.height >= top;
+        if !lazy || near_viewport {
+            out.push(url::resolve(base, src));
+        }
+    }
+    for child in &node.children {
+        collect_images(child, config, images, base, top, bottom, out);
+    }
+}
+
+/// Approximate memory held by a tab's documents (bytes).
+fn tab_memory_usage(tab: &BrowserTab) -> usize {
+    tab.document
+        .iter()
+        .chain(&tab.reader_original)
+        .map(Document::estimated_size)
+        .sum()
+}
+
+/// Split a URL into the part before `#` and the fragment, if any.
+fn split_fragment(url: &str) -> (&str, Option<&str>) {
+    match url.split_once('#') {
+        Some((page, fragment)) => (page, Some(fragment)),
+        None => (url, None),
+    }
+}
+
 impl Default for BrowserPlugin {
     fn default() -> Self {
         Self::new(BrowserConfig::default())
@@ -148,4 +1369,1218 @@ mod tests {
         let plugin = BrowserPlugin::default();
         assert!(plugin.coherence_score() > 0.0);
     }
+
+    fn article_document() -> crate::types::Document {
+        use crate::types::Element;
+
+        let article = Element::new("div")
+            .with_child(Element::new("p").with_text("First paragraph of the story."))
+            .with_child(Element::new("p").with_text("Second paragraph of the story."));
+        let root = Element::new("html").with_child(
+            Element::new("body")
+                .with_child(Element::new("nav").with_child(Element::new("a").with_text("Home")))
+                .with_child(article),
+        );
+        crate::types::Document { title: "Story".into(), root, url: "https://example.com".into() }
+    }
+
+    #[test]
+    fn test_reader_mode_round_trip() {
+        let mut plugin = BrowserPlugin::default();
+        plugin.navigate("https://example.com").unwrap();
+        plugin.tabs[0].document = Some(article_document());
+
+        plugin.enter_reader_mode().unwrap();
+        assert!(plugin.is_reader_mode());
+        let reader = plugin.active_tab().and_then(|t| t.document.as_ref()).unwrap();
+        assert!(reader.root.inner_text().contains("Second paragraph"));
+        assert!(!reader.root.inner_text().contains("Home"));
+
+        plugin.exit_reader_mode().unwrap();
+        assert!(!plugin.is_reader_mode());
+        let restored = plugin.active_tab().and_then(|t| t.document.as_ref()).unwrap();
+        assert_eq!(restored.root.inner_text(), article_document().root.inner_text());
+    }
+
+    #[test]
+    fn test_reader_mode_requires_article() {
+        let mut plugin = BrowserPlugin::default();
+        assert!(plugin.enter_reader_mode().is_err());
+
+        plugin.navigate("https://example.com").unwrap();
+        assert!(plugin.enter_reader_mode().is_err());
+        assert!(!plugin.is_reader_mode());
+    }
+
+    #[test]
+    fn test_user_stylesheet_in_cascade() {
+        use crate::types::{CssRule, Element};
+
+        let user_rule = CssRule {
+            selector:     "p".into(),
+            declarations: vec![("width".into(), "480px".into())],
+        };
+        let config = BrowserConfig {
+            user_stylesheet: Some(StyleSheet { rules: vec![user_rule] }),
+            ..Default::default()
+        };
+        let plugin = BrowserPlugin::new(config);
+        let author = [StyleSheet {
+            rules: vec![CssRule {
+                selector:     "p".into(),
+                declarations: vec![("height".into(), "20px".into())],
+            }],
+        }];
+
+        let style = plugin.style_cascade(&author).computed_style(&Element::new("p"), None);
+        assert_eq!(style.width, Some(480.0));
+        assert_eq!(style.height, Some(20.0));
+    }
+
+    #[test]
+    fn test_fragment_navigation_scrolls_to_section() {
+        use crate::types::{Document, Element};
+
+        let mut plugin = BrowserPlugin::default();
+        plugin.navigate("https://example.com/page").unwrap();
+        let body = Element::new("body")
+            .with_child(Element::new("p"))
+            .with_child(Element::new("p"))
+            .with_child(Element::new("section").with_attribute("id", "section"));
+        let body = (0..50).fold(body, |body, _| body.with_child(Element::new("p").with_text("x")));
+        plugin.tabs[0].document = Some(Document {
+            title: "Page".into(),
+            root:  Element::new("html").with_child(body),
+            url:   "https://example.com/page".into(),
+        });
+
+        plugin.navigate("https://example.com/page#section").unwrap();
+
+        let tree = plugin.layout_active_document().unwrap().unwrap();
+        let section_y = tree.find_by_id("section").unwrap().layout.y;
+        assert!(section_y > 0.0);
+        assert_eq!(plugin.scroll_offset(), section_y);
+
+        plugin.navigate("https://example.com/page#missing").unwrap();
+        assert_eq!(plugin.scroll_offset(), 0.0);
+
+        // Scrolling stops at the end of the content.
+        plugin.scroll_to(1e6).unwrap();
+        assert_eq!(plugin.scroll_offset(), plugin.renderer.max_scroll_offset(&tree));
+        assert!(plugin.scroll_offset() > section_y);
+    }
+
+    #[test]
+    fn test_home_and_new_tab_urls() {
+        let config = BrowserConfig {
+            home_url: "https://essentia.dev".into(),
+            new_tab_url: "about:newtab".into(),
+            ..Default::default()
+        };
+        let mut plugin = BrowserPlugin::new(config);
+        plugin.new_tab();
+        assert_eq!(plugin.active_tab().unwrap().url, "about:newtab");
+
+        plugin.go_home().unwrap();
+        assert_eq!(plugin.active_tab().unwrap().url, "https://essentia.dev");
+
+        plugin.config.home_url = "https://example.com".into();
+        plugin.go_home().unwrap();
+        assert_eq!(plugin.active_tab().unwrap().url, "https://example.com");
+    }
+
+    #[test]
+    fn test_expired_cache_entry_is_revalidated() {
+        use std::time::Duration;
+
+        use crate::fetch::{FetchResponse, testing::MockFetcher};
+
+        let url = "https://example.com/";
+        let mut response = FetchResponse::ok("text/html", "<html></html>");
+        response.headers = vec![
+            ("Cache-Control".into(), "max-age=60".into()),
+            ("ETag".into(), "\"v1\"".into()),
+        ];
+        let fetcher = Arc::new(MockFetcher::default().with_response(url, response));
+        let mut plugin = BrowserPlugin::default().with_fetcher(fetcher.clone());
+
+        let start = Instant::now();
+        plugin.load_document(url, None, start).unwrap();
+        plugin.load_document(url, None, start + Duration::from_secs(30)).unwrap();
+        assert_eq!(fetcher.requests().len(), 1);
+
+        plugin.load_document(url, None, start + Duration::from_secs(61)).unwrap();
+        let requests = fetcher.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].header("If-None-Match"), Some("\"v1\""));
+
+        // The 304 renewed the entry.
+        plugin.load_document(url, None, start + Duration::from_secs(90)).unwrap();
+        assert_eq!(fetcher.requests().len(), 2);
+    }
+
+    #[test]
+    fn test_not_modified_without_cache_entry_is_an_error() {
+        use crate::fetch::{FetchResponse, testing::MockFetcher};
+
+        let url = "https://example.com/";
+        let response = FetchResponse { status: 304, ..FetchResponse::ok("text/html", "") };
+        let fetcher = Arc::new(MockFetcher::default().with_response(url, response));
+        let mut plugin = BrowserPlugin::default().with_fetcher(fetcher.clone());
+
+        let now = Instant::now();
+        let error = plugin.load_document(url, None, now).unwrap_err();
+        assert!(matches!(error, BrowserError::Network(m) if m.contains("304")));
+        // Nothing was cached, so the next load fetches again.
+        assert!(plugin.load_document(url, None, now).is_err());
+        assert_eq!(fetcher.requests().len(), 2);
+    }
+
+    #[test]
+    fn test_navigation_observer_callback_order() {
+        use std::sync::Mutex;
+
+        use crate::fetch::{FetchResponse, testing::MockFetcher};
+
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<String>>);
+
+        impl NavigationObserver for Recorder {
+            fn on_navigation_start(&self, url: &str) {
+                self.0.lock().unwrap().push(format!("start {url}"));
+            }
+
+            fn on_content_received(&self, _url: &str, response: &FetchResponse) {
+                self.0.lock().unwrap().push(format!("content {}", response.status));
+            }
+
+            fn on_parse_complete(&self, _url: &str, _document: &Document) {
+                self.0.lock().unwrap().push("parse".into());
+            }
+
+            fn on_render_complete(&self, _url: &str) {
+                self.0.lock().unwrap().push("render".into());
+            }
+
+            fn on_error(&self, _url: &str, error: &BrowserError) {
+                self.0.lock().unwrap().push(format!("error {error}"));
+            }
+        }
+
+        let broken = FetchResponse { status: 500, ..FetchResponse::ok("text/html", "") };
+        let fetcher = MockFetcher::default().with_response("https://down.test/", broken);
+        let mut plugin = BrowserPlugin::default().with_fetcher(Arc::new(fetcher));
+        let first = Arc::new(Recorder::default());
+        let second = Arc::new(Recorder::default());
+        plugin.add_navigation_observer(first.clone());
+        plugin.add_navigation_observer(second.clone());
+
+        plugin.navigate("https://example.com/").unwrap();
+        assert!(plugin.navigate("https://down.test/").is_err());
+
+        let events = first.0.lock().unwrap().clone();
+        assert_eq!(
+            events,
+            [
+                "start https://example.com/",
+                "content 200",
+                "parse",
+                "render",
+                "start https://down.test/",
+                "content 500",
+                "error Network error: HTTP 500 for https://down.test/",
+            ]
+        );
+        assert_eq!(*second.0.lock().unwrap(), events);
+    }
+
+    #[test]
+    fn test_lazy_image_fetched_when_scrolled_into_view() {
+        use crate::{fetch::testing::MockFetcher, types::Element};
+
+        let fetcher = Arc::new(MockFetcher::default());
+        let mut plugin = BrowserPlugin::default().with_fetcher(fetcher.clone());
+        plugin.resize(800.0, 100.0);
+        plugin.navigate("https://example.com").unwrap();
+
+        let mut body = Element::new("body")
+            .with_child(Element::new("img").with_attribute("src", "https://example.com/hero.png"));
+        for _ in 0..100 {
+            body = body.with_child(Element::new("p"));
+        }
+        body = body.with_child(
+            Element::new("img")
+                .with_attribute("src", "https://example.com/footer.png")
+                .with_attribute("loading", "lazy"),
+        );
+        plugin.tabs[0].document = Some(Document {
+            title: "Page".into(),
+            root:  Element::new("html").with_child(body),
+            url:   "https://example.com".into(),
+        });
+
+        plugin.scroll_to(0.0).unwrap();
+        assert_eq!(fetcher.requested(ResourceKind::Image), ["https://example.com/hero.png"]);
+
+        plugin.scroll_to(1500.0).unwrap();
+        plugin.scroll_to(0.0).unwrap();
+        assert_eq!(
+            fetcher.requested(ResourceKind::Image),
+            [
+                "https://example.com/hero.png",
+                "https://example.com/footer.png"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_image_fetches_respect_max_connections() {
+        use std::time::Duration;
+
+        use crate::{fetch::testing::MockFetcher, types::Element};
+
+        let fetcher = Arc::new(MockFetcher::default().with_delay(Duration::from_millis(5)));
+        let config = BrowserConfig { max_connections: 2, ..Default::default() };
+        let mut plugin = BrowserPlugin::new(config).with_fetcher(fetcher.clone());
+        plugin.navigate("https://example.com").unwrap();
+
+        let mut body = Element::new("body");
+        for i in 0..8 {
+            let src = format!("https://example.com/{}.png", i % 6);
+            body = body.with_child(Element::new("img").with_attribute("src", src));
+        }
+        plugin.tabs[0].document = Some(Document {
+            title: "Gallery".into(),
+            root:  Element::new("html").with_child(body),
+            url:   "https://example.com".into(),
+        });
+        plugin.scroll_to(0.0).unwrap();
+
+        assert_eq!(fetcher.requested(ResourceKind::Image).len(), 6);
+        // The six distinct images plus the favicon.
+        assert_eq!(plugin.tabs[0].loaded_images.len(), 7);
+        assert!(fetcher.peak_concurrency() <= 2);
+    }
+
+    #[test]
+    fn test_internal_pages_follow_dark_mode() {
+        use crate::types::Color;
+
+        let body_background = |plugin: &mut BrowserPlugin| {
+            let tree = plugin.layout_active_document().unwrap().unwrap();
+            let body = tree.root.children.iter().find(|n| n.element.tag == "body").unwrap();
+            (body.computed_style.background_color, body.computed_style.color)
+        };
+
+        let mut plugin =
+            BrowserPlugin::new(BrowserConfig { dark_mode: true, ..Default::default() });
+        plugin.navigate("about:blank").unwrap();
+        let (background, text) = body_background(&mut plugin);
+        assert_eq!(background, Color { r: 0x1e, g: 0x1e, b: 0x1e, a: 255 });
+        assert!(text.r > 200);
+
+        let mut light = BrowserPlugin::default();
+        light.navigate("about:blank").unwrap();
+        assert_eq!(body_background(&mut light).0, Color::WHITE);
+
+        // Ordinary pages keep the default transparent canvas.
+        plugin.navigate("https://example.com").unwrap();
+        assert_eq!(body_background(&mut plugin).0, Color::TRANSPARENT);
+    }
+
+    #[test]
+    fn test_blocked_navigation_shows_error_page() {
+        use crate::types::Color;
+
+        let config = BrowserConfig {
+            blocked_hosts: vec!["tracker.example".into()],
+            https_only: true,
+            dark_mode: true,
+            ..Default::default()
+        };
+        let mut plugin = BrowserPlugin::new(config);
+
+        let error = plugin.navigate("https://ads.tracker.example/pixel").unwrap_err();
+        assert!(matches!(error, BrowserError::Blocked(_)));
+        let tab = plugin.active_tab().unwrap();
+        assert!(matches!(tab.navigation_state, NavigationState::Error));
+        let text = tab.document.as_ref().unwrap().root.inner_text();
+        assert!(text.contains("https://ads.tracker.example/pixel"));
+        assert!(text.contains("tracker block list"));
+
+        // Error pages are themed like other internal pages.
+        let tree = plugin.layout_active_document().unwrap().unwrap();
+        let body = tree.root.children.iter().find(|n| n.element.tag == "body").unwrap();
+        assert_eq!(
+            body.computed_style.background_color,
+            Color { r: 0x1e, g: 0x1e, b: 0x1e, a: 255 }
+        );
+
+        plugin.navigate("http://example.com/").unwrap_err();
+        let text = plugin.active_tab().unwrap().document.as_ref().unwrap().root.inner_text();
+        assert!(text.contains("HTTPS-only mode refused"));
+
+        plugin.navigate("https://example.com/").unwrap();
+        assert!(matches!(plugin.active_tab().unwrap().navigation_state, NavigationState::Loaded));
+    }
+
+    #[test]
+    fn test_copy_selection_across_elements() {
+        use crate::types::Element;
+
+        let mut plugin = BrowserPlugin::default();
+        plugin.navigate("https://example.com").unwrap();
+        let body = Element::new("body")
+            .with_child(Element::new("p").with_text("Hello   world"))
+            .with_child(Element::new("p").with_text("Second line"))
+            .with_child(Element::new("p").with_text("Unselected"));
+        plugin.tabs[0].document = Some(Document {
+            title: "Page".into(),
+            root:  Element::new("html").with_child(body),
+            url:   "https://example.com".into(),
+        });
+        assert_eq!(plugin.copy_selection(), None);
+
+        // Positions may be given end first.
+        plugin.set_selection(
+            TextPosition { path: vec![0, 1], offset: 6 },
+            TextPosition { path: vec![0, 0], offset: 6 },
+        );
+        assert_eq!(plugin.copy_selection().as_deref(), Some("world\nSecond"));
+
+        plugin.navigate("https://example.com/other").unwrap();
+        assert_eq!(plugin.copy_selection(), None);
+    }
+
+    #[test]
+    fn test_slow_fetch_times_out() {
+        use crate::fetch::testing::MockFetcher;
+
+        let fetcher = Arc::new(MockFetcher::default().with_delay(Duration::from_secs(3600)));
+        let config = BrowserConfig { request_timeout_ms: 20, ..Default::default() };
+        let mut plugin = BrowserPlugin::new(config).with_fetcher(fetcher.clone());
+
+        let error = plugin.navigate("https://slow.example/").unwrap_err();
+        assert!(matches!(error, BrowserError::Network(ref msg) if msg == "timeout"));
+        let tab = plugin.active_tab().unwrap();
+        assert!(matches!(tab.navigation_state, NavigationState::Error));
+        assert_eq!(fetcher.requests()[0].timeout, Some(Duration::from_millis(20)));
+
+        let tab_id = tab.id;
+        assert!(!plugin.cancel_navigation(tab_id));
+        plugin.tabs[0].navigation_state = NavigationState::Loading;
+        assert!(plugin.cancel_navigation(tab_id));
+        assert!(matches!(plugin.tabs[0].navigation_state, NavigationState::Error));
+    }
+
+    #[test]
+    fn test_asset_fetch_flags() {
+        use crate::{fetch::testing::MockFetcher, types::Element};
+
+        let page = || Document {
+            title: "Page".into(),
+            root:  Element::new("html").with_child(
+                Element::new("body")
+                    .with_child(Element::new("img").with_attribute("src", "/hero.png")),
+            ),
+            url:   "https://example.com/".into(),
+        };
+        let fetched = |config: BrowserConfig| {
+            let fetcher = Arc::new(MockFetcher::default());
+            let mut plugin = BrowserPlugin::new(config).with_fetcher(fetcher.clone());
+            plugin.navigate("https://example.com/").unwrap();
+            plugin.tabs[0].document = Some(page());
+            plugin.scroll_to(0.0).unwrap();
+            let favicon = plugin.tabs[0].favicon.clone();
+            (
+                favicon,
+                fetcher.requested(ResourceKind::Favicon),
+                fetcher.requested(ResourceKind::Image),
+            )
+        };
+
+        let (favicon, icons, images) = fetched(BrowserConfig::default());
+        assert_eq!(favicon.as_deref(), Some("https://example.com/favicon.ico"));
+        assert_eq!(icons, ["https://example.com/favicon.ico"]);
+        assert_eq!(images, ["https://example.com/hero.png"]);
+
+        let (favicon, icons, images) =
+            fetched(BrowserConfig { load_favicons: false, ..Default::default() });
+        assert_eq!(favicon.as_deref(), Some("https://example.com/favicon.ico"));
+        assert!(icons.is_empty());
+        assert_eq!(images, ["https://example.com/hero.png"]);
+
+        let (_, icons, images) =
+            fetched(BrowserConfig { enable_images: false, ..Default::default() });
+        assert_eq!(icons, ["https://example.com/favicon.ico"]);
+        assert!(images.is_empty());
+    }
+
+    #[test]
+    fn test_author_stylesheets_cascade_in_document_order() {
+        use crate::{
+            fetch::{FetchResponse, testing::MockFetcher},
+            types::{CssRule, Element},
+        };
+
+        let fetcher = Arc::new(MockFetcher::default().with_response(
+            "https://example.com/css/late.css",
+            FetchResponse::ok("text/css", "p { width: 200px }"),
+        ));
+        let user = StyleSheet {
+            rules: vec![CssRule {
+                selector:     "p".into(),
+                declarations: vec![("height".into(), "30px".into())],
+            }],
+        };
+        let config = BrowserConfig { user_stylesheet: Some(user), ..Default::default() };
+        let mut plugin = BrowserPlugin::new(config).with_fetcher(fetcher.clone());
+        plugin.navigate("https://example.com/").unwrap();
+
+        let head = Element::new("head")
+            .with_child(Element::new("style").with_text("p { width: 100px; height: 10px }"))
+            .with_child(
+                Element::new("link")
+                    .with_attribute("rel", "stylesheet")
+                    .with_attribute("href", "css/late.css"),
+            );
+        plugin.tabs[0].document = Some(Document {
+            title: "Styled".into(),
+            root:  Element::new("html")
+                .with_child(head)
+                .with_child(Element::new("body").with_child(Element::new("p"))),
+            url:   "https://example.com/".into(),
+        });
+        plugin.load_stylesheets();
+        plugin.load_stylesheets();
+        assert_eq!(
+            fetcher.requested(ResourceKind::Stylesheet),
+            ["https://example.com/css/late.css"]
+        );
+
+        let (tree, _) = plugin.render_active_document().unwrap().unwrap();
+        let p = &tree.root.children[0].children[0];
+        assert_eq!(p.element.tag, "p");
+        // The linked sheet comes later, so it wins at equal specificity; the
+        // user sheet beats both for normal declarations.
+        assert_eq!(p.computed_style.width, Some(200.0));
+        assert_eq!(p.computed_style.height, Some(30.0));
+    }
+
+    #[test]
+    fn test_script_sets_document_and_tab_title() {
+        let mut plugin = BrowserPlugin::default();
+        plugin.navigate("https://example.com/").unwrap();
+
+        plugin.run_script("document.title = \"X\"").unwrap();
+        let tab = plugin.active_tab().unwrap();
+        assert_eq!(tab.document.as_ref().unwrap().title, "X");
+        assert_eq!(tab.title, "X");
+
+        let config = BrowserConfig {
+            script_blocked_hosts: vec!["example.com".into()],
+            ..Default::default()
+        };
+        let mut plugin = BrowserPlugin::new(config);
+        plugin.navigate("https://example.com/").unwrap();
+        assert!(matches!(
+            plugin.run_script("document.title = 'Y'"),
+            Err(BrowserError::Script(_))
+        ));
+        assert_ne!(plugin.active_tab().unwrap().title, "Y");
+    }
+
+    #[test]
+    fn test_search_keywords_use_search_template() {
+        use crate::fetch::testing::MockFetcher;
+
+        let fetcher = Arc::new(MockFetcher::default());
+        let config =
+            BrowserConfig { search_template: "https://search/?q={}".into(), ..Default::default() };
+        let mut plugin = BrowserPlugin::new(config).with_fetcher(fetcher.clone());
+
+        plugin.navigate("hello world").unwrap();
+        assert_eq!(plugin.active_tab().unwrap().url, "https://search/?q=hello%20world");
+        plugin.navigate("example.com").unwrap();
+        assert_eq!(plugin.active_tab().unwrap().url, "example.com");
+        assert_eq!(fetcher.requests()[0].url, "https://search/?q=hello%20world");
+    }
+
+    #[test]
+    fn test_origin_cookie_denial_persists_across_navigations() {
+        use crate::fetch::testing::MockFetcher;
+
+        let fetcher = Arc::new(MockFetcher::default());
+        let mut plugin = BrowserPlugin::default().with_fetcher(fetcher.clone());
+        assert!(plugin.config().enable_cookies);
+        plugin.permissions_mut().set("https://a.example", Permission::Cookies, false);
+
+        for url in [
+            "https://a.example/one",
+            "https://b.example/",
+            "https://a.example/two",
+        ] {
+            plugin.navigate(url).unwrap();
+        }
+        let cookies: Vec<bool> = fetcher
+            .requests()
+            .iter()
+            .filter(|r| r.kind == ResourceKind::Document)
+            .map(|r| r.cookies)
+            .collect();
+        assert_eq!(cookies, [false, true, false]);
+        assert!(plugin.permissions().query("https://b.example/", Permission::Cookies));
+    }
+
+    #[test]
+    fn test_popups_blocked_in_strict_mode_unless_granted() {
+        let config =
+            BrowserConfig { privacy_mode: crate::types::PrivacyMode::Strict, ..Default::default() };
+        let mut plugin = BrowserPlugin::new(config);
+        plugin.new_tab();
+
+        assert_eq!(plugin.request_new_window("https://ads.example/", "https://news.example"), None);
+        assert_eq!(plugin.blocked_popups(), 1);
+        assert_eq!(plugin.tabs().len(), 1);
+
+        plugin.permissions_mut().set("https://news.example", Permission::Popups, true);
+        let tab_id =
+            plugin.request_new_window("https://news.example/story", "https://news.example");
+        assert_eq!(plugin.active_tab().map(|tab| tab.id), tab_id);
+        assert_eq!(plugin.active_tab().unwrap().url, "https://news.example/story");
+        assert_eq!(plugin.blocked_popups(), 1);
+
+        let mut plugin = BrowserPlugin::default();
+        assert!(plugin.request_new_window("https://a.example/", "https://b.example").is_some());
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_navigate_async_loads_tab() {
+        use std::{
+            future::Future,
+            pin::pin,
+            sync::Mutex,
+            task::{Context, Poll, Wake, Waker},
+            thread::{self, Thread},
+        };
+
+        use crate::fetch::{AsyncContentFetcher, FetchFuture};
+
+        /// Returns each page after yielding to the executor once.
+        #[derive(Default)]
+        struct AsyncMock {
+            requests: Mutex<Vec<String>>,
+        }
+
+        impl AsyncContentFetcher for AsyncMock {
+            fn fetch<'a>(&'a self, request: &'a FetchRequest) -> FetchFuture<'a> {
+                self.requests.lock().unwrap().push(request.url.clone());
+                let mut yielded = false;
+                Box::pin(std::future::poll_fn(move |cx| {
+                    if !yielded {
+                        yielded = true;
+                        cx.waker().wake_by_ref();
+                        return Poll::Pending;
+                    }
+                    Poll::Ready(Ok(FetchResponse::ok(
+                        "text/html",
+                        "<html><body><p>Hi</p></body></html>",
+                    )))
+                }))
+            }
+        }
+
+        struct ThreadWaker(Thread);
+
+        impl Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        fn block_on<F: Future>(future: F) -> F::Output {
+            let mut future = pin!(future);
+            let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+            let mut cx = Context::from_waker(&waker);
+            loop {
+                if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                    return output;
+                }
+                thread::park();
+            }
+        }
+
+        let fetcher = Arc::new(AsyncMock::default());
+        let mut plugin = BrowserPlugin::default().with_async_fetcher(fetcher.clone());
+        block_on(plugin.navigate_async("https://async.example/")).unwrap();
+
+        let tab = plugin.active_tab().unwrap();
+        assert!(matches!(tab.navigation_state, NavigationState::Loaded));
+        assert_eq!(tab.document.as_ref().unwrap().url, "https://async.example/");
+        assert_eq!(*fetcher.requests.lock().unwrap(), ["https://async.example/"]);
+    }
+
+    #[test]
+    fn test_strict_mode_reports_generic_user_agent() {
+        use crate::fetch::testing::MockFetcher;
+
+        let user_agent = |privacy_mode| {
+            let fetcher = Arc::new(MockFetcher::default());
+            let config = BrowserConfig { privacy_mode, ..Default::default() };
+            let mut plugin = BrowserPlugin::new(config).with_fetcher(fetcher.clone());
+            plugin.navigate("https://example.com/").unwrap();
+            let sent = fetcher.requests()[0].header("user-agent").map(str::to_string);
+            (plugin.fingerprint_profile().user_agent, sent)
+        };
+
+        let (profile, sent) = user_agent(crate::types::PrivacyMode::Standard);
+        assert_eq!(profile, "EssentiaBrowser/1.0");
+        assert_eq!(sent.as_deref(), Some("EssentiaBrowser/1.0"));
+        let (profile, sent) = user_agent(crate::types::PrivacyMode::Strict);
+        assert_ne!(profile, "EssentiaBrowser/1.0");
+        assert_eq!(sent, Some(profile));
+    }
+
+    #[test]
+    fn test_autoplay_media_blocked_by_config() {
+        use crate::types::Element;
+
+        let page = || Document {
+            title: String::new(),
+            root:  Element::new("body")
+                .with_child(
+                    Element::new("video")
+                        .with_attribute("autoplay", "")
+                        .with_attribute("src", "intro.mp4"),
+                )
+                .with_child(
+                    Element::new("video")
+                        .with_attribute("autoplay", "")
+                        .with_attribute("muted", "")
+                        .with_attribute("src", "loop.mp4"),
+                )
+                .with_child(Element::new("audio").with_attribute("src", "song.mp3")),
+            url:   "https://example.com/".into(),
+        };
+        let load = |block_autoplay| {
+            let mut plugin =
+                BrowserPlugin::new(BrowserConfig { block_autoplay, ..Default::default() });
+            plugin.new_tab();
+            plugin.commit_navigation("https://example.com/", Ok(page())).unwrap();
+            plugin
+        };
+        let sources = |media: Vec<&Element>| {
+            media
+                .iter()
+                .map(|m| m.attribute("src").unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let plugin = load(true);
+        assert_eq!(sources(plugin.autoplay_media()), ["loop.mp4"]);
+        assert_eq!(sources(plugin.autoplay_blocked_media()), ["intro.mp4"]);
+        // Blocking is enforced at play time; the page keeps its markup.
+        let root = &plugin.tabs[0].document.as_ref().unwrap().root;
+        assert_eq!(root, &page().root);
+
+        let plugin = load(false);
+        assert_eq!(sources(plugin.autoplay_media()), ["intro.mp4", "loop.mp4"]);
+        assert!(plugin.autoplay_blocked_media().is_empty());
+    }
+
+    #[test]
+    fn test_history_restores_scroll_position() {
+        use crate::fetch::testing::MockFetcher;
+
+        let tall = "<p>line</p>".repeat(200);
+        let fetcher = Arc::new(
+            MockFetcher::default()
+                .with_response("https://a.example/", FetchResponse::ok("text/html", tall)),
+        );
+        let mut plugin = BrowserPlugin::default().with_fetcher(fetcher);
+
+        plugin.navigate("https://a.example/").unwrap();
+        plugin.scroll_to(450.0).unwrap();
+        plugin.navigate("https://b.example/").unwrap();
+        assert_eq!(plugin.renderer.scroll_offset(), 0.0);
+
+        plugin.go_back().unwrap();
+        assert_eq!(plugin.active_tab().unwrap().url, "https://a.example/");
+        assert_eq!(plugin.renderer.scroll_offset(), 450.0);
+        plugin.go_forward().unwrap();
+        assert_eq!(plugin.active_tab().unwrap().url, "https://b.example/");
+        assert_eq!(plugin.renderer.scroll_offset(), 0.0);
+        assert!(plugin.go_forward().is_err());
+    }
+
+    #[test]
+    fn test_oversized_document_rejected_before_parse() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use crate::fetch::testing::MockFetcher;
+
+        #[derive(Default)]
+        struct ParseCounter(AtomicUsize);
+
+        impl NavigationObserver for ParseCounter {
+            fn on_parse_complete(&self, _url: &str, _document: &Document) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let html = format!("<html><body>{}</body></html>", "<p>filler</p>".repeat(100));
+        let fetcher =
+            Arc::new(MockFetcher::default().with_response(
+                "https://big.example/",
+                FetchResponse::ok("text/html", html.as_str()),
+            ));
+        let config = BrowserConfig { max_document_bytes: 512, ..Default::default() };
+        let mut plugin = BrowserPlugin::new(config).with_fetcher(fetcher);
+        let parses = Arc::new(ParseCounter::default());
+        plugin.add_navigation_observer(parses.clone());
+
+        let error = plugin.navigate("https://big.example/").unwrap_err();
+        assert!(matches!(error, BrowserError::Network(ref msg) if msg == "document too large"));
+        assert!(matches!(plugin.active_tab().unwrap().navigation_state, NavigationState::Error));
+        assert_eq!(parses.0.load(Ordering::Relaxed), 0);
+
+        plugin.navigate("https://small.example/").unwrap();
+        assert_eq!(parses.0.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_plugin_events_during_navigation() {
+        use std::sync::Mutex;
+
+        use crate::fetch::testing::MockFetcher;
+
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<PluginEvent>>);
+
+        impl PluginObserver for Recorder {
+            fn on_event(&self, event: &PluginEvent) {
+                self.0.lock().unwrap().push(event.clone());
+            }
+        }
+
+        let fetcher = Arc::new(MockFetcher::default().with_response(
+            "https://a.example/",
+            FetchResponse { status: 404, ..FetchResponse::ok("text/html", "") },
+        ));
+        let mut plugin = BrowserPlugin::default().with_fetcher(fetcher);
+        let recorder = Arc::new(Recorder::default());
+        plugin.add_event_observer(recorder.clone());
+
+        plugin.navigate("https://b.example/").unwrap();
+        let _ = plugin.navigate("https://a.example/");
+        plugin.close_tab(1);
+
+        let url = |url: &str| url.to_string();
+        let events = recorder.0.lock().unwrap();
+        assert_eq!(events[0], PluginEvent::TabCreated { tab_id: 1 });
+        assert_eq!(
+            events[1],
+            PluginEvent::LoadStarted { tab_id: 1, url: url("https://b.example/") }
+        );
+        assert!(matches!(&events[2], PluginEvent::TitleChanged { tab_id: 1, .. }));
+        assert_eq!(
+            events[3],
+            PluginEvent::LoadFinished { tab_id: 1, url: url("https://b.example/") }
+        );
+        assert_eq!(
+            events[4],
+            PluginEvent::LoadStarted { tab_id: 1, url: url("https://a.example/") }
+        );
+        assert!(
+            matches!(&events[events.len() - 2], PluginEvent::Error { tab_id: 1, message, .. } if message.contains("404"))
+        );
+        assert_eq!(events[events.len() - 1], PluginEvent::TabClosed { tab_id: 1 });
+    }
+
+    #[test]
+    fn test_failed_image_is_reported_without_failing_navigation() {
+        use std::sync::Mutex;
+
+        use crate::fetch::testing::MockFetcher;
+
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<PluginEvent>>);
+
+        impl PluginObserver for Recorder {
+            fn on_event(&self, event: &PluginEvent) {
+                self.0.lock().unwrap().push(event.clone());
+            }
+        }
+
+        let html = r#"<img src="/missing.png"><img src="/ok.png">"#;
+        let fetcher = Arc::new(
+            MockFetcher::default()
+                .with_response("https://a.example/", FetchResponse::ok("text/html", html))
+                .with_response(
+                    "https://a.example/missing.png",
+                    FetchResponse { status: 404, ..FetchResponse::ok("image/png", "") },
+                ),
+        );
+        let mut plugin = BrowserPlugin::default().with_fetcher(fetcher);
+        let recorder = Arc::new(Recorder::default());
+        plugin.add_event_observer(recorder.clone());
+
+        plugin.navigate("https://a.example/").unwrap();
+        plugin.scroll_to(0.0).unwrap();
+
+        let loaded = &plugin.tabs[0].loaded_images;
+        assert!(loaded.contains("https://a.example/ok.png"));
+        assert!(!loaded.contains("https://a.example/missing.png"));
+        let events = recorder.0.lock().unwrap();
+        let failures: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                PluginEvent::ResourceFailed { url, message, .. } => Some((url, message)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(failures.len(), 1);
+        assert!(failures.iter().all(|(url, message)| {
+            url.as_str() == "https://a.example/missing.png" && message.contains("404")
+        }));
+        assert!(events.iter().any(|event| matches!(event, PluginEvent::LoadFinished { .. })));
+        drop(events);
+
+        // A reload retries the image.
+        plugin.reload(false).unwrap();
+        let events = recorder.0.lock().unwrap();
+        let failed = |event: &&PluginEvent| matches!(event, PluginEvent::ResourceFailed { .. });
+        assert_eq!(events.iter().filter(failed).count(), 2);
+    }
+
+    #[test]
+    fn test_render_timing_is_recorded_in_page_metrics() {
+        use crate::fetch::testing::MockFetcher;
+
+        let html = "<p>one</p>".repeat(100);
+        let fetcher = Arc::new(
+            MockFetcher::default()
+                .with_response("https://a.example/", FetchResponse::ok("text/html", html)),
+        );
+        let mut plugin = BrowserPlugin::default().with_fetcher(fetcher);
+        assert_eq!(plugin.page_metrics().render_timing, RenderTiming::default());
+
+        plugin.navigate("https://a.example/").unwrap();
+        let timing = plugin.page_metrics().render_timing;
+        assert!(timing.style_ms >= 0.0 && timing.layout_ms >= 0.0 && timing.paint_ms >= 0.0);
+        assert!(timing.style_ms + timing.layout_ms + timing.paint_ms > 0.0);
+    }
+
+    #[test]
+    fn test_navigating_to_current_url_adds_no_history_entry() {
+        let mut plugin = BrowserPlugin::default();
+        plugin.navigate("http://x/").unwrap();
+        plugin.navigate("http://x").unwrap();
+        plugin.navigate("http://X:80").unwrap();
+        assert_eq!(plugin.active_tab().unwrap().history.len(), 1);
+
+        plugin.navigate("http://x/other").unwrap();
+        assert_eq!(plugin.active_tab().unwrap().history.len(), 2);
+    }
+
+    #[test]
+    fn test_navigation_sends_referrer_from_page_policy() {
+        use crate::{fetch::testing::MockFetcher, types::Element};
+
+        let referer = |config: BrowserConfig, meta: Option<&str>| {
+            let fetcher = Arc::new(MockFetcher::default());
+            let mut plugin = BrowserPlugin::new(config).with_fetcher(fetcher.clone());
+            plugin.new_tab();
+            let mut root = Element::new("head");
+            if let Some(content) = meta {
+                root = root.with_child(
+                    Element::new("meta")
+                        .with_attribute("name", "referrer")
+                        .with_attribute("content", content),
+                );
+            }
+            plugin.tabs[0].document = Some(Document {
+                title: String::new(),
+                root,
+                url: "https://a.example/page?q=1".into(),
+            });
+            plugin.follow_link("https://b.example/").unwrap();
+            fetcher.requests()[0].header("referer").map(str::to_string)
+        };
+
+        assert_eq!(referer(BrowserConfig::default(), None).as_deref(), Some("https://a.example/"));
+        let private = BrowserConfig {
+            privacy_mode: crate::types::PrivacyMode::Private,
+            ..Default::default()
+        };
+        assert_eq!(referer(private.clone(), None), None);
+        assert_eq!(referer(private, Some("origin")).as_deref(), Some("https://a.example/"));
+        assert_eq!(referer(BrowserConfig::default(), Some("no-referrer")), None);
+    }
+
+    #[test]
+    fn test_typed_and_history_navigations_send_no_referrer() {
+        use crate::fetch::{FetchResponse, testing::MockFetcher};
+
+        let uncached = |body: &str| {
+            let mut response = FetchResponse::ok("text/html", body.to_string());
+            response.headers = vec![("Cache-Control".into(), "no-store".into())];
+            response
+        };
+        let fetcher = Arc::new(
+            MockFetcher::default()
+                .with_response("https://a.example/", uncached("<p>a</p>"))
+                .with_response("https://b.example/", uncached("<p>b</p>")),
+        );
+        let mut plugin = BrowserPlugin::default().with_fetcher(fetcher.clone());
+
+        plugin.navigate("https://a.example/").unwrap();
+        plugin.navigate("https://b.example/").unwrap();
+        plugin.go_back().unwrap();
+        plugin.reload(false).unwrap();
+        let documents: Vec<_> = fetcher
+            .requests()
+            .into_iter()
+            .filter(|r| r.kind == ResourceKind::Document)
+            .collect();
+        assert_eq!(documents.len(), 4);
+        assert!(documents.iter().all(|request| request.header("referer").is_none()));
+
+        plugin.follow_link("https://b.example/").unwrap();
+        let last = fetcher.requests().into_iter().rfind(|r| r.kind == ResourceKind::Document);
+        assert_eq!(last.unwrap().header("referer"), Some("https://a.example/"));
+    }
+
+    #[test]
+    fn test_hibernated_tab_reloads_on_activation() {
+        use crate::fetch::testing::MockFetcher;
+
+        let mut uncached = FetchResponse::ok("text/html", b"<p>a</p>".to_vec());
+        uncached.headers.push(("Cache-Control".into(), "no-store".into()));
+        let fetcher =
+            Arc::new(MockFetcher::default().with_response("https://a.example/", uncached));
+        let mut plugin = BrowserPlugin::default().with_fetcher(fetcher.clone());
+        let first = plugin.new_tab();
+        plugin.navigate("https://a.example/").unwrap();
+        let second = plugin.new_tab();
+        plugin.navigate("https://b.example/").unwrap();
+        let loaded = plugin.memory_usage();
+
+        assert!(!plugin.hibernate_tab(second), "the active tab stays loaded");
+        assert!(plugin.hibernate_tab(first));
+        assert!(plugin.is_hibernated(first));
+        assert!(plugin.tabs[0].document.is_none());
+        assert_eq!(plugin.tabs[0].url, "https://a.example/");
+        assert!(plugin.memory_usage() < loaded);
+
+        plugin.activate_tab(first).unwrap();
+        assert!(!plugin.is_hibernated(first));
+        assert!(plugin.active_tab().unwrap().document.is_some());
+        assert_eq!(
+            fetcher.requested(ResourceKind::Document),
+            [
+                "https://a.example/",
+                "https://b.example/",
+                "https://a.example/"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_idle_and_memory_pressure_hibernate_background_tabs() {
+        let mut plugin = BrowserPlugin::default();
+        let first = plugin.new_tab();
+        plugin.navigate("https://a.example/").unwrap();
+        let second = plugin.new_tab();
+        plugin.navigate("https://b.example/").unwrap();
+        assert!(!plugin.is_hibernated(first));
+
+        plugin.hibernate_idle_tabs(Instant::now() + Duration::from_secs(31 * 60));
+        assert!(plugin.is_hibernated(first));
+        assert!(!plugin.is_hibernated(second));
+
+        let config = BrowserConfig { max_memory: 1, hibernate_after: None, ..Default::default() };
+        let mut plugin = BrowserPlugin::new(config);
+        let first = plugin.new_tab();
+        plugin.navigate("https://a.example/").unwrap();
+        plugin.new_tab();
+        plugin.navigate("https://b.example/").unwrap();
+        assert!(plugin.is_hibernated(first));
+    }
+
+    #[test]
+    fn test_find_next_and_prev_wrap_around() {
+        use crate::types::Element;
+
+        let filler = |body: Element| {
+            (0..50).fold(body, |body, _| body.with_child(Element::new("p").with_text("filler")))
+        };
+        let body = filler((0..3).fold(Element::new("body"), |body, i| {
+            filler(body).with_child(Element::new("p").with_text(format!("needle {i}")))
+        }));
+        let mut plugin = BrowserPlugin::default();
+        plugin.new_tab();
+        plugin.resize(800.0, 200.0);
+        plugin.tabs[0].document = Some(Document {
+            title: String::new(),
+            root:  body,
+            url:   "https://a.example/".into(),
+        });
+
+        assert_eq!(plugin.find_start("NEEDLE").unwrap(), 3);
+        let current = |plugin: &BrowserPlugin| {
+            let find = plugin.active_tab().unwrap().find.as_ref().unwrap();
+            assert_eq!(find.matches.iter().filter(|m| m.current).count(), 1);
+            assert!(find.matches[find.current].current);
+            (find.current, find.matches[find.current].layout.y)
+        };
+        let (index, y) = current(&plugin);
+        assert_eq!(index, 0);
+        assert_eq!(plugin.scroll_offset(), y);
+
+        assert_eq!(plugin.find_next().unwrap(), Some(1));
+        assert_eq!(plugin.find_next().unwrap(), Some(2));
+        let (_, last_y) = current(&plugin);
+        assert_eq!(plugin.scroll_offset(), last_y);
+        assert_eq!(plugin.find_next().unwrap(), Some(0));
+        assert_eq!(plugin.find_prev().unwrap(), Some(2));
+        assert_eq!(current(&plugin).0, 2);
+        assert_eq!(plugin.find_highlights().len(), 3);
+
+        plugin.find_stop();
+        assert_eq!(plugin.find_next().unwrap(), None);
+        assert!(plugin.find_highlights().is_empty());
+    }
+
+    #[test]
+    fn test_mixed_content_image_blocked_or_upgraded() {
+        use crate::{fetch::testing::MockFetcher, types::Element};
+
+        let load = |mixed_content_policy| {
+            let fetcher = Arc::new(MockFetcher::default());
+            let config = BrowserConfig { mixed_content_policy, ..Default::default() };
+            let mut plugin = BrowserPlugin::new(config).with_fetcher(fetcher.clone());
+            plugin.new_tab();
+            let page = Document {
+                title: String::new(),
+                root:  Element::new("body")
+                    .with_child(
+                        Element::new("img").with_attribute("src", "http://cdn.example/a.png"),
+                    )
+                    .with_child(Element::new("img").with_attribute("src", "/b.png")),
+                url:   "https://secure.example/".into(),
+            };
+            plugin.commit_navigation("https://secure.example/", Ok(page)).unwrap();
+            plugin.scroll_to(0.0).unwrap();
+            let counts = (plugin.mixed_content_upgraded(), plugin.mixed_content_blocked());
+            (fetcher.requested(ResourceKind::Image), counts)
+        };
+
+        let (images, counts) = load(Some(MixedContentPolicy::Block));
+        assert_eq!(images, ["https://secure.example/b.png"]);
+        assert_eq!(counts, (0, 1));
+        let (mut images, counts) = load(Some(MixedContentPolicy::Upgrade));
+        images.sort();
+        assert_eq!(images, ["https://cdn.example/a.png", "https://secure.example/b.png"]);
+        assert_eq!(counts, (1, 0));
+        assert_eq!(load(Some(MixedContentPolicy::Allow)).1, (0, 0));
+    }
+
+    #[test]
+    fn test_mixed_content_css_background_and_stylesheet_blocked() {
+        use crate::{fetch::testing::MockFetcher, types::Element};
+
+        let fetcher = Arc::new(MockFetcher::default());
+        let config = BrowserConfig {
+            mixed_content_policy: Some(MixedContentPolicy::Block),
+            ..Default::default()
+        };
+        let mut plugin = BrowserPlugin::new(config).with_fetcher(fetcher.clone());
+        plugin.new_tab();
+        let page = Document {
+            title: String::new(),
+            root:  Element::new("html")
+                .with_child(
+                    Element::new("head")
+                        .with_child(
+                            Element::new("style").with_text(
+                                "div { background-image: url(http://cdn.example/bg.png) }",
+                            ),
+                        )
+                        .with_child(
+                            Element::new("link")
+                                .with_attribute("rel", "stylesheet")
+                                .with_attribute("href", "http://cdn.example/site.css"),
+                        ),
+                )
+                .with_child(Element::new("body").with_child(Element::new("div").with_text("x"))),
+            url:   "https://secure.example/".into(),
+        };
+        plugin.commit_navigation("https://secure.example/", Ok(page)).unwrap();
+        plugin.scroll_to(0.0).unwrap();
+
+        assert!(fetcher.requested(ResourceKind::Image).is_empty());
+        assert!(fetcher.requested(ResourceKind::Stylesheet).is_empty());
+        assert_eq!(plugin.mixed_content_blocked(), 2);
+        let link = &plugin.tabs[0].document.as_ref().unwrap().root.children[0].children[1];
+        assert_eq!(link.attribute("href"), Some("http://cdn.example/site.css"));
+    }
+
+    #[test]
+    fn test_navigating_after_going_back_drops_forward_entries() {
+        let mut plugin = BrowserPlugin::default();
+        assert!(!plugin.can_go_back() && !plugin.can_go_forward());
+
+        plugin.navigate("https://a.example/").unwrap();
+        plugin.navigate("https://b.example/").unwrap();
+        assert!(plugin.can_go_back() && !plugin.can_go_forward());
+
+        plugin.go_back().unwrap();
+        assert!(!plugin.can_go_back() && plugin.can_go_forward());
+        assert!(plugin.go_back().is_err());
+
+        plugin.navigate("https://c.example/").unwrap();
+        assert!(plugin.can_go_back() && !plugin.can_go_forward());
+        let urls: Vec<_> = plugin
+            .active_tab()
+            .unwrap()
+            .history
+            .iter()
+            .map(|entry| entry.url.as_str())
+            .collect();
+        assert_eq!(urls, ["https://a.example/", "https://c.example/"]);
+
+        plugin.go_back().unwrap();
+        plugin.go_forward().unwrap();
+        assert_eq!(plugin.active_tab().unwrap().url, "https://c.example/");
+    }
+
+    #[test]
+    fn test_reload_rebuilds_document() {
+        use crate::fetch::testing::MockFetcher;
+
+        let fetcher = Arc::new(MockFetcher::default().with_response(
+            "https://a.example/",
+            FetchResponse::ok("text/html", "<title>Fresh</title><p>Body</p>"),
+        ));
+        let mut plugin = BrowserPlugin::default().with_fetcher(fetcher.clone());
+        assert!(plugin.reload(false).is_err());
+
+        plugin.navigate("https://a.example/#top").unwrap();
+        plugin.tabs[0].document.as_mut().unwrap().title = "Edited".into();
+        plugin.reload(false).unwrap();
+        let tab = plugin.active_tab().unwrap();
+        assert_eq!(tab.document.as_ref().unwrap().title, "Fresh");
+        assert!(matches!(tab.navigation_state, NavigationState::Loaded));
+        assert_eq!(tab.history.len(), 1);
+        assert_eq!(fetcher.requested(ResourceKind::Document).len(), 1);
+
+        plugin.reload(true).unwrap();
+        assert_eq!(fetcher.requested(ResourceKind::Document).len(), 2);
+
+        plugin.config.blocked_hosts.push("a.example".into());
+        assert!(plugin.reload(true).is_err());
+        assert!(matches!(plugin.active_tab().unwrap().navigation_state, NavigationState::Error));
+    }
 }
@@ -0,0 +1,419 @@
+//! Per-domain content policies.
+
+use std::collections::HashMap;
+
+use crate::{
+    config::BrowserConfig,
+    types::{
+        Document, FingerprintProfile, MixedContentPolicy, Permission, PrivacyMode, ReferrerPolicy,
+    },
+    url,
+};
+
+/// `User-Agent` reported instead of the configured one when resisting
+/// fingerprinting.
+const GENERIC_USER_AGENT: &str = "Mozilla/5.0 (compatible)";
+/// Granularity of the viewport size reported when resisting fingerprinting
+/// (px).
+const VIEWPORT_STEP: f32 = 100.0;
+/// Granularity of the zoom factor reported when resisting fingerprinting.
+const ZOOM_STEP: f32 = 0.25;
+
+impl PrivacyMode {
+    /// Parse a privacy mode name (`standard`, `strict` or `private`).
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "standard" => Some(Self::Standard),
+            "strict" => Some(Self::Strict),
+            "private" => Some(Self::Private),
+            _ => None,
+        }
+    }
+}
+
+impl MixedContentPolicy {
+    /// Parse a policy name (`allow`, `upgrade` or `block`).
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "allow" => Some(Self::Allow),
+            "upgrade" => Some(Self::Upgrade),
+            "block" => Some(Self::Block),
+            _ => None,
+        }
+    }
+
+    /// The configured policy, or the default for the config's modes.
+    pub fn for_config(config: &BrowserConfig) -> Self {
+        config.mixed_content_policy.unwrap_or(
+            if config.https_only || config.privacy_mode != PrivacyMode::Standard {
+                Self::Block
+            } else {
+                Self::Upgrade
+            },
+        )
+    }
+
+    /// Treatment of a subresource request from the page at `from` to `to`:
+    /// only `http:` requests from `https:` pages are mixed content.
+    pub(crate) fn check(self, from: &str, to: &str) -> MixedContent {
+        if self == Self::Allow || !has_scheme(from, "https") || !has_scheme(to, "http") {
+            return MixedContent::Allowed;
+        }
+        match self {
+            Self::Upgrade => MixedContent::Upgraded(format!("https{}", &to[4..])),
+            _ => MixedContent::Blocked,
+        }
+    }
+}
+
+/// Outcome of [`MixedContentPolicy::check`] for one subresource request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum MixedContent {
+    /// Fetch the URL as referenced.
+    Allowed,
+    /// Fetch this `https:` URL instead.
+    Upgraded(String),
+    /// Do not fetch it.
+    Blocked,
+}
+
+impl ReferrerPolicy {
+    /// Parse a policy name (`no-referrer`, `origin`, `same-origin` or
+    /// `strict-origin`).
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "no-referrer" => Some(Self::NoReferrer),
+            "origin" => Some(Self::Origin),
+            "same-origin" => Some(Self::SameOrigin),
+            "strict-origin" => Some(Self::StrictOrigin),
+            _ => None,
+        }
+    }
+
+    /// Policy used when neither the config nor the page sets one.
+    pub fn default_for(mode: PrivacyMode) -> Self {
+        match mode {
+            PrivacyMode::Standard => Self::StrictOrigin,
+            PrivacyMode::Strict => Self::SameOrigin,
+            PrivacyMode::Private => Self::NoReferrer,
+        }
+    }
+
+    /// `Referer` value for a request from the page at `from` to `to`.
+    pub(crate) fn referrer(self, from: &str, to: &str) -> Option<String> {
+        let origin = url::origin(from).filter(|_| is_http(from))?;
+        match self {
+            Self::NoReferrer => None,
+            Self::Origin => Some(format!("{origin}/")),
+            Self::SameOrigin => {
+                (url::origin(to).as_ref() == Some(&origin)).then(|| strip_url(from))
+            },
+            Self::StrictOrigin => {
+                let downgrade = origin.starts_with("https:") && !to.starts_with("https:");
+                (!downgrade).then(|| format!("{origin}/"))
+            },
+        }
+    }
+}
+
+/// Where a document's requests come from, for their `Referer` header.
+#[derive(Debug, Clone)]
+pub(crate) struct ReferrerSource {
+    policy: ReferrerPolicy,
+    url:    Option<String>,
+}
+
+impl ReferrerSource {
+    /// Requests made from `document`, which sets the policy with
+    /// `<meta name="referrer">` or falls back to the configured one.
+    pub(crate) fn new(config: &BrowserConfig, document: Option<&Document>) -> Self {
+        let policy = document
+            .and_then(Document::referrer_policy)
+            .or(config.referrer_policy)
+            .unwrap_or_else(|| ReferrerPolicy::default_for(config.privacy_mode));
+        Self { policy, url: document.map(|d| d.url.clone()) }
+    }
+
+    /// `Referer` value for a request to `to`.
+    pub(crate) fn referrer(&self, to: &str) -> Option<String> {
+        self.policy.referrer(self.url.as_deref()?, to)
+    }
+}
+
+fn has_scheme(url: &str, scheme: &str) -> bool {
+    url.split_once(':').is_some_and(|(s, _)| s.eq_ignore_ascii_case(scheme))
+}
+
+fn is_http(url: &str) -> bool {
+    let scheme = url.split_once(':').map_or("", |(scheme, _)| scheme);
+    scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https")
+}
+
+/// `url` without its fragment and credentials, as sent in full referrers.
+fn strip_url(url: &str) -> String {
+    let url = url.split_once('#').map_or(url, |(url, _)| url);
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_string();
+    };
+    let authority_end = rest.find(['/', '?']).unwrap_or(rest.len());
+    let (authority, path) = rest.split_at(authority_end);
+    let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    let path = if path.is_empty() {
+        "/"
+    } else {
+        path
+    };
+    format!("{scheme}://{host}{path}")
+}
+
+impl FingerprintProfile {
+    /// What a browser with `config` and the given viewport reveals.
+    ///
+    /// Strict and private modes report a generic user agent, a viewport
+    /// rounded down to whole steps and a coarsened zoom factor, so these
+    /// values are shared by many users.
+    pub(crate) fn new(config: &BrowserConfig, viewport: (f32, f32), zoom: f32) -> Self {
+        let (width, height) = viewport;
+        if config.privacy_mode == PrivacyMode::Standard {
+            return Self {
+                user_agent: config.user_agent.clone(),
+                viewport_width: width,
+                viewport_height: height,
+                zoom,
+                resists_fingerprinting: false,
+            };
+        }
+        let step =
+            |value: f32| ((value / VIEWPORT_STEP).floor() * VIEWPORT_STEP).max(VIEWPORT_STEP);
+        Self {
+            user_agent:             GENERIC_USER_AGENT.to_string(),
+            viewport_width:         step(width),
+            viewport_height:        step(height),
+            zoom:                   ((zoom / ZOOM_STEP).round() * ZOOM_STEP).max(ZOOM_STEP),
+            resists_fingerprinting: true,
+        }
+    }
+}
+
+/// `User-Agent` to send under `config`, which is generic when the privacy
+/// mode resists fingerprinting.
+pub(crate) fn reported_user_agent(config: &BrowserConfig) -> &str {
+    match config.privacy_mode {
+        PrivacyMode::Standard => &config.user_agent,
+        PrivacyMode::Strict | PrivacyMode::Private => GENERIC_USER_AGENT,
+    }
+}
+
+/// Decides whether a document's scripts may run or be fetched.
+///
+/// Per-domain lists take precedence over the global `enable_javascript`
+/// toggle: a denied domain never runs scripts and an allowed one always does.
+/// Other domains follow the toggle, tightened by the privacy mode: strict
+/// mode only runs scripts on allowed domains, private mode only on secure
+/// (`https:`) pages. Domains match their subdomains too.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScriptPolicy {
+    enabled:      bool,
+    privacy_mode: PrivacyMode,
+    allowed:      Vec<String>,
+    denied:       Vec<String>,
+}
+
+impl ScriptPolicy {
+    /// The policy described by a browser configuration.
+    pub fn from_config(config: &BrowserConfig) -> Self {
+        Self {
+            enabled:      config.enable_javascript,
+            privacy_mode: config.privacy_mode,
+            allowed:      config.script_allowed_hosts.clone(),
+            denied:       config.script_blocked_hosts.clone(),
+        }
+    }
+
+    /// Check whether scripts of the document at `url` are permitted.
+    pub fn allows(&self, url: &str) -> bool {
+        let host = url::host(url);
+        let listed = |domains: &[String]| {
+            host.as_deref()
+                .is_some_and(|host| domains.iter().any(|domain| url::host_matches(host, domain)))
+        };
+        if listed(&self.denied) {
+            return false;
+        }
+        if listed(&self.allowed) {
+            return true;
+        }
+        self.enabled
+            && match self.privacy_mode {
+                PrivacyMode::Standard => true,
+                PrivacyMode::Strict => false,
+                PrivacyMode::Private => {
+                    url.get(..6).is_some_and(|s| s.eq_ignore_ascii_case("https:"))
+                },
+            }
+    }
+}
+
+/// Per-origin permission grants, consulted by the subsystems that fetch
+/// or run content.
+///
+/// An explicit grant or denial for an origin wins over the configuration.
+/// Without one, the configured toggle applies, tightened by the privacy
+/// mode: strict mode denies cookies and popups. Scripts fall back to the
+/// [`ScriptPolicy`].
+#[derive(Debug, Clone, Default)]
+pub struct PermissionStore {
+    cookies: bool,
+    images:  bool,
+    popups:  bool,
+    scripts: ScriptPolicy,
+    grants:  HashMap<(String, Permission), bool>,
+}
+
+impl PermissionStore {
+    /// A store with no grants and defaults taken from a browser configuration.
+    pub fn from_config(config: &BrowserConfig) -> Self {
+        let strict = config.privacy_mode == PrivacyMode::Strict;
+        Self {
+            cookies: config.enable_cookies && !strict,
+            images:  config.enable_images,
+            popups:  !config.block_popups && !strict,
+            scripts: ScriptPolicy::from_config(config),
+            grants:  HashMap::new(),
+        }
+    }
+
+    /// Grant or deny `permission` to the origin of `url`.
+    pub fn set(&mut self, url: &str, permission: Permission, allowed: bool) {
+        self.grants.insert((origin_key(url), permission), allowed);
+    }
+
+    /// Forget the explicit setting of `permission` for the origin of `url`.
+    pub fn reset(&mut self, url: &str, permission: Permission) {
+        self.grants.remove(&(origin_key(url), permission));
+    }
+
+    /// Check whether the origin of `url` has `permission`.
+    pub fn query(&self, url: &str, permission: Permission) -> bool {
+        if let Some(allowed) = self.grants.get(&(origin_key(url), permission)) {
+            return *allowed;
+        }
+        match permission {
+            Permission::Cookies => self.cookies,
+            Permission::Images => self.images,
+            Permission::Popups => self.popups,
+            Permission::Scripts => self.scripts.allows(url),
+        }
+    }
+}
+
+/// Grants are keyed by origin; URLs without one (`about:blank`) key as-is.
+fn origin_key(url: &str) -> String {
+    url::origin(url).unwrap_or_else(|| url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domain_lists_override_global_toggle() {
+        let config = BrowserConfig {
+            enable_javascript: false,
+            script_allowed_hosts: vec!["app.example".into()],
+            script_blocked_hosts: vec!["ads.example".into()],
+            ..Default::default()
+        };
+        let policy = ScriptPolicy::from_config(&config);
+        assert!(policy.allows("https://app.example/"));
+        assert!(policy.allows("https://www.app.example/editor"));
+        assert!(!policy.allows("https://news.example/"));
+
+        let policy =
+            ScriptPolicy::from_config(&BrowserConfig { enable_javascript: true, ..config });
+        assert!(!policy.allows("https://ads.example/tag.js"));
+        assert!(!policy.allows("https://cdn.ads.example/"));
+        assert!(policy.allows("https://news.example/"));
+    }
+
+    #[test]
+    fn test_privacy_modes_tighten_defaults() {
+        let config = |privacy_mode| BrowserConfig {
+            privacy_mode,
+            script_allowed_hosts: vec!["app.example".into()],
+            ..Default::default()
+        };
+
+        let strict = ScriptPolicy::from_config(&config(PrivacyMode::Strict));
+        assert!(!strict.allows("https://news.example/"));
+        assert!(strict.allows("https://app.example/"));
+
+        let private = ScriptPolicy::from_config(&config(PrivacyMode::Private));
+        assert!(private.allows("https://news.example/"));
+        assert!(!private.allows("http://news.example/"));
+        assert_eq!(PrivacyMode::parse(" Strict "), Some(PrivacyMode::Strict));
+        assert_eq!(PrivacyMode::parse("paranoid"), None);
+    }
+
+    #[test]
+    fn test_permission_grants_override_defaults() {
+        let mut permissions = PermissionStore::from_config(&BrowserConfig::default());
+        assert!(permissions.query("https://a.example/", Permission::Cookies));
+
+        permissions.set("https://a.example/login", Permission::Cookies, false);
+        assert!(!permissions.query("https://A.example/account", Permission::Cookies));
+        assert!(permissions.query("http://a.example/", Permission::Cookies));
+        permissions.reset("https://a.example", Permission::Cookies);
+        assert!(permissions.query("https://a.example/", Permission::Cookies));
+
+        let config = BrowserConfig { privacy_mode: PrivacyMode::Strict, ..Default::default() };
+        let mut permissions = PermissionStore::from_config(&config);
+        assert!(!permissions.query("https://a.example/", Permission::Scripts));
+        assert!(!permissions.query("https://a.example/", Permission::Popups));
+        permissions.set("https://a.example/", Permission::Scripts, true);
+        assert!(permissions.query("https://a.example/app.js", Permission::Scripts));
+    }
+
+    #[test]
+    fn test_fingerprint_profile_normalizes_in_strict_mode() {
+        let config = BrowserConfig { user_agent: "Custom/2.0".into(), ..Default::default() };
+        let standard = FingerprintProfile::new(&config, (1366.0, 768.0), 1.1);
+        assert_eq!(standard.user_agent, "Custom/2.0");
+        assert_eq!((standard.viewport_width, standard.viewport_height), (1366.0, 768.0));
+        assert!(!standard.resists_fingerprinting);
+
+        let config = BrowserConfig { privacy_mode: PrivacyMode::Strict, ..config };
+        let strict = FingerprintProfile::new(&config, (1366.0, 768.0), 1.1);
+        assert_eq!(strict.user_agent, GENERIC_USER_AGENT);
+        assert_eq!(reported_user_agent(&config), GENERIC_USER_AGENT);
+        assert_eq!((strict.viewport_width, strict.viewport_height), (1300.0, 700.0));
+        assert_eq!(strict.zoom, 1.0);
+    }
+
+    #[test]
+    fn test_referrer_policies_for_cross_origin_request() {
+        let from = "https://user:pw@a.example/page?q=1#top";
+        let referrer = |policy: ReferrerPolicy, to| policy.referrer(from, to);
+
+        assert_eq!(referrer(ReferrerPolicy::NoReferrer, "https://b.example/"), None);
+        assert_eq!(
+            referrer(ReferrerPolicy::Origin, "https://b.example/").as_deref(),
+            Some("https://a.example/")
+        );
+        assert_eq!(referrer(ReferrerPolicy::SameOrigin, "https://b.example/"), None);
+        assert_eq!(
+            referrer(ReferrerPolicy::SameOrigin, "https://a.example/next").as_deref(),
+            Some("https://a.example/page?q=1")
+        );
+        assert_eq!(
+            referrer(ReferrerPolicy::StrictOrigin, "https://b.example/").as_deref(),
+            Some("https://a.example/")
+        );
+        assert_eq!(referrer(ReferrerPolicy::StrictOrigin, "http://b.example/"), None);
+        assert_eq!(ReferrerPolicy::Origin.referrer("about:blank", "https://b.example/"), None);
+
+        assert_eq!(ReferrerPolicy::parse(" Same-Origin "), Some(ReferrerPolicy::SameOrigin));
+        assert_eq!(ReferrerPolicy::parse("unsafe-url"), None);
+        assert_eq!(ReferrerPolicy::default_for(PrivacyMode::Strict), ReferrerPolicy::SameOrigin);
+    }
+}
@@ -1,20 +1,102 @@
 //! Rendering engine.
 
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Instant,
+};
+
 use crate::{
-    errors::BrowserResult,
-    types::{ComputedStyle, Document, LayoutBox, RenderNode, RenderTree},
+    errors::{BrowserError, BrowserResult},
+    image::{ImageProvider, PLACEHOLDER_IMAGE_SIZE},
+    style::{Cascade, StyleOrigin, parse_length},
+    types::{
+        BorderStyle, BoxEdges, Color, ComputedStyle, Display, DisplayItem, Document, Element,
+        FindMatch, FlexDirection, LayoutBox, RenderNode, RenderTiming, RenderTree, StyleSheet,
+        TextAlign, TextPosition, ViewportMeta, ViewportWidth, Visibility,
+    },
+    url,
 };
 
+/// Style `node` and its descendants; `ancestors` holds the elements above
+/// it, root first.
+fn apply_styles_in<'a>(
+    node: &'a mut RenderNode,
+    cascade: &Cascade<'_>,
+    ancestors: &mut Vec<&'a Element>,
+    parent: Option<&ComputedStyle>,
+) {
+    let RenderNode { element, computed_style, children, .. } = node;
+    *computed_style = cascade.computed_style_in(element, ancestors, parent);
+    ancestors.push(element);
+    for child in children {
+        apply_styles_in(child, cascade, ancestors, Some(computed_style));
+    }
+    ancestors.pop();
+}
+
+/// Approximate advance width of a single character (px).
+pub(crate) const CHAR_WIDTH: f32 = 8.0;
+/// Height of a single line of text (px).
+pub(crate) const LINE_HEIGHT: f32 = 16.0;
+
+/// Devtools box-model overlay colors.
+const OVERLAY_MARGIN: Color = Color { r: 246, g: 178, b: 107, a: 168 };
+const OVERLAY_BORDER: Color = Color { r: 255, g: 229, b: 153, a: 168 };
+const OVERLAY_PADDING: Color = Color { r: 147, g: 196, b: 125, a: 140 };
+const OVERLAY_CONTENT: Color = Color { r: 111, g: 168, b: 220, a: 168 };
+
+/// Translucent fill drawn over find-in-page matches.
+const HIGHLIGHT_COLOR: Color = Color { r: 255, g: 235, b: 59, a: 128 };
+/// Accent fill drawn over the current find-in-page match.
+const CURRENT_MATCH_COLOR: Color = Color { r: 255, g: 150, b: 50, a: 160 };
+
+/// Computed styles of one parent's children, keyed by tag and attributes.
+type SiblingStyles<'e> = HashMap<(&'e str, &'e [(String, String)]), ComputedStyle>;
+
+/// Default smooth-scrolling speed (px per second).
+const DEFAULT_SCROLL_SPEED: f32 = 3000.0;
+
 /// Render engine for layout and painting.
 pub struct RenderEngine {
-    viewport_width:  f32,
-    viewport_height: f32,
+    viewport_width:   f32,
+    viewport_height:  f32,
+    device_width:     Option<f32>,
+    viewport_meta:    Option<ViewportMeta>,
+    scroll_y:         f32,
+    scroll_target:    Option<f32>,
+    scroll_speed:     f32,
+    selection:        Option<(TextPosition, TextPosition)>,
+    image_provider:   Option<Arc<dyn ImageProvider>>,
+    base_url:         Option<String>,
+    char_width:       f32,
+    /// Computed styles reused from an identical sibling while building
+    /// styled render trees.
+    style_cache_hits: AtomicUsize,
 }
 
 impl RenderEngine {
     /// Create a new render engine.
     pub fn new(width: f32, height: f32) -> Self {
-        Self { viewport_width: width, viewport_height: height }
+        Self {
+            viewport_width:   width,
+            viewport_height:  height,
+            device_width:     None,
+            viewport_meta:    None,
+            scroll_y:         0.0,
+            scroll_target:    None,
+            scroll_speed:     DEFAULT_SCROLL_SPEED,
+            selection:        None,
+            image_provider:   None,
+            base_url:         None,
+            char_width:       CHAR_WIDTH,
+            style_cache_hits: AtomicUsize::new(0),
+        }
     }
 
     /// Build render tree from document.
@@ -23,8 +105,101 @@ impl RenderEngine {
         Ok(RenderTree { root: root_node })
     }
 
+    /// Build a render tree with computed styles resolved through a cascade.
+    ///
+    /// Elements computing to `display: none` are left out of the tree.
+    pub fn build_styled_render_tree(
+        &self,
+        document: &Document,
+        cascade: &Cascade<'_>,
+    ) -> BrowserResult<RenderTree> {
+        let style = cascade.computed_style(&document.root, None);
+        let mut ancestors = Vec::new();
+        Ok(RenderTree {
+            root: self.build_styled_node(&document.root, style, cascade, &mut ancestors),
+        })
+    }
+
+    /// Style, lay out and paint a document, timing each stage.
+    pub fn render_timed(
+        &self,
+        document: &Document,
+        cascade: &Cascade<'_>,
+    ) -> BrowserResult<(RenderTree, Vec<DisplayItem>, RenderTiming)> {
+        let elapsed_ms = |start: Instant| start.elapsed().as_secs_f64() * 1000.0;
+        let start = Instant::now();
+        let mut tree = self.build_styled_render_tree(document, cascade)?;
+        let style_ms = elapsed_ms(start);
+        let start = Instant::now();
+        self.layout(&mut tree);
+        let layout_ms = elapsed_ms(start);
+        let start = Instant::now();
+        let display_list = self.paint(&tree);
+        let paint_ms = elapsed_ms(start);
+        Ok((tree, display_list, RenderTiming { style_ms, layout_ms, paint_ms }))
+    }
+
+    /// Recompute the styles of `node` and its descendants from an author
+    /// stylesheet.
+    ///
+    /// Matching declarations are applied in specificity and source order,
+    /// with inherited properties taken from the parent node. Nodes are kept
+    /// even if they compute to `display: none`. Sibling combinators never
+    /// match here, since render nodes hold copies of their elements; use
+    /// [`build_styled_render_tree`](Self::build_styled_render_tree) for
+    /// full selector support.
+    pub fn apply_styles(&self, node: &mut RenderNode, stylesheet: &StyleSheet) {
+        let cascade = Cascade::new().with_sheet(StyleOrigin::Author, stylesheet);
+        apply_styles_in(node, &cascade, &mut Vec::new(), None);
+    }
+
+    /// Build a styled render node and its displayed descendants.
+    ///
+    /// `ancestors` holds the elements above `element`, root first. Children
+    /// with the same tag and attributes share one computed style, since
+    /// they match the same rules and inherit from the same parent, unless
+    /// the cascade has sibling selectors.
+    fn build_styled_node<'e>(
+        &self,
+        element: &'e Element,
+        computed_style: ComputedStyle,
+        cascade: &Cascade<'_>,
+        ancestors: &mut Vec<&'e Element>,
+    ) -> RenderNode {
+        ancestors.push(element);
+        let share_styles = cascade.shares_sibling_styles();
+        let mut shared = SiblingStyles::new();
+        let children = element
+            .children
+            .iter()
+            .filter_map(|child| {
+                let key = (child.tag.as_str(), child.attributes.as_slice());
+                let style = if let Some(style) = shared.get(&key) {
+                    self.style_cache_hits.fetch_add(1, Ordering::Relaxed);
+                    style.clone()
+                } else {
+                    let style = cascade.computed_style_in(child, ancestors, Some(&computed_style));
+                    if share_styles {
+                        shared.insert(key, style.clone());
+                    }
+                    style
+                };
+                (!matches!(style.display, Display::None))
+                    .then(|| self.build_styled_node(child, style, cascade, ancestors))
+            })
+            .collect();
+        ancestors.pop();
+
+        RenderNode {
+            element: element.clone(),
+            computed_style,
+            layout: LayoutBox::default(),
+            children,
+        }
+    }
+
     /// Build a single render node.
-    fn build_render_node(&self, element: &crate::types::Element, x: f32, y: f32) -> RenderNode {
+    fn build_render_node(&self, element: &Element, x: f32, y: f32) -> RenderNode {
         let computed_style = ComputedStyle::default();
 
         let layout = LayoutBox {
@@ -45,23 +220,582 @@ impl RenderEngine {
     }
 
     /// Layout the render tree.
-    pub fn layout(&mut self, tree: &mut RenderTree) {
-        self.layout_node(&mut tree.root, 0.0, 0.0, self.viewport_width);
+    pub fn layout(&self, tree: &mut RenderTree) {
+        let margin = tree.root.computed_style.margin;
+        let width = (self.layout_width() - margin.left - margin.right).max(0.0);
+        self.layout_node(&mut tree.root, margin.left, margin.top, width);
+    }
+
+    /// Re-lay out only the subtree at `path` (child indices from the root)
+    /// after its content changed.
+    ///
+    /// The subtree keeps its position and width; if its height changes, the
+    /// following siblings of it and of each ancestor are shifted and the
+    /// ancestors' heights adjusted. A node sharing a flex row or an inline
+    /// line with its siblings also moves them sideways, so the nearest such
+    /// row or line container is laid out again in full instead. The result
+    /// matches what [`layout`](Self::layout) would produce for the whole tree.
+    pub fn relayout_subtree(&mut self, tree: &mut RenderTree, path: &[usize]) -> BrowserResult<()> {
+        self.relayout_at(&mut tree.root, path)
+            .map(|_| ())
+            .ok_or_else(|| BrowserError::Render(format!("No render node at path {path:?}")))
+    }
+
+    /// Re-lay out the node at `path` below `node`, returning the change in
+    /// height, or `None` if the path does not exist.
+    fn relayout_at(&self, node: &mut RenderNode, path: &[usize]) -> Option<f32> {
+        let Some((&index, rest)) = path.split_first() else {
+            let LayoutBox { x, y, width, height } = node.layout;
+            self.layout_node(node, x, y, width);
+            return Some(node.layout.height - height);
+        };
+
+        let child = node.children.get_mut(index)?;
+        if is_flex_row(&node.computed_style) || is_inline_level(child.computed_style.display) {
+            rest.iter().try_fold(&*child, |node, &index| node.children.get(index))?;
+            return self.relayout_at(node, &[]);
+        }
+        let delta = self.relayout_at(child, rest)?;
+        if delta != 0.0 {
+            for sibling in &mut node.children[index + 1..] {
+                translate_y(sibling, delta);
+            }
+            node.layout.height += delta;
+        }
+        Some(delta)
     }
 
-    /// Layout a single node.
+    /// Consult `provider` for the intrinsic size of images during layout.
+    pub fn set_image_provider(&mut self, provider: Option<Arc<dyn ImageProvider>>) {
+        self.image_provider = provider;
+    }
+
+    /// Base URL against which image sources are resolved before asking the
+    /// image provider.
+    pub fn set_base_url(&mut self, base_url: Option<String>) {
+        self.base_url = base_url;
+    }
+
+    /// Size reserved for an `<img>`: CSS `width`/`height`, then the `width`
+    /// and `height` attributes, then the provider's intrinsic size, then a
+    /// placeholder. A single given dimension keeps the intrinsic aspect ratio.
+    fn image_size(&self, node: &RenderNode) -> (f32, f32) {
+        let element = &node.element;
+        let dimension = |css: Option<f32>, attribute: &str| {
+            css.or_else(|| element.attribute(attribute).and_then(parse_dimension))
+        };
+        let width = dimension(node.computed_style.width, "width");
+        let height = dimension(node.computed_style.height, "height");
+
+        let intrinsic = || {
+            let src = element.attribute("src")?;
+            let url = match &self.base_url {
+                Some(base) => url::resolve(base, src),
+                None => src.to_string(),
+            };
+            self.image_provider.as_ref()?.intrinsic_size(&url)
+        };
+        match (width, height) {
+            (Some(width), Some(height)) => (width, height),
+            (width, height) => {
+                let (iw, ih) = intrinsic().unwrap_or(PLACEHOLDER_IMAGE_SIZE);
+                match (width, height) {
+                    (Some(width), None) if iw > 0.0 => (width, width * ih / iw),
+                    (None, Some(height)) if ih > 0.0 => (height * iw / ih, height),
+                    (width, height) => (width.unwrap_or(iw), height.unwrap_or(ih)),
+                }
+            },
+        }
+    }
+
+    /// Set the advance width assumed for every character when layout
+    /// measures text. Defaults to [`CHAR_WIDTH`].
+    pub fn set_char_width(&mut self, width: f32) {
+        self.char_width = width.max(0.1);
+    }
+
+    /// Width an inline-level node takes up before wrapping: its CSS width or
+    /// image size, otherwise its widest text line plus its children laid
+    /// end to end, and its horizontal padding and border.
+    fn intrinsic_width(&self, node: &RenderNode) -> f32 {
+        let border = used_border(&node.computed_style);
+        let padding = node.computed_style.padding;
+        let content = if node.element.tag == "img" {
+            self.image_size(node).0
+        } else if let Some(width) = node.computed_style.width {
+            width
+        } else {
+            let text = rendered_text(node).map_or(0, |text| {
+                text.split('\n').map(|line| line.chars().count()).max().unwrap_or(0)
+            });
+            let children: f32 = node
+                .children
+                .iter()
+                .map(|child| {
+                    let margin = child.computed_style.margin;
+                    self.intrinsic_width(child) + margin.left + margin.right
+                })
+                .sum();
+            text as f32 * self.char_width + children
+        };
+        content + padding.left + padding.right + border.left + border.right
+    }
+
+    /// Number of lines `text` occupies in a content box `width` wide: lines
+    /// break at `\n` and, for wrapping white-space, wherever a line runs past
+    /// the box edge.
+    fn text_line_count(&self, text: &str, width: f32, wraps: bool) -> usize {
+        let columns = (width / self.char_width).floor().max(1.0) as usize;
+        text.split('\n')
+            .map(|line| match line.chars().count() {
+                count if wraps && count > columns => count.div_ceil(columns),
+                _ => 1,
+            })
+            .sum()
+    }
+
+    /// Set the device width used for `width=device-width` viewports.
+    /// Defaults to the physical viewport width.
+    pub fn set_device_width(&mut self, width: Option<f32>) {
+        self.device_width = width;
+    }
+
+    /// Apply the page's viewport meta tag, or restore the default viewport
+    /// with `None`.
+    pub fn set_viewport_meta(&mut self, meta: Option<ViewportMeta>) {
+        self.viewport_meta = meta;
+    }
+
+    /// Width of the layout viewport in CSS pixels.
+    ///
+    /// Without a viewport meta tag this is the physical viewport width.
+    pub fn layout_width(&self) -> f32 {
+        let device_width = self.device_width.unwrap_or(self.viewport_width);
+        match self.viewport_meta {
+            Some(ViewportMeta { width: Some(ViewportWidth::DeviceWidth), .. }) => device_width,
+            Some(ViewportMeta { width: Some(ViewportWidth::Pixels(width)), .. }) => width,
+            Some(ViewportMeta { width: None, initial_scale: Some(scale) }) => device_width / scale,
+            _ => self.viewport_width,
+        }
+    }
+
+    /// Initial zoom factor requested by the viewport meta tag.
+    pub fn zoom(&self) -> f32 {
+        self.viewport_meta.and_then(|meta| meta.initial_scale).unwrap_or(1.0)
+    }
+
+    /// Layout a single node whose border box starts at `(x, y)` and is
+    /// `available_width` wide.
+    ///
+    /// Block children are stacked inside the content box, separated by their
+    /// vertical margins; adjacent margins collapse to the larger of the two.
+    /// Runs of inline-level children flow left to right at their intrinsic
+    /// width and wrap onto a new line when the next one would overflow.
     fn layout_node(&self, node: &mut RenderNode, x: f32, y: f32, available_width: f32) {
+        let border = used_border(&node.computed_style);
+        let padding = node.computed_style.padding;
         node.layout.x = x;
         node.layout.y = y;
         node.layout.width = available_width;
 
-        let mut child_y = y;
+        if node.element.tag == "img" {
+            let (width, height) = self.image_size(node);
+            node.layout.width = width + padding.left + padding.right + border.left + border.right;
+            node.layout.height = height + padding.top + padding.bottom + border.top + border.bottom;
+            return;
+        }
+
+        let content_x = x + border.left + padding.left;
+        let content_width =
+            (available_width - border.left - border.right - padding.left - padding.right).max(0.0);
+        let mut child_y = y + border.top + padding.top;
+        if is_flex_row(&node.computed_style) {
+            child_y += self.layout_flex_row(&mut node.children, content_x, child_y, content_width);
+            node.layout.height = child_y - y + padding.bottom + border.bottom;
+            return;
+        }
+        let mut previous_margin = None;
+        let mut line: Option<InlineLine> = None;
         for child in &mut node.children {
-            self.layout_node(child, x, child_y, available_width);
-            child_y += child.layout.height + 8.0; // Simple block layout
+            let margin = child.computed_style.margin;
+            if is_inline_level(child.computed_style.display) {
+                let max_width = (content_width - margin.left - margin.right).max(0.0);
+                let width = self.intrinsic_width(child).min(max_width);
+                let outer_width = width + margin.left + margin.right;
+                let line = line.get_or_insert_with(|| InlineLine {
+                    x:      0.0,
+                    top:    child_y + previous_margin.take().unwrap_or(0.0),
+                    height: 0.0,
+                });
+                if line.x > 0.0 && line.x + outer_width > content_width {
+                    line.top += line.height;
+                    line.x = 0.0;
+                    line.height = 0.0;
+                }
+                let child_x = content_x + line.x + margin.left;
+                self.layout_node(child, child_x, line.top + margin.top, width);
+                line.x += outer_width;
+                line.height = line.height.max(margin.top + child.layout.height + margin.bottom);
+                continue;
+            }
+            if let Some(line) = line.take() {
+                child_y = line.top + line.height;
+                previous_margin = Some(0.0);
+            }
+            child_y += previous_margin.map_or(margin.top, |bottom: f32| bottom.max(margin.top));
+            let width = (content_width - margin.left - margin.right).max(0.0);
+            self.layout_node(child, content_x + margin.left, child_y, width);
+            child_y += child.layout.height;
+            previous_margin = Some(margin.bottom);
+        }
+        if let Some(line) = line {
+            child_y = line.top + line.height;
+        }
+        child_y += previous_margin.unwrap_or(0.0);
+        if node.children.is_empty()
+            && let Some(text) = rendered_text(node)
+        {
+            let wraps = node.computed_style.white_space.wraps();
+            child_y += self.text_line_count(&text, content_width, wraps) as f32 * LINE_HEIGHT;
+        }
+
+        node.layout.height = child_y - y + padding.bottom + border.bottom;
+    }
+
+    /// Lay out the children of a row flex container side by side from
+    /// `(x, y)`, returning the height of the row.
+    ///
+    /// Children with a CSS width keep it; the remaining space is shared
+    /// equally among the others. Items do not wrap or shrink.
+    fn layout_flex_row(&self, children: &mut [RenderNode], x: f32, y: f32, width: f32) -> f32 {
+        let horizontal = |edges: BoxEdges| edges.left + edges.right;
+        let fixed_width = |child: &RenderNode| {
+            let style = &child.computed_style;
+            style
+                .width
+                .map(|width| width + horizontal(style.padding) + horizontal(used_border(style)))
+        };
+        let (fixed, flexible) =
+            children
+                .iter()
+                .fold((0.0, 0), |(fixed, flexible), child| match fixed_width(child) {
+                    Some(width) => {
+                        (fixed + width + horizontal(child.computed_style.margin), flexible)
+                    },
+                    None => (fixed, flexible + 1),
+                });
+        let share = if flexible > 0 {
+            (width - fixed).max(0.0) / flexible as f32
+        } else {
+            0.0
+        };
+
+        let (mut cursor, mut height) = (x, 0.0_f32);
+        for child in children {
+            let margin = child.computed_style.margin;
+            let child_width =
+                fixed_width(child).unwrap_or_else(|| (share - horizontal(margin)).max(0.0));
+            self.layout_node(child, cursor + margin.left, y + margin.top, child_width);
+            cursor += margin.left + child_width + margin.right;
+            height = height.max(margin.top + child.layout.height + margin.bottom);
+        }
+        height
+    }
+
+    /// Produce the display list for a laid-out render tree, in paint order.
+    pub fn paint(&self, tree: &RenderTree) -> Vec<DisplayItem> {
+        let mut items = Vec::new();
+        Self::paint_node(&tree.root, 1.0, &mut items);
+        items
+    }
+
+    /// Paint a node followed by its children.
+    ///
+    /// `opacity` is the product of the ancestors' opacities. Hidden nodes
+    /// paint nothing themselves, but visible descendants still paint.
+    fn paint_node(node: &RenderNode, opacity: f32, items: &mut Vec<DisplayItem>) {
+        let style = &node.computed_style;
+        let opacity = opacity * style.opacity;
+        let visible = style.visibility == Visibility::Visible && opacity > 0.0;
+
+        let background = style.background_color.with_opacity(opacity);
+        if visible && background.a > 0 {
+            items.push(DisplayItem::Rect { layout: node.layout.clone(), color: background });
+        }
+        if visible && style.border_style == BorderStyle::Solid {
+            let color = style.border_color.unwrap_or(style.color).with_opacity(opacity);
+            Self::paint_border(&node.layout, &style.border_width, color, items);
+        }
+
+        if visible && let Some(text) = rendered_text(node) {
+            items.push(DisplayItem::Text {
+                layout:      text_box(node, &text),
+                content:     text.to_string(),
+                color:       style.color.with_opacity(opacity),
+                font_weight: style.font_weight,
+                font_style:  style.font_style,
+            });
         }
 
-        node.layout.height = child_y - y;
+        for child in &node.children {
+            Self::paint_node(child, opacity, items);
+        }
+    }
+
+    /// Emit the four edges of a solid border drawn inside `layout`.
+    /// Zero-width edges are skipped.
+    fn paint_border(
+        layout: &LayoutBox,
+        widths: &BoxEdges,
+        color: Color,
+        items: &mut Vec<DisplayItem>,
+    ) {
+        let LayoutBox { x, y, width, height } = *layout;
+        let inner_height = (height - widths.top - widths.bottom).max(0.0);
+        let edges = [
+            (x, y, width, widths.top),
+            (x + width - widths.right, y + widths.top, widths.right, inner_height),
+            (x, y + height - widths.bottom, width, widths.bottom),
+            (x, y + widths.top, widths.left, inner_height),
+        ];
+        for (x, y, width, height) in edges {
+            if width > 0.0 && height > 0.0 {
+                items.push(DisplayItem::Rect { layout: LayoutBox { x, y, width, height }, color });
+            }
+        }
+    }
+
+    /// Rasterize the visible part of the render tree into an RGBA buffer at
+    /// viewport size, honoring the scroll offset.
+    ///
+    /// Returns `(width, height, pixels)` with four bytes per pixel, row-major.
+    /// Text is drawn as solid glyph blocks, which is enough for screenshots
+    /// and tab thumbnails.
+    pub fn render_to_rgba(&self, tree: &RenderTree) -> (u32, u32, Vec<u8>) {
+        self.rasterize(&self.paint(tree))
+    }
+
+    /// Rasterize a display list, as produced by [`paint`](Self::paint), the
+    /// same way as [`render_to_rgba`](Self::render_to_rgba).
+    pub fn rasterize(&self, display_list: &[DisplayItem]) -> (u32, u32, Vec<u8>) {
+        let width = self.viewport_width.max(0.0).round() as u32;
+        let height = self.viewport_height.max(0.0).round() as u32;
+        let mut canvas = Canvas::new(width, height, Color::WHITE);
+
+        for item in display_list {
+            match item {
+                DisplayItem::Rect { layout, color } => {
+                    let y = layout.y - self.scroll_y;
+                    canvas.fill_rect(layout.x, y, layout.width, layout.height, *color);
+                },
+                DisplayItem::Text { layout, content, color, .. } => {
+                    let positions = char_positions(layout, content);
+                    for (ch, (x, y)) in content.chars().zip(positions) {
+                        if ch.is_whitespace() {
+                            continue;
+                        }
+                        canvas.fill_rect(
+                            x + 1.0,
+                            y - self.scroll_y + 4.0,
+                            CHAR_WIDTH - 2.0,
+                            LINE_HEIGHT - 6.0,
+                            *color,
+                        );
+                    }
+                },
+            }
+        }
+
+        (canvas.width, canvas.height, canvas.pixels)
+    }
+
+    /// Find case-insensitive occurrences of `query` in the tree's text runs.
+    ///
+    /// Matches are returned in document order; the first one is marked as
+    /// the current match.
+    pub fn find(&self, tree: &RenderTree, query: &str) -> Vec<FindMatch> {
+        let query: Vec<char> = query.chars().map(fold_case).collect();
+        let mut matches = Vec::new();
+        if !query.is_empty() {
+            for (path, node) in tree.iter_with_paths() {
+                Self::find_in_node(node, &path, &query, &mut matches);
+            }
+        }
+        if let Some(first) = matches.first_mut() {
+            first.current = true;
+        }
+        matches
+    }
+
+    fn find_in_node(
+        node: &RenderNode,
+        path: &[usize],
+        query: &[char],
+        matches: &mut Vec<FindMatch>,
+    ) {
+        if let Some(content) = rendered_text(node) {
+            let text: Vec<char> = content.chars().map(fold_case).collect();
+            let text_layout = text_box(node, &content);
+            let positions = char_positions(&text_layout, &content);
+            let mut start = 0;
+            while start + query.len() <= text.len() {
+                if text[start..start + query.len()] == *query {
+                    let end = start + query.len();
+                    matches.push(FindMatch {
+                        path: path.to_vec(),
+                        start,
+                        end,
+                        layout: text_run_box(&text_layout, &positions, start, end),
+                        current: false,
+                    });
+                    start = end;
+                } else {
+                    start += 1;
+                }
+            }
+        }
+    }
+
+    /// Select the text between two positions, in either order.
+    pub fn set_selection(&mut self, start: TextPosition, end: TextPosition) {
+        self.selection = Some(if start <= end {
+            (start, end)
+        } else {
+            (end, start)
+        });
+    }
+
+    /// Clear the text selection.
+    pub fn clear_selection(&mut self) {
+        self.selection = None;
+    }
+
+    /// Current selection as ordered `(start, end)` positions.
+    pub fn selection(&self) -> Option<(&TextPosition, &TextPosition)> {
+        self.selection.as_ref().map(|(start, end)| (start, end))
+    }
+
+    /// Plain text of the selection in `tree`, with text runs from different
+    /// elements separated by newlines. `None` if nothing is selected.
+    pub fn selected_text(&self, tree: &RenderTree) -> Option<String> {
+        let (start, end) = self.selection.as_ref()?;
+        let mut runs = Vec::new();
+        // Pre-order visits paths in increasing order.
+        for (path, node) in tree.iter_with_paths() {
+            if path > end.path {
+                break;
+            }
+            if path < start.path {
+                continue;
+            }
+            let Some(content) = rendered_text(node) else {
+                continue;
+            };
+            let from = if path == start.path {
+                start.offset
+            } else {
+                0
+            };
+            let to = if path == end.path {
+                end.offset
+            } else {
+                usize::MAX
+            };
+            let run: String = content.chars().take(to).skip(from).collect();
+            if !run.is_empty() {
+                runs.push(run);
+            }
+        }
+        let text = runs.join("\n");
+        (!text.is_empty()).then_some(text)
+    }
+
+    /// Produce translucent highlight rects for find-in-page matches.
+    ///
+    /// The current match is drawn with an accent color.
+    pub fn highlight(&self, matches: &[FindMatch]) -> Vec<DisplayItem> {
+        matches
+            .iter()
+            .map(|m| DisplayItem::Rect {
+                layout: m.layout.clone(),
+                color:  if m.current {
+                    CURRENT_MATCH_COLOR
+                } else {
+                    HIGHLIGHT_COLOR
+                },
+            })
+            .collect()
+    }
+
+    /// Current vertical scroll offset (px).
+    pub fn scroll_offset(&self) -> f32 {
+        self.scroll_y
+    }
+
+    /// Set the vertical scroll offset (px). Negative offsets clamp to zero.
+    ///
+    /// Cancels any smooth scroll in progress.
+    pub fn set_scroll_offset(&mut self, y: f32) {
+        self.scroll_y = y.max(0.0);
+        self.scroll_target = None;
+    }
+
+    /// Scroll to `y`, either immediately or, with `smooth`, animated over the
+    /// following frames by [`advance_scroll`](Self::advance_scroll).
+    pub fn scroll_to(&mut self, y: f32, smooth: bool) {
+        if smooth {
+            self.scroll_target = Some(y.max(0.0));
+        } else {
+            self.set_scroll_offset(y);
+        }
+    }
+
+    /// Set the smooth-scrolling speed (px per second). Non-positive speeds
+    /// are ignored.
+    pub fn set_scroll_speed(&mut self, px_per_second: f32) {
+        if px_per_second > 0.0 {
+            self.scroll_speed = px_per_second;
+        }
+    }
+
+    /// Whether a smooth scroll is in progress.
+    pub fn is_scrolling(&self) -> bool {
+        self.scroll_target.is_some()
+    }
+
+    /// Advance a smooth scroll by one frame of `delta_ms` milliseconds.
+    ///
+    /// Returns `true` while the scroll is still in progress.
+    pub fn advance_scroll(&mut self, delta_ms: f64) -> bool {
+        let Some(target) = self.scroll_target else {
+            return false;
+        };
+        let step = self.scroll_speed * (delta_ms.max(0.0) / 1000.0) as f32;
+        let remaining = target - self.scroll_y;
+        if remaining.abs() <= step {
+            self.scroll_y = target;
+            self.scroll_target = None;
+            return false;
+        }
+        self.scroll_y += step.copysign(remaining);
+        true
+    }
+
+    /// Laid-out size of `tree`'s content, at least the viewport size, for
+    /// sizing scrollbars.
+    pub fn content_size(&self, tree: &RenderTree) -> (f32, f32) {
+        let (width, height) = tree.content_size();
+        (width.max(self.viewport_width), height.max(self.viewport_height))
+    }
+
+    /// Largest vertical scroll offset that still shows content.
+    pub fn max_scroll_offset(&self, tree: &RenderTree) -> f32 {
+        (tree.content_size().1 - self.viewport_height).max(0.0)
+    }
+
+    /// Current viewport size as `(width, height)`.
+    pub fn viewport_size(&self) -> (f32, f32) {
+        (self.viewport_width, self.viewport_height)
     }
 
     /// Update viewport size.
@@ -71,8 +805,1183 @@ impl RenderEngine {
     }
 }
 
+impl fmt::Debug for RenderEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RenderEngine")
+            .field("viewport_width", &self.viewport_width)
+            .field("viewport_height", &self.viewport_height)
+            .field("scroll_y", &self.scroll_y)
+            .field("has_image_provider", &self.image_provider.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
 impl Default for RenderEngine {
     fn default() -> Self {
         Self::new(1920.0, 1080.0)
     }
 }
+
+/// Grow a box by per-side edge sizes.
+fn outset(layout: &LayoutBox, edges: &BoxEdges) -> LayoutBox {
+    LayoutBox {
+        x:      layout.x - edges.left,
+        y:      layout.y - edges.top,
+        width:  layout.width + edges.left + edges.right,
+        height: layout.height + edges.top + edges.bottom,
+    }
+}
+
+/// Border widths that take up space: none unless a border style is set.
+fn used_border(style: &ComputedStyle) -> BoxEdges {
+    if style.border_style == BorderStyle::None {
+        BoxEdges::default()
+    } else {
+        style.border_width
+    }
+}
+
+/// Shrink a box by per-side edge sizes, never below zero size.
+fn inset(layout: &LayoutBox, edges: &BoxEdges) -> LayoutBox {
+    LayoutBox {
+        x:      layout.x + edges.left,
+        y:      layout.y + edges.top,
+        width:  (layout.width - edges.left - edges.right).max(0.0),
+        height: (layout.height - edges.top - edges.bottom).max(0.0),
+    }
+}
+
+/// Parse an HTML `width`/`height` attribute: a non-negative number of pixels.
+fn parse_dimension(value: &str) -> Option<f32> {
+    let value = value.trim();
+    parse_length(value)
+        .or_else(|| value.parse().ok())
+        .filter(|size: &f32| size.is_finite() && *size >= 0.0)
+}
+
+/// Move a laid-out subtree vertically.
+fn translate_y(node: &mut RenderNode, dy: f32) {
+    node.layout.y += dy;
+    for child in &mut node.children {
+        translate_y(child, dy);
+    }
+}
+
+/// Lowercase a character for case-insensitive matching, keeping one char per
+/// char so match offsets stay valid in the original text.
+fn fold_case(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// A node's text after white-space processing, if it has any visible text.
+fn rendered_text(node: &RenderNode) -> Option<Cow<'_, str>> {
+    let text = node.element.text_content.as_deref()?;
+    let text = node.computed_style.white_space.apply(text);
+    (!text.trim().is_empty()).then_some(text)
+}
+
+/// Box occupied by a node's text run.
+///
+/// Text whose widest line fits is placed according to `text-align`. Wrapping
+/// text wider than the node fills the node's box from the left; text that
+/// may not wrap overflows it.
+fn text_box(node: &RenderNode, text: &str) -> LayoutBox {
+    let layout = &node.layout;
+    let lines = text.split('\n').count();
+    let widest = text.split('\n').map(|line| line.chars().count()).max().unwrap_or(0);
+    let text_width = widest as f32 * CHAR_WIDTH;
+    if text_width > layout.width && node.computed_style.white_space.wraps() {
+        return layout.clone();
+    }
+    let slack = (layout.width - text_width).max(0.0);
+    let offset = match node.computed_style.text_align {
+        TextAlign::Left => 0.0,
+        TextAlign::Center => slack / 2.0,
+        TextAlign::Right => slack,
+    };
+    LayoutBox {
+        x:      layout.x + offset,
+        y:      layout.y,
+        width:  text_width,
+        height: lines as f32 * LINE_HEIGHT,
+    }
+}
+
+/// Top-left corner of each character cell of `text` within a text box.
+///
+/// Lines break at `\n` and wrap at the box width.
+fn char_positions(layout: &LayoutBox, text: &str) -> Vec<(f32, f32)> {
+    let columns = (layout.width / CHAR_WIDTH).floor().max(1.0) as usize;
+    let (mut column, mut row) = (0, 0);
+    let mut positions = Vec::new();
+    for ch in text.chars() {
+        if column == columns && ch != '\n' {
+            column = 0;
+            row += 1;
+        }
+        positions
+            .push((layout.x + column as f32 * CHAR_WIDTH, layout.y + row as f32 * LINE_HEIGHT));
+        if ch == '\n' {
+            column = 0;
+            row += 1;
+        } else {
+            column += 1;
+        }
+    }
+    positions
+}
+
+/// Box covering characters `start..end` of a text box. Runs that wrap are
+/// clipped to the end of their first line.
+fn text_run_box(
+    layout: &LayoutBox,
+    positions: &[(f32, f32)],
+    start: usize,
+    end: usize,
+) -> LayoutBox {
+    let (x, y) = positions[start];
+    let line_end = layout.x + layout.width.max(CHAR_WIDTH);
+    let width = ((end - start) as f32 * CHAR_WIDTH).min(line_end - x);
+    LayoutBox { x, y, width, height: LINE_HEIGHT }
+}
+
+/// Byte length of a `width` x `height` RGBA buffer, or `None` if it does not
+/// fit in `usize`.
+pub(crate) fn rgba_len(width: u32, height: u32) -> Option<usize> {
+    (width as usize).checked_mul(height as usize)?.checked_mul(4)
+}
+
+/// Downscale an RGBA buffer by averaging the source pixels covered by each
+/// destination pixel.
+///
+/// Returns an empty buffer if the target size does not fit in memory.
+pub(crate) fn downscale_rgba(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    target_width: u32,
+    target_height: u32,
+) -> Vec<u8> {
+    let Some(len) = rgba_len(target_width, target_height) else {
+        return Vec::new();
+    };
+    let mut out = Vec::with_capacity(len);
+    if width == 0 || height == 0 {
+        out.resize(len, 255);
+        return out;
+    }
+
+    let (width, height) = (width as u64, height as u64);
+    let (target_width, target_height) = (target_width as u64, target_height as u64);
+    for ty in 0..target_height {
+        let y0 = ty * height / target_height;
+        let y1 = ((ty + 1) * height / target_height).max(y0 + 1).min(height);
+        for tx in 0..target_width {
+            let x0 = tx * width / target_width;
+            let x1 = ((tx + 1) * width / target_width).max(x0 + 1).min(width);
+
+            let mut sum = [0u64; 4];
+            for sy in y0..y1 {
+                for sx in x0..x1 {
+                    let i = ((sy * width + sx) * 4) as usize;
+                    for (acc, value) in sum.iter_mut().zip(&pixels[i..i + 4]) {
+                        *acc += *value as u64;
+                    }
+                }
+            }
+            let count = (y1 - y0) * (x1 - x0);
+            out.extend(sum.iter().map(|acc| (acc / count) as u8));
+        }
+    }
+    out
+}
+
+impl RenderNode {
+    /// The node's descendants in pre-order (document order), excluding the
+    /// node itself.
+    pub fn descendants(&self) -> impl Iterator<Item = &RenderNode> {
+        let mut stack: Vec<&RenderNode> = self.children.iter().rev().collect();
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev());
+            Some(node)
+        })
+    }
+
+    /// The node's descendants in pre-order, each with its child-index path
+    /// relative to this node.
+    pub fn descendants_with_paths(&self) -> impl Iterator<Item = (Vec<usize>, &RenderNode)> {
+        let mut stack: Vec<(Vec<usize>, &RenderNode)> = self
+            .children
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(i, child)| (vec![i], child))
+            .collect();
+        std::iter::from_fn(move || {
+            let (path, node) = stack.pop()?;
+            stack.extend(node.children.iter().enumerate().rev().map(|(i, child)| {
+                let mut child_path = path.clone();
+                child_path.push(i);
+                (child_path, child)
+            }));
+            Some((path, node))
+        })
+    }
+}
+
+impl RenderTree {
+    /// All nodes in pre-order (document order), starting with the root.
+    pub fn iter(&self) -> impl Iterator<Item = &RenderNode> {
+        std::iter::once(&self.root).chain(self.root.descendants())
+    }
+
+    /// All nodes in pre-order, each with its child-index path from the root
+    /// (empty for the root itself).
+    pub fn iter_with_paths(&self) -> impl Iterator<Item = (Vec<usize>, &RenderNode)> {
+        std::iter::once((Vec::new(), &self.root)).chain(self.root.descendants_with_paths())
+    }
+
+    /// Width and height of the laid-out content: the extent of every box,
+    /// measured from the origin.
+    pub fn content_size(&self) -> (f32, f32) {
+        self.iter().fold((0.0_f32, 0.0_f32), |(width, height), node| {
+            let LayoutBox { x, y, width: w, height: h } = node.layout;
+            (width.max(x + w), height.max(y + h))
+        })
+    }
+
+    /// Visible text of the tree in document order, with each node's text
+    /// processed according to its `white-space` mode.
+    pub fn inner_text(&self) -> String {
+        self.iter()
+            .filter(|node| node.computed_style.visibility == Visibility::Visible)
+            .filter_map(rendered_text)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Box-model rectangles of every node in document order, color coded the
+    /// way devtools overlays are: margin, border, padding and content box for
+    /// each node, outermost first.
+    ///
+    /// A node's layout box is its border box.
+    pub fn layout_overlay(&self) -> Vec<(LayoutBox, Color)> {
+        let mut overlay = Vec::new();
+        for node in self.iter() {
+            let style = &node.computed_style;
+            let border = used_border(style);
+            let margin_box = outset(&node.layout, &style.margin);
+            let padding_box = inset(&node.layout, &border);
+            let content_box = inset(&padding_box, &style.padding);
+            overlay.extend([
+                (margin_box, OVERLAY_MARGIN),
+                (node.layout.clone(), OVERLAY_BORDER),
+                (padding_box, OVERLAY_PADDING),
+                (content_box, OVERLAY_CONTENT),
+            ]);
+        }
+        overlay
+    }
+
+    /// Find the first node, in document order, whose element has the given id.
+    pub fn find_by_id(&self, id: &str) -> Option<&RenderNode> {
+        self.iter().find(|node| node.element.attribute("id") == Some(id))
+    }
+}
+
+/// Line box being filled by a run of inline-level children, relative to the
+/// parent's content box horizontally.
+struct InlineLine {
+    x:      f32,
+    top:    f32,
+    height: f32,
+}
+
+/// Whether nodes with this display value flow inline rather than stacking.
+fn is_inline_level(display: Display) -> bool {
+    matches!(display, Display::Inline | Display::InlineBlock | Display::InlineFlex)
+}
+
+/// Whether a node with this style lays its children out side by side in a
+/// flex row.
+fn is_flex_row(style: &ComputedStyle) -> bool {
+    matches!(style.display, Display::Flex | Display::InlineFlex)
+        && style.flex_direction == FlexDirection::Row
+}
+
+/// RGBA pixel buffer used by the rasterizer.
+struct Canvas {
+    width:  u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    /// Create a canvas filled with a solid color, or an empty canvas if the
+    /// buffer would not fit in memory.
+    fn new(width: u32, height: u32, fill: Color) -> Self {
+        let Some(len) = rgba_len(width, height) else {
+            return Self { width: 0, height: 0, pixels: Vec::new() };
+        };
+        let pixels = [fill.r, fill.g, fill.b, fill.a].repeat(len / 4);
+        Self { width, height, pixels }
+    }
+
+    /// Fill a rectangle, alpha-blending over existing pixels.
+    fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Color) {
+        if color.a == 0 {
+            return;
+        }
+
+        let x0 = x.round().clamp(0.0, self.width as f32) as u32;
+        let x1 = (x + width).round().clamp(0.0, self.width as f32) as u32;
+        let y0 = y.round().clamp(0.0, self.height as f32) as u32;
+        let y1 = (y + height).round().clamp(0.0, self.height as f32) as u32;
+
+        let alpha = color.a as u32;
+        for py in y0..y1 {
+            for px in x0..x1 {
+                let i = (py as usize * self.width as usize + px as usize) * 4;
+                let pixel = &mut self.pixels[i..i + 4];
+                for (dst, src) in pixel.iter_mut().zip([color.r, color.g, color.b]) {
+                    *dst = ((src as u32 * alpha + *dst as u32 * (255 - alpha)) / 255) as u8;
+                }
+                pixel[3] = pixel[3].max(color.a);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::WhiteSpace;
+
+    fn leaf(tag: &str, style: ComputedStyle, layout: LayoutBox) -> RenderNode {
+        RenderNode {
+            element: Element::new(tag),
+            computed_style: style,
+            layout,
+            children: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_render_full_viewport_red_background() {
+        let engine = RenderEngine::new(16.0, 8.0);
+        let red = Color { r: 255, g: 0, b: 0, a: 255 };
+        let style = ComputedStyle { background_color: red, ..Default::default() };
+        let layout = LayoutBox { x: 0.0, y: 0.0, width: 16.0, height: 8.0 };
+        let tree = RenderTree { root: leaf("body", style, layout) };
+
+        let (width, height, pixels) = engine.render_to_rgba(&tree);
+
+        assert_eq!((width, height), (16, 8));
+        assert_eq!(pixels.len(), 16 * 8 * 4);
+        assert!(pixels.chunks(4).all(|p| p == [255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_oversized_buffers_are_empty_instead_of_overflowing() {
+        let engine = RenderEngine::new(u32::MAX as f32, u32::MAX as f32);
+        assert_eq!(engine.rasterize(&[]), (0, 0, Vec::new()));
+
+        assert!(downscale_rgba(&[], 0, 0, u32::MAX, u32::MAX).is_empty());
+        let pixels = [0, 0, 0, 255].repeat(4);
+        assert_eq!(downscale_rgba(&pixels, 2, 2, 1, 1), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_paint_emits_backgrounds_and_text() {
+        let engine = RenderEngine::default();
+        let blue = Color { r: 0, g: 0, b: 255, a: 255 };
+        let layout = LayoutBox { x: 0.0, y: 0.0, width: 100.0, height: 20.0 };
+        let mut root = leaf(
+            "div",
+            ComputedStyle { background_color: blue, ..Default::default() },
+            layout.clone(),
+        );
+        let mut text = leaf("p", ComputedStyle::default(), layout);
+        text.element.text_content = Some("Hi".into());
+        root.children.push(text);
+
+        let items = engine.paint(&RenderTree { root });
+
+        assert_eq!(items.len(), 2);
+        assert!(matches!(items[0], DisplayItem::Rect { color, .. } if color.b == 255));
+        assert!(matches!(&items[1], DisplayItem::Text { content, .. } if content == "Hi"));
+    }
+
+    #[test]
+    fn test_visibility_hidden_keeps_layout_unlike_display_none() {
+        use crate::{
+            style::StyleOrigin,
+            types::{CssRule, StyleSheet},
+        };
+
+        let body = Element::new("body")
+            .with_child(Element::new("div").with_attribute("class", "a").with_text("Secret"))
+            .with_child(Element::new("p").with_text("After"));
+        let document =
+            Document { title: String::new(), root: body, url: "https://example.com".into() };
+        let layout_with = |declaration: (&str, &str)| {
+            let sheet = StyleSheet {
+                rules: vec![CssRule {
+                    selector:     ".a".into(),
+                    declarations: vec![(declaration.0.into(), declaration.1.into())],
+                }],
+            };
+            let cascade = Cascade::new().with_sheet(StyleOrigin::Author, &sheet);
+            let engine = RenderEngine::default();
+            let mut tree = engine.build_styled_render_tree(&document, &cascade).unwrap();
+            engine.layout(&mut tree);
+            let texts: Vec<String> = engine
+                .paint(&tree)
+                .into_iter()
+                .filter_map(|item| match item {
+                    DisplayItem::Text { content, .. } => Some(content),
+                    DisplayItem::Rect { .. } => None,
+                })
+                .collect();
+            let after_y = tree.root.children.last().unwrap().layout.y;
+            (tree.root.children.len(), after_y, texts)
+        };
+
+        let (count, hidden_y, texts) = layout_with(("visibility", "hidden"));
+        assert_eq!(count, 2);
+        assert_eq!(texts, ["After"]);
+
+        let (count, none_y, texts) = layout_with(("display", "none"));
+        assert_eq!(count, 1);
+        assert_eq!(texts, ["After"]);
+        assert!(hidden_y > none_y);
+    }
+
+    #[test]
+    fn test_opacity_scales_painted_alpha() {
+        let engine = RenderEngine::default();
+        let layout = LayoutBox { x: 0.0, y: 0.0, width: 10.0, height: 10.0 };
+        let style =
+            ComputedStyle { background_color: Color::BLACK, opacity: 0.5, ..Default::default() };
+        let mut root = leaf("div", style.clone(), layout.clone());
+        root.children.push(leaf("div", style, layout));
+
+        let items = engine.paint(&RenderTree { root });
+        let alphas: Vec<u8> = items
+            .iter()
+            .map(|item| match item {
+                DisplayItem::Rect { color, .. } | DisplayItem::Text { color, .. } => color.a,
+            })
+            .collect();
+        assert_eq!(alphas, [128, 64]);
+    }
+
+    #[test]
+    fn test_paint_emits_border_edges() {
+        let engine = RenderEngine::default();
+        let style = ComputedStyle {
+            border_width: BoxEdges::uniform(2.0),
+            border_style: BorderStyle::Solid,
+            border_color: Color::parse("#000"),
+            ..Default::default()
+        };
+        let layout = LayoutBox { x: 10.0, y: 20.0, width: 100.0, height: 50.0 };
+
+        let items = engine.paint(&RenderTree { root: leaf("div", style, layout) });
+
+        let edges: Vec<(f32, f32, f32, f32)> = items
+            .iter()
+            .map(|item| match item {
+                DisplayItem::Rect { layout, color } if *color == Color::BLACK => {
+                    (layout.x, layout.y, layout.width, layout.height)
+                },
+                other => panic!("unexpected display item {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            edges,
+            [
+                (10.0, 20.0, 100.0, 2.0),
+                (108.0, 22.0, 2.0, 46.0),
+                (10.0, 68.0, 100.0, 2.0),
+                (10.0, 22.0, 2.0, 46.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_text_align_center_positions_run() {
+        let engine = RenderEngine::default();
+        let style = ComputedStyle { text_align: TextAlign::Center, ..Default::default() };
+        let mut node = leaf("p", style, LayoutBox { x: 10.0, y: 0.0, width: 400.0, height: 16.0 });
+        node.element.text_content = Some("Hello".into());
+        let tree = RenderTree { root: node };
+
+        let items = engine.paint(&tree);
+        let DisplayItem::Text { layout, .. } = &items[0] else {
+            panic!("expected a text item");
+        };
+        assert_eq!((layout.x, layout.width), (190.0, 40.0));
+        assert_eq!(engine.find(&tree, "llo")[0].layout.x, 206.0);
+    }
+
+    #[test]
+    fn test_white_space_pre_preserves_spaces_and_newlines() {
+        let engine = RenderEngine::default();
+        let layout = LayoutBox { x: 0.0, y: 0.0, width: 400.0, height: 16.0 };
+        let text = "a  b\nc";
+        let mut pre = leaf(
+            "pre",
+            ComputedStyle { white_space: WhiteSpace::Pre, ..Default::default() },
+            layout.clone(),
+        );
+        pre.element.text_content = Some(text.into());
+        let mut normal = leaf("p", ComputedStyle::default(), layout);
+        normal.element.text_content = Some(text.into());
+
+        let pre_tree = RenderTree { root: pre };
+        assert_eq!(pre_tree.inner_text(), "a  b\nc");
+        let DisplayItem::Text { layout, content, .. } = &engine.paint(&pre_tree)[0] else {
+            panic!("expected a text item");
+        };
+        assert_eq!(content, "a  b\nc");
+        assert_eq!(layout.height, 2.0 * LINE_HEIGHT);
+        let found = &engine.find(&pre_tree, "c")[0];
+        assert_eq!((found.layout.x, found.layout.y), (0.0, LINE_HEIGHT));
+
+        let normal_tree = RenderTree { root: normal };
+        assert_eq!(normal_tree.inner_text(), "a b c");
+        assert_eq!(engine.find(&normal_tree, "c")[0].layout.x, 4.0 * CHAR_WIDTH);
+    }
+
+    #[test]
+    fn test_render_text_draws_glyph_blocks() {
+        let engine = RenderEngine::new(32.0, 16.0);
+        let mut node = leaf(
+            "p",
+            ComputedStyle::default(),
+            LayoutBox { x: 0.0, y: 0.0, width: 32.0, height: 16.0 },
+        );
+        node.element.text_content = Some("ab".into());
+
+        let (width, _, pixels) = engine.render_to_rgba(&RenderTree { root: node });
+        let pixel = |x: u32, y: u32| {
+            let i = ((y * width + x) * 4) as usize;
+            [pixels[i], pixels[i + 1], pixels[i + 2], pixels[i + 3]]
+        };
+
+        assert_eq!(pixel(4, 8), [0, 0, 0, 255]);
+        assert_eq!(pixel(20, 8), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_find_matches_produce_highlight_rects() {
+        let engine = RenderEngine::default();
+        let layout = LayoutBox { x: 10.0, y: 20.0, width: 400.0, height: 16.0 };
+        let mut root = leaf("div", ComputedStyle::default(), layout.clone());
+        root.element.text_content = Some("Foo bar foo".into());
+        let mut child = leaf("p", ComputedStyle::default(), layout);
+        child.element.text_content = Some("food".into());
+        root.children.push(child);
+        let tree = RenderTree { root };
+
+        let matches = engine.find(&tree, "foo");
+        assert_eq!(matches.len(), 3);
+        assert_eq!(matches[2].path, vec![0]);
+        assert_eq!((matches[1].start, matches[1].end), (8, 11));
+        assert_eq!(matches[1].layout.x, 10.0 + 8.0 * CHAR_WIDTH);
+
+        let highlights = engine.highlight(&matches);
+        assert_eq!(highlights.len(), 3);
+        assert!(matches!(
+            highlights[0],
+            DisplayItem::Rect { color, .. } if color == CURRENT_MATCH_COLOR
+        ));
+        assert!(highlights[1..].iter().all(|item| matches!(
+            item,
+            DisplayItem::Rect { color, layout } if *color == HIGHLIGHT_COLOR && layout.width == 3.0 * CHAR_WIDTH
+        )));
+    }
+
+    #[test]
+    fn test_find_folds_query_and_text_alike() {
+        let engine = RenderEngine::default();
+        let layout = LayoutBox { x: 0.0, y: 0.0, width: 400.0, height: 16.0 };
+        let mut root = leaf("p", ComputedStyle::default(), layout);
+        // 'İ' lowercases to two chars, "i\u{307}".
+        root.element.text_content = Some("İstanbul istanbul".into());
+        let tree = RenderTree { root };
+
+        let matches = engine.find(&tree, "İSTANBUL");
+        let ranges: Vec<_> = matches.iter().map(|m| (m.start, m.end)).collect();
+        assert_eq!(ranges, [(0, 8), (9, 17)]);
+    }
+
+    #[test]
+    fn test_device_width_viewport_layout() {
+        let meta = Element::new("meta")
+            .with_attribute("name", "viewport")
+            .with_attribute("content", "width=device-width, initial-scale=1");
+        let root = Element::new("html")
+            .with_child(Element::new("head").with_child(meta))
+            .with_child(Element::new("body").with_child(Element::new("p")));
+        let document = Document { title: String::new(), root, url: String::new() };
+
+        let mut engine = RenderEngine::default();
+        engine.set_device_width(Some(375.0));
+        let mut tree = engine.build_render_tree(&document).unwrap();
+
+        engine.layout(&mut tree);
+        assert_eq!(tree.root.layout.width, 1920.0);
+
+        engine.set_viewport_meta(document.viewport_meta());
+        engine.layout(&mut tree);
+        assert_eq!(engine.layout_width(), 375.0);
+        assert_eq!(engine.zoom(), 1.0);
+        assert_eq!(tree.root.layout.width, 375.0);
+        assert_eq!(tree.root.children[1].children[0].layout.width, 375.0);
+    }
+
+    #[test]
+    fn test_smooth_scroll_converges() {
+        let mut engine = RenderEngine::default();
+        engine.scroll_to(500.0, false);
+        assert_eq!(engine.scroll_offset(), 500.0);
+        assert!(!engine.is_scrolling());
+
+        engine.set_scroll_speed(1000.0);
+        engine.scroll_to(100.0, true);
+        let mut frames = 0;
+        let mut last = engine.scroll_offset();
+        while engine.advance_scroll(100.0) {
+            frames += 1;
+            assert!(engine.scroll_offset() < last);
+            last = engine.scroll_offset();
+        }
+        assert_eq!(frames, 3);
+        assert_eq!(engine.scroll_offset(), 100.0);
+        assert!(!engine.is_scrolling());
+
+        // A direct offset change cancels the animation.
+        engine.scroll_to(900.0, true);
+        engine.advance_scroll(16.0);
+        engine.set_scroll_offset(0.0);
+        assert!(!engine.advance_scroll(16.0));
+        assert_eq!(engine.scroll_offset(), 0.0);
+    }
+
+    #[test]
+    fn test_relayout_subtree_matches_full_layout() {
+        fn page(extra_items: usize) -> Document {
+            let list = (0..2 + extra_items)
+                .fold(Element::new("ul"), |ul, _| ul.with_child(Element::new("li")));
+            let body = Element::new("body")
+                .with_child(Element::new("h1"))
+                .with_child(Element::new("section").with_child(list))
+                .with_child(Element::new("p"))
+                .with_child(Element::new("footer").with_child(Element::new("p")));
+            Document {
+                title: String::new(),
+                root:  Element::new("html").with_child(body),
+                url:   "https://example.com/".into(),
+            }
+        }
+        fn boxes(node: &RenderNode, out: &mut Vec<(f32, f32, f32, f32)>) {
+            let LayoutBox { x, y, width, height } = node.layout;
+            out.push((x, y, width, height));
+            node.children.iter().for_each(|child| boxes(child, out));
+        }
+
+        let mut engine = RenderEngine::new(800.0, 600.0);
+        let cascade = Cascade::new();
+        let mut expected = engine.build_styled_render_tree(&page(3), &cascade).unwrap();
+        engine.layout(&mut expected);
+
+        // Lay out the old page, then swap in the changed list unlaid.
+        let mut incremental = engine.build_styled_render_tree(&page(0), &cascade).unwrap();
+        engine.layout(&mut incremental);
+        let path = [0, 1, 0];
+        let changed = expected.root.children[0].children[1].children[0].clone();
+        let list = &mut incremental.root.children[0].children[1].children[0];
+        *list = RenderNode {
+            layout: list.layout.clone(),
+            children: changed
+                .children
+                .into_iter()
+                .map(|child| RenderNode { layout: LayoutBox::default(), ..child })
+                .collect(),
+            ..changed
+        };
+        engine.relayout_subtree(&mut incremental, &path).unwrap();
+
+        let (mut want, mut got) = (Vec::new(), Vec::new());
+        boxes(&expected.root, &mut want);
+        boxes(&incremental.root, &mut got);
+        assert_eq!(got, want);
+        assert!(engine.relayout_subtree(&mut incremental, &[0, 9]).is_err());
+    }
+
+    #[test]
+    fn test_image_sizing_from_attributes_and_provider() {
+        struct Sizes;
+        impl ImageProvider for Sizes {
+            fn intrinsic_size(&self, url: &str) -> Option<(f32, f32)> {
+                (url == "https://example.com/img/cat.png").then_some((320.0, 240.0))
+            }
+        }
+
+        let img = |src: &str| Element::new("img").with_attribute("src", src);
+        let body = Element::new("body")
+            .with_child(
+                img("cat.png").with_attribute("width", "100").with_attribute("height", "50"),
+            )
+            .with_child(img("cat.png"))
+            .with_child(img("cat.png").with_attribute("width", "160px"))
+            .with_child(img("missing.png"));
+        let document = Document {
+            title: String::new(),
+            root:  Element::new("html").with_child(body),
+            url:   "https://example.com/img/page.html".into(),
+        };
+
+        let mut engine = RenderEngine::new(800.0, 600.0);
+        engine.set_image_provider(Some(Arc::new(Sizes)));
+        engine.set_base_url(Some(document.base_url()));
+        let mut tree = engine.build_render_tree(&document).unwrap();
+        engine.layout(&mut tree);
+
+        let sizes: Vec<_> = tree.root.children[0]
+            .children
+            .iter()
+            .map(|node| (node.layout.width, node.layout.height))
+            .collect();
+        assert_eq!(
+            sizes,
+            [
+                (100.0, 50.0),
+                (320.0, 240.0),
+                (160.0, 120.0),
+                PLACEHOLDER_IMAGE_SIZE
+            ]
+        );
+        let second = &tree.root.children[0].children[1];
+        assert_eq!(second.layout.y, tree.root.children[0].children[0].layout.y + 50.0);
+    }
+
+    #[test]
+    fn test_layout_overlay_box_model() {
+        let style = ComputedStyle {
+            margin: BoxEdges::uniform(10.0),
+            border_width: BoxEdges::uniform(2.0),
+            border_style: BorderStyle::Solid,
+            padding: BoxEdges { top: 4.0, right: 8.0, bottom: 4.0, left: 8.0 },
+            ..Default::default()
+        };
+        let layout = LayoutBox { x: 20.0, y: 20.0, width: 100.0, height: 50.0 };
+        let tree = RenderTree { root: leaf("div", style, layout) };
+
+        let overlay = tree.layout_overlay();
+        let boxes: Vec<_> = overlay.iter().map(|(b, _)| (b.x, b.y, b.width, b.height)).collect();
+        assert_eq!(
+            boxes,
+            [
+                (10.0, 10.0, 120.0, 70.0),
+                (20.0, 20.0, 100.0, 50.0),
+                (22.0, 22.0, 96.0, 46.0),
+                (30.0, 26.0, 80.0, 38.0),
+            ]
+        );
+        let colors: Vec<_> = overlay.iter().map(|(_, color)| *color).collect();
+        assert_eq!(
+            colors,
+            [
+                OVERLAY_MARGIN,
+                OVERLAY_BORDER,
+                OVERLAY_PADDING,
+                OVERLAY_CONTENT
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_tree_iterators() {
+        let node = |tag: &str, children: Vec<RenderNode>| RenderNode {
+            children,
+            ..leaf(tag, ComputedStyle::default(), LayoutBox::default())
+        };
+        let tree = RenderTree {
+            root: node(
+                "html",
+                vec![
+                    node("body", vec![node("h1", vec![]), node("ul", vec![node("li", vec![])])]),
+                    node("footer", vec![]),
+                ],
+            ),
+        };
+
+        let tags: Vec<_> = tree.iter().map(|n| n.element.tag.as_str()).collect();
+        assert_eq!(tags, ["html", "body", "h1", "ul", "li", "footer"]);
+        assert_eq!(tree.iter().count(), 6);
+        assert_eq!(tree.root.children[0].descendants().count(), 3);
+
+        let paths: Vec<_> = tree.iter_with_paths().map(|(path, _)| path).collect();
+        assert_eq!(
+            paths,
+            [
+                vec![],
+                vec![0],
+                vec![0, 0],
+                vec![0, 1],
+                vec![0, 1, 0],
+                vec![1]
+            ]
+        );
+        let (path, li) = tree.root.children[0].descendants_with_paths().last().unwrap();
+        assert_eq!((path, li.element.tag.as_str()), (vec![1, 0], "li"));
+    }
+
+    #[test]
+    fn test_content_size_of_tall_document() {
+        let body = (0..200).fold(Element::new("body"), |body, _| {
+            body.with_child(Element::new("p").with_text("Line"))
+        });
+        let document = Document {
+            title: String::new(),
+            root:  Element::new("html").with_child(body),
+            url:   String::new(),
+        };
+        let engine = RenderEngine::new(800.0, 600.0);
+        let mut tree = engine.build_render_tree(&document).unwrap();
+        engine.layout(&mut tree);
+
+        let (width, height) = tree.content_size();
+        assert_eq!(width, 800.0);
+        assert!(height > 600.0);
+        assert_eq!(engine.content_size(&tree), (width, height));
+        assert_eq!(engine.max_scroll_offset(&tree), height - 600.0);
+
+        let short = RenderTree { root: leaf("p", ComputedStyle::default(), LayoutBox::default()) };
+        assert_eq!(engine.content_size(&short), (800.0, 600.0));
+        assert_eq!(engine.max_scroll_offset(&short), 0.0);
+    }
+
+    #[test]
+    fn test_identical_siblings_share_computed_style() {
+        use crate::{css::CssParser, style::StyleOrigin};
+
+        let list = (0..50).fold(Element::new("ul"), |list, i| {
+            list.with_child(
+                Element::new("li").with_attribute("class", "x").with_text(i.to_string()),
+            )
+        });
+        let document = Document {
+            title: String::new(),
+            root:  Element::new("body").with_child(list),
+            url:   "https://example.com".into(),
+        };
+        let sheet =
+            CssParser::parse("ul { color: red } .x { padding: 2px; font-size: 2em }").unwrap();
+
+        let engine = RenderEngine::default();
+        let cascade = Cascade::new().with_sheet(StyleOrigin::Author, &sheet);
+        let tree = engine.build_styled_render_tree(&document, &cascade).unwrap();
+        assert_eq!(engine.style_cache_hits.load(Ordering::Relaxed), 49);
+        let items = &tree.root.children[0].children;
+        assert!(items.iter().all(|item| item.computed_style.font_size == 32.0
+            && item.computed_style.padding.left == 2.0
+            && item.computed_style.color == items[0].computed_style.color));
+
+        // Sibling selectors can style identical elements differently.
+        let sheet = CssParser::parse(".x + .x { padding: 1px }").unwrap();
+        let cascade = Cascade::new().with_sheet(StyleOrigin::Author, &sheet);
+        let engine = RenderEngine::default();
+        let tree = engine.build_styled_render_tree(&document, &cascade).unwrap();
+        assert_eq!(engine.style_cache_hits.load(Ordering::Relaxed), 0);
+        let items = &tree.root.children[0].children;
+        assert_eq!(
+            (items[0].computed_style.padding.left, items[1].computed_style.padding.left),
+            (0.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn test_render_timed_reports_stage_times() {
+        use crate::{css::CssParser, style::StyleOrigin};
+
+        let body = (0..200).fold(Element::new("body"), |body, i| {
+            body.with_child(
+                Element::new("div")
+                    .with_attribute(
+                        "class",
+                        if i % 2 == 0 {
+                            "even"
+                        } else {
+                            "odd"
+                        },
+                    )
+                    .with_child(Element::new("p").with_text(format!("paragraph {i}"))),
+            )
+        });
+        let document =
+            Document { title: String::new(), root: body, url: "https://example.com".into() };
+        let sheet = CssParser::parse(".even { background-color: red } p { padding: 4px }").unwrap();
+        let cascade = Cascade::new().with_sheet(StyleOrigin::Author, &sheet);
+
+        let engine = RenderEngine::default();
+        let (tree, display_list, timing) = engine.render_timed(&document, &cascade).unwrap();
+        assert_eq!(tree.root.children.len(), 200);
+        assert!(!display_list.is_empty());
+        assert!(timing.style_ms >= 0.0 && timing.layout_ms >= 0.0 && timing.paint_ms >= 0.0);
+        assert!(timing.style_ms + timing.layout_ms + timing.paint_ms > 0.0);
+    }
+
+    #[test]
+    fn test_apply_styles_fills_computed_styles() {
+        use crate::css::CssParser;
+
+        let document = Document {
+            title: String::new(),
+            root:  Element::new("body").with_child(
+                Element::new("div")
+                    .with_attribute("class", "row")
+                    .with_child(Element::new("p").with_attribute("id", "lead"))
+                    .with_child(Element::new("p")),
+            ),
+            url:   "https://example.com".into(),
+        };
+        let sheet = CssParser::parse(
+            "div { display: block; width: 100px } .row { display: flex; width: 200px } \
+             div p { color: red; height: 20px } #lead { color: blue } \
+             body { background-color: #00ff00 }",
+        )
+        .unwrap();
+
+        let engine = RenderEngine::default();
+        let mut tree = engine.build_render_tree(&document).unwrap();
+        engine.apply_styles(&mut tree.root, &sheet);
+
+        assert_eq!(tree.root.computed_style.background_color, Color { r: 0, g: 255, b: 0, a: 255 });
+        let row = &tree.root.children[0];
+        assert_eq!(row.computed_style.display, Display::Flex);
+        assert_eq!(row.computed_style.width, Some(200.0));
+        let (lead, other) = (&row.children[0], &row.children[1]);
+        assert_eq!(lead.computed_style.color, Color { r: 0, g: 0, b: 255, a: 255 });
+        assert_eq!(other.computed_style.color, Color { r: 255, g: 0, b: 0, a: 255 });
+        assert_eq!(other.computed_style.height, Some(20.0));
+        assert_eq!(other.computed_style.display, Display::Block);
+    }
+
+    #[test]
+    fn test_box_model_offsets_child_by_margin_padding_and_border() {
+        use crate::types::{CssRule, StyleSheet};
+
+        let rule = |selector: &str, declarations: &[(&str, &str)]| CssRule {
+            selector:     selector.into(),
+            declarations: declarations
+                .iter()
+                .map(|(p, v)| (p.to_string(), v.to_string()))
+                .collect(),
+        };
+        let sheet = StyleSheet {
+            rules: vec![
+                rule("div", &[("padding", "20px"), ("border", "2px solid black")]),
+                rule("p", &[("margin", "10px"), ("padding", "5px")]),
+                rule(".wide", &[("margin-top", "30px")]),
+            ],
+        };
+        let document = Document {
+            title: String::new(),
+            root:  Element::new("div")
+                .with_child(Element::new("p").with_text("One"))
+                .with_child(Element::new("p").with_attribute("class", "wide").with_text("Two")),
+            url:   String::new(),
+        };
+        let cascade = Cascade::new().with_sheet(StyleOrigin::Author, &sheet);
+        let engine = RenderEngine::new(400.0, 300.0);
+        let mut tree = engine.build_styled_render_tree(&document, &cascade).unwrap();
+        engine.layout(&mut tree);
+
+        let boxes: Vec<_> = tree
+            .iter()
+            .map(|node| (node.layout.x, node.layout.y, node.layout.width, node.layout.height))
+            .collect();
+        // Content box of the div starts at 22px; the first paragraph sits a
+        // 10px margin inside it, the second 30px below the first (the larger
+        // of the adjoining 10px and 30px margins).
+        let first = (32.0, 32.0, 336.0, LINE_HEIGHT + 10.0);
+        let second_y = first.1 + first.3 + 30.0;
+        let div_height = second_y + LINE_HEIGHT + 10.0 + 10.0 + 22.0;
+        assert_eq!(
+            boxes,
+            [
+                (0.0, 0.0, 400.0, div_height),
+                first,
+                (32.0, second_y, 336.0, LINE_HEIGHT + 10.0),
+            ]
+        );
+    }
+
+    fn styled_tree(engine: &RenderEngine, root: Element, css: &str) -> RenderTree {
+        use crate::css::CssParser;
+
+        let sheet = CssParser::parse(css).unwrap();
+        let document = Document { title: String::new(), root, url: String::new() };
+        let cascade = Cascade::new().with_sheet(StyleOrigin::Author, &sheet);
+        let mut tree = engine.build_styled_render_tree(&document, &cascade).unwrap();
+        engine.layout(&mut tree);
+        tree
+    }
+
+    #[test]
+    fn test_inline_children_flow_on_one_line() {
+        let root = ["one", "two", "three"]
+            .into_iter()
+            .fold(Element::new("p"), |p, text| p.with_child(Element::new("span").with_text(text)));
+        let css = "span { display: inline }";
+
+        let engine = RenderEngine::new(400.0, 300.0);
+        let tree = styled_tree(&engine, root.clone(), css);
+        let boxes: Vec<_> = tree.root.children.iter().map(|n| (n.layout.x, n.layout.y)).collect();
+        assert_eq!(boxes, [(0.0, 0.0), (3.0 * CHAR_WIDTH, 0.0), (6.0 * CHAR_WIDTH, 0.0)]);
+        assert_eq!(tree.root.layout.height, LINE_HEIGHT);
+
+        // At 10px per character "onetwo" no longer fits in 50px.
+        let mut engine = RenderEngine::new(50.0, 300.0);
+        engine.set_char_width(10.0);
+        let tree = styled_tree(&engine, root, css);
+        let ys: Vec<_> = tree.root.children.iter().map(|n| n.layout.y).collect();
+        assert_eq!(ys, [0.0, LINE_HEIGHT, 2.0 * LINE_HEIGHT]);
+    }
+
+    #[test]
+    fn test_long_text_wraps_onto_multiple_lines() {
+        let text = "word ".repeat(30);
+        let root = Element::new("div")
+            .with_child(Element::new("p").with_text(text.trim()))
+            .with_child(Element::new("p").with_text("After"));
+
+        // 149 characters in 50 columns of 8px: three lines.
+        let engine = RenderEngine::new(400.0, 300.0);
+        let tree = styled_tree(&engine, root, "");
+        let ys: Vec<_> = tree.root.children.iter().map(|n| n.layout.y).collect();
+        assert_eq!(ys, [0.0, 3.0 * LINE_HEIGHT]);
+        assert_eq!(tree.root.layout.height, 4.0 * LINE_HEIGHT);
+    }
+
+    #[test]
+    fn test_flex_row_shares_width_among_children() {
+        let root = (0..3).fold(Element::new("div"), |div, i| {
+            div.with_child(Element::new("p").with_attribute("class", format!("p{i}")))
+        });
+        let engine = RenderEngine::new(300.0, 200.0);
+
+        let tree = styled_tree(&engine, root.clone(), "div { display: flex } p { height: 0 }");
+        let columns: Vec<_> = tree
+            .root
+            .children
+            .iter()
+            .map(|n| (n.layout.x, n.layout.y, n.layout.width))
+            .collect();
+        assert_eq!(columns, [(0.0, 0.0, 100.0), (100.0, 0.0, 100.0), (200.0, 0.0, 100.0)]);
+        let total: f32 = tree.root.children.iter().map(|n| n.layout.width).sum();
+        assert_eq!(total, tree.root.layout.width);
+
+        let tree = styled_tree(
+            &engine,
+            root,
+            "div { display: flex } .p0 { width: 40px; padding: 5px } p { margin: 0 10px }",
+        );
+        let columns: Vec<_> =
+            tree.root.children.iter().map(|n| (n.layout.x, n.layout.width)).collect();
+        assert_eq!(columns, [(10.0, 50.0), (80.0, 95.0), (195.0, 95.0)]);
+    }
+
+    #[test]
+    fn test_relayout_subtree_in_flex_row_and_inline_line() {
+        fn boxes(node: &RenderNode, out: &mut Vec<(f32, f32, f32, f32)>) {
+            let LayoutBox { x, y, width, height } = node.layout;
+            out.push((x, y, width, height));
+            node.children.iter().for_each(|child| boxes(child, out));
+        }
+        // Lay out `before`, swap in the child of `after` at `index` and re-lay
+        // out only that child.
+        let check = |before: Element, after: Element, css: &str, index: usize| {
+            let mut engine = RenderEngine::new(300.0, 200.0);
+            let expected = styled_tree(&engine, after, css);
+            let mut incremental = styled_tree(&engine, before, css);
+            let changed = expected.root.children[index].clone();
+            let node = &mut incremental.root.children[index];
+            *node = RenderNode { layout: node.layout.clone(), ..changed };
+            engine.relayout_subtree(&mut incremental, &[index]).unwrap();
+
+            let (mut want, mut got) = (Vec::new(), Vec::new());
+            boxes(&expected.root, &mut want);
+            boxes(&incremental.root, &mut got);
+            assert_eq!(got, want);
+        };
+        let row = |first: &str| {
+            [first, "b", "c"].into_iter().fold(Element::new("div"), |div, text| {
+                div.with_child(Element::new("p").with_text(text))
+            })
+        };
+        let line = |first: &str| {
+            [first, "two", "three"].into_iter().fold(Element::new("p"), |p, text| {
+                p.with_child(Element::new("span").with_text(text))
+            })
+        };
+
+        // A flex item growing taller must not push the items beside it down.
+        let long = "word ".repeat(6);
+        check(row("a"), row(long.trim()), "div { display: flex }", 0);
+        // An inline box growing wider moves the rest of its line sideways.
+        check(line("one"), line("one more word"), "span { display: inline }", 0);
+    }
+
+    #[test]
+    fn test_flex_direction_column_stacks_children() {
+        let root = Element::new("div")
+            .with_child(Element::new("p").with_text("One"))
+            .with_child(Element::new("p").with_text("Two"));
+        let engine = RenderEngine::new(300.0, 200.0);
+        let tree =
+            styled_tree(&engine, root, "div { display: flex; flex-direction: column-reverse }");
+
+        assert_eq!(tree.root.computed_style.flex_direction, FlexDirection::Column);
+        let ys: Vec<_> = tree.root.children.iter().map(|n| n.layout.y).collect();
+        assert_eq!(ys, [0.0, LINE_HEIGHT]);
+    }
+
+    #[test]
+    fn test_paint_order_of_styled_tree() {
+        let root = Element::new("div")
+            .with_child(Element::new("p").with_attribute("class", "note").with_text("First"))
+            .with_child(Element::new("p").with_text("Second"));
+        let engine = RenderEngine::new(200.0, 100.0);
+        let tree = styled_tree(
+            &engine,
+            root,
+            "div { background-color: #eee } .note { background-color: yellow; color: navy }",
+        );
+
+        let items: Vec<_> = engine
+            .paint(&tree)
+            .into_iter()
+            .map(|item| match item {
+                DisplayItem::Rect { layout, color } => (format!("rect {color:?}"), layout.y),
+                DisplayItem::Text { layout, content, .. } => (format!("text {content}"), layout.y),
+            })
+            .collect();
+        let grey = Color { r: 238, g: 238, b: 238, a: 255 };
+        let yellow = Color { r: 255, g: 255, b: 0, a: 255 };
+        assert_eq!(
+            items,
+            [
+                (format!("rect {grey:?}"), 0.0),
+                (format!("rect {yellow:?}"), 0.0),
+                ("text First".to_string(), 0.0),
+                ("text Second".to_string(), LINE_HEIGHT),
+            ]
+        );
+    }
+}
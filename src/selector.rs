@@ -0,0 +1,549 @@
+//! Compiled CSS selectors.
+
+use std::{borrow::Cow, iter::Peekable, ptr, str::Chars};
+
+use crate::{
+    css::split_selector_list,
+    errors::{BrowserError, BrowserResult},
+    types::Element,
+};
+
+/// A compiled complex selector such as `div.note > p#intro`.
+///
+/// Compound selectors are stored left to right with the combinator that
+/// joins each one to the next; specificity is computed once at parse time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selector {
+    compounds:   Vec<Compound>,
+    combinators: Vec<Combinator>,
+    specificity: (u32, u32, u32),
+}
+
+/// Attributes whose values HTML compares ASCII case-insensitively in
+/// attribute selectors.
+const CASE_INSENSITIVE_ATTRIBUTES: &[&str] = &[
+    "accept",
+    "accept-charset",
+    "align",
+    "charset",
+    "checked",
+    "clear",
+    "compact",
+    "dir",
+    "disabled",
+    "enctype",
+    "frame",
+    "hreflang",
+    "http-equiv",
+    "lang",
+    "language",
+    "media",
+    "method",
+    "multiple",
+    "nowrap",
+    "readonly",
+    "rel",
+    "rev",
+    "rules",
+    "scope",
+    "selected",
+    "shape",
+    "target",
+    "type",
+    "valign",
+    "valuetype",
+];
+
+/// A compound selector: an optional type plus id, class, attribute and
+/// `:root` conditions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Compound {
+    tag:        Option<String>,
+    ids:        Vec<String>,
+    classes:    Vec<String>,
+    attributes: Vec<AttributeSelector>,
+    root:       bool,
+}
+
+/// An attribute condition such as `[type="text"]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AttributeSelector {
+    name:             String,
+    operator:         AttributeOperator,
+    value:            String,
+    case_insensitive: bool,
+}
+
+/// How an attribute selector compares the attribute value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AttributeOperator {
+    /// `[attr]`
+    Exists,
+    /// `[attr=value]`
+    Equals,
+    /// `[attr^=value]`
+    Prefix,
+    /// `[attr$=value]`
+    Suffix,
+    /// `[attr*=value]`
+    Substring,
+}
+
+/// Relationship between two compound selectors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Combinator {
+    /// Whitespace: any ancestor.
+    Descendant,
+    /// `>`: the parent.
+    Child,
+    /// `+`: the immediately preceding sibling.
+    NextSibling,
+    /// `~`: any preceding sibling.
+    SubsequentSibling,
+}
+
+impl Selector {
+    /// Compile a selector. Supports type, universal, class, id, attribute and
+    /// `:root` selectors joined by descendant, child (`>`), adjacent sibling (`+`) and
+    /// general sibling (`~`) combinators.
+    pub fn parse(selector: &str) -> BrowserResult<Self> {
+        let invalid = || BrowserError::Parse(format!("Invalid selector: {selector}"));
+        let mut compounds = Vec::new();
+        let mut combinators = Vec::new();
+        let mut chars = selector.trim().chars().peekable();
+
+        loop {
+            let mut compound = Compound::default();
+            let mut empty = true;
+            if chars.peek() == Some(&'*') {
+                chars.next();
+                empty = false;
+            } else if chars.peek().is_some_and(|&c| is_ident_char(c)) {
+                compound.tag = Some(take_ident(&mut chars).to_ascii_lowercase());
+                empty = false;
+            }
+            while let Some(&marker) = chars.peek().filter(|c| matches!(c, '.' | '#' | '[' | ':')) {
+                chars.next();
+                match marker {
+                    '.' | '#' => {
+                        let name = take_ident(&mut chars);
+                        if name.is_empty() {
+                            return Err(invalid());
+                        }
+                        let list = if marker == '.' {
+                            &mut compound.classes
+                        } else {
+                            &mut compound.ids
+                        };
+                        list.push(name);
+                    },
+                    ':' => {
+                        if !take_ident(&mut chars).eq_ignore_ascii_case("root") {
+                            return Err(invalid());
+                        }
+                        compound.root = true;
+                    },
+                    _ => {
+                        let attribute = AttributeSelector::parse(&mut chars).ok_or_else(invalid)?;
+                        compound.attributes.push(attribute);
+                    },
+                }
+                empty = false;
+            }
+            if empty {
+                return Err(invalid());
+            }
+            compounds.push(compound);
+
+            let spaced = chars.peek().is_some_and(|c| c.is_whitespace());
+            skip_whitespace(&mut chars);
+            let combinator = match chars.peek() {
+                None => break,
+                Some('>') => Combinator::Child,
+                Some('+') => Combinator::NextSibling,
+                Some('~') => Combinator::SubsequentSibling,
+                Some(_) if spaced => {
+                    combinators.push(Combinator::Descendant);
+                    continue;
+                },
+                Some(_) => return Err(invalid()),
+            };
+            chars.next();
+            skip_whitespace(&mut chars);
+            combinators.push(combinator);
+        }
+
+        let specificity = compounds.iter().fold((0, 0, 0), |(a, b, c), compound| {
+            (
+                a + compound.ids.len() as u32,
+                b + (compound.classes.len() + compound.attributes.len()) as u32
+                    + u32::from(compound.root),
+                c + u32::from(compound.tag.is_some()),
+            )
+        });
+        Ok(Self { compounds, combinators, specificity })
+    }
+
+    /// `(id, class, type)` specificity.
+    pub fn specificity(&self) -> (u32, u32, u32) {
+        self.specificity
+    }
+
+    /// Whether matching depends on an element's siblings (`+` or `~`).
+    pub(crate) fn has_sibling_combinator(&self) -> bool {
+        self.combinators
+            .iter()
+            .any(|c| matches!(c, Combinator::NextSibling | Combinator::SubsequentSibling))
+    }
+
+    /// Check whether the selector matches `element`, given its ancestors
+    /// ordered from the root down to its parent.
+    ///
+    /// Sibling combinators find siblings through the parent's children, so
+    /// `element` and `ancestors` must be references into the same tree.
+    /// `:root` matches an element without ancestors.
+    pub fn matches(&self, element: &Element, ancestors: &[&Element]) -> bool {
+        self.matches_at(self.compounds.len() - 1, element, ancestors)
+    }
+
+    fn matches_at(&self, index: usize, element: &Element, ancestors: &[&Element]) -> bool {
+        let compound = &self.compounds[index];
+        if !compound.matches(element) || (compound.root && !ancestors.is_empty()) {
+            return false;
+        }
+        if index == 0 {
+            return true;
+        }
+        match self.combinators[index - 1] {
+            Combinator::Descendant => (0..ancestors.len())
+                .rev()
+                .any(|i| self.matches_at(index - 1, ancestors[i], &ancestors[..i])),
+            Combinator::Child => ancestors
+                .split_last()
+                .is_some_and(|(parent, rest)| self.matches_at(index - 1, parent, rest)),
+            Combinator::NextSibling => preceding_siblings(element, ancestors)
+                .last()
+                .is_some_and(|sibling| self.matches_at(index - 1, sibling, ancestors)),
+            Combinator::SubsequentSibling => preceding_siblings(element, ancestors)
+                .iter()
+                .any(|sibling| self.matches_at(index - 1, sibling, ancestors)),
+        }
+    }
+}
+
+/// Check whether a selector list such as `h1, .title` matches `element`,
+/// given its ancestors ordered from the root down to its parent.
+///
+/// A list containing an invalid selector matches nothing, as in CSS. Use
+/// [`Selector::parse`] to compile a selector once for repeated matching.
+pub fn matches_selector(selector: &str, element: &Element, ancestors: &[&Element]) -> bool {
+    let selectors: BrowserResult<Vec<_>> =
+        split_selector_list(selector).into_iter().map(Selector::parse).collect();
+    selectors.is_ok_and(|selectors| selectors.iter().any(|s| s.matches(element, ancestors)))
+}
+
+/// `(id, class, type)` specificity of a selector, or of the most specific
+/// selector in a list. Invalid selectors have none, `(0, 0, 0)`.
+///
+/// The cascade compares specificities as tuples, so `#a` beats `.b` beats
+/// `div`; equal ones fall back to source order, the later rule winning.
+pub fn specificity(selector: &str) -> (u32, u32, u32) {
+    split_selector_list(selector)
+        .into_iter()
+        .filter_map(|s| Selector::parse(s).ok())
+        .map(|s| s.specificity())
+        .max()
+        .unwrap_or_default()
+}
+
+impl Compound {
+    fn matches(&self, element: &Element) -> bool {
+        if let Some(tag) = &self.tag
+            && !tag.eq_ignore_ascii_case(&element.tag)
+        {
+            return false;
+        }
+        if !self.ids.iter().all(|id| element.attribute("id") == Some(id.as_str())) {
+            return false;
+        }
+        let classes = element.attribute("class").unwrap_or("");
+        self.classes.iter().all(|class| classes.split_whitespace().any(|c| c == class))
+            && self.attributes.iter().all(|attribute| attribute.matches(element))
+    }
+}
+
+impl AttributeSelector {
+    /// Parse the rest of an attribute selector after its opening `[`.
+    fn parse(chars: &mut Peekable<Chars<'_>>) -> Option<Self> {
+        skip_whitespace(chars);
+        let name = take_ident(chars).to_ascii_lowercase();
+        if name.is_empty() {
+            return None;
+        }
+        skip_whitespace(chars);
+        let operator = match chars.next()? {
+            ']' => {
+                return Some(Self {
+                    name,
+                    operator: AttributeOperator::Exists,
+                    value: String::new(),
+                    case_insensitive: false,
+                });
+            },
+            '=' => AttributeOperator::Equals,
+            prefix => {
+                let operator = match prefix {
+                    '^' => AttributeOperator::Prefix,
+                    '$' => AttributeOperator::Suffix,
+                    '*' => AttributeOperator::Substring,
+                    _ => return None,
+                };
+                if chars.next()? != '=' {
+                    return None;
+                }
+                operator
+            },
+        };
+
+        skip_whitespace(chars);
+        let value = match chars.peek() {
+            Some(&quote @ ('"' | '\'')) => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next()? {
+                        c if c == quote => break value,
+                        c => value.push(c),
+                    }
+                }
+            },
+            _ => Some(take_ident(chars)).filter(|v| !v.is_empty())?,
+        };
+        skip_whitespace(chars);
+        // An explicit `i` or `s` flag overrides the HTML default.
+        let case_insensitive = match take_ident(chars).to_ascii_lowercase().as_str() {
+            "" => CASE_INSENSITIVE_ATTRIBUTES.contains(&name.as_str()),
+            "i" => true,
+            "s" => false,
+            _ => return None,
+        };
+        skip_whitespace(chars);
+        (chars.next()? == ']').then_some(Self { name, operator, value, case_insensitive })
+    }
+
+    fn matches(&self, element: &Element) -> bool {
+        let Some(actual) = element.attribute(&self.name) else {
+            return false;
+        };
+        let (actual, expected) = if self.case_insensitive {
+            (
+                Cow::Owned(actual.to_ascii_lowercase()),
+                Cow::Owned(self.value.to_ascii_lowercase()),
+            )
+        } else {
+            (Cow::Borrowed(actual), Cow::Borrowed(self.value.as_str()))
+        };
+        // Prefix, suffix and substring selectors with an empty value never
+        // match.
+        match self.operator {
+            AttributeOperator::Exists => true,
+            AttributeOperator::Equals => actual == expected,
+            AttributeOperator::Prefix => !expected.is_empty() && actual.starts_with(&*expected),
+            AttributeOperator::Suffix => !expected.is_empty() && actual.ends_with(&*expected),
+            AttributeOperator::Substring => !expected.is_empty() && actual.contains(&*expected),
+        }
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_' || !c.is_ascii()
+}
+
+/// Siblings before `element` in its parent, the last of `ancestors`.
+fn preceding_siblings<'a>(element: &Element, ancestors: &[&'a Element]) -> &'a [Element] {
+    let Some(parent) = ancestors.last() else {
+        return &[];
+    };
+    let position = parent.children.iter().position(|child| ptr::eq(child, element));
+    position.map_or(&[], |i| &parent.children[..i])
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars<'_>>) {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+fn take_ident(chars: &mut Peekable<Chars<'_>>) -> String {
+    let mut ident = String::new();
+    while let Some(&c) = chars.peek() {
+        if !is_ident_char(c) {
+            break;
+        }
+        ident.push(c);
+        chars.next();
+    }
+    ident
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        style::{Cascade, StyleOrigin},
+        types::{CssRule, StyleSheet},
+    };
+
+    #[test]
+    fn test_parse_and_specificity() {
+        let selector = Selector::parse("div.note #intro.lead").unwrap();
+        assert_eq!(selector.specificity(), (1, 2, 1));
+        assert_eq!(Selector::parse("*").unwrap().specificity(), (0, 0, 0));
+        assert_eq!(Selector::parse(":root").unwrap().specificity(), (0, 1, 0));
+        for invalid in ["", "div..a", "p >", "> p", "a > > b", "#", "a,b", "a:hover"] {
+            assert!(Selector::parse(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_descendant_matching_uses_ancestors() {
+        let div = Element::new("div").with_attribute("class", "note");
+        let section = Element::new("section");
+        let p = Element::new("p")
+            .with_attribute("id", "intro")
+            .with_attribute("class", "a lead");
+
+        let selector = Selector::parse("div.note p.lead").unwrap();
+        assert!(selector.matches(&p, &[&div, &section]));
+        assert!(!selector.matches(&p, &[&section]));
+        assert!(!selector.matches(&div, &[]));
+        assert!(Selector::parse("P#intro").unwrap().matches(&p, &[]));
+    }
+
+    /// Ids of the elements under `root` matching `selector`, in document order.
+    fn select<'a>(
+        root: &'a Element,
+        ancestors: &mut Vec<&'a Element>,
+        selector: &Selector,
+        out: &mut Vec<&'a str>,
+    ) {
+        if selector.matches(root, ancestors) {
+            out.extend(root.attribute("id"));
+        }
+        ancestors.push(root);
+        for child in &root.children {
+            select(child, ancestors, selector, out);
+        }
+        ancestors.pop();
+    }
+
+    fn select_ids<'a>(root: &'a Element, selector: &str) -> Vec<&'a str> {
+        let mut out = Vec::new();
+        select(root, &mut Vec::new(), &Selector::parse(selector).unwrap(), &mut out);
+        out
+    }
+
+    #[test]
+    fn test_child_and_sibling_combinators() {
+        let nested =
+            Element::new("ul").with_child(Element::new("li").with_attribute("id", "inner"));
+        let list = Element::new("ul")
+            .with_child(Element::new("li").with_attribute("id", "a"))
+            .with_child(Element::new("li").with_attribute("id", "b").with_child(nested));
+        let body = Element::new("body")
+            .with_child(Element::new("h2").with_attribute("id", "h"))
+            .with_child(Element::new("p").with_attribute("id", "p1"))
+            .with_child(Element::new("p").with_attribute("id", "p2"))
+            .with_child(list);
+        let root = Element::new("div").with_attribute("id", "root").with_child(body);
+
+        assert_eq!(select_ids(&root, "ul li"), ["a", "b", "inner"]);
+        assert_eq!(select_ids(&root, "body > ul > li"), ["a", "b"]);
+        assert_eq!(select_ids(&root, "li > ul > li"), ["inner"]);
+        assert_eq!(select_ids(&root, "h2 + p"), ["p1"]);
+        assert_eq!(select_ids(&root, "h2~p"), ["p1", "p2"]);
+        assert_eq!(select_ids(&root, "h2 ~ ul li + li"), ["b"]);
+        assert_eq!(select_ids(&root, "#root > p"), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_attribute_selectors() {
+        let input = Element::new("input")
+            .with_attribute("TYPE", "Text")
+            .with_attribute("disabled", "")
+            .with_attribute("href", "https://example.com/guide.pdf")
+            .with_attribute("title", "Hello World");
+        let matches = |selector: &str| Selector::parse(selector).unwrap().matches(&input, &[]);
+
+        assert!(matches("[disabled]"));
+        assert!(!matches("[checked]"));
+        // `type` values are case-insensitive in HTML; most others are not.
+        assert!(matches("input[type=\"text\"]"));
+        assert!(matches("[title='Hello World']"));
+        assert!(!matches("[title='hello world']"));
+        assert!(matches("[title='hello world' i]"));
+        assert!(!matches("[type=text s]"));
+        assert!(matches("[href^=https]"));
+        assert!(matches("[href$='.pdf']"));
+        assert!(!matches("[href$='.PDF']"));
+        assert!(matches("[href*=example]"));
+        assert!(!matches("[href*='']"));
+
+        assert_eq!(Selector::parse("a[href][rel=next]").unwrap().specificity(), (0, 2, 1));
+        for invalid in ["[", "[=x]", "[a=]", "[a~=b]", "[a='b]", "[a=b x]"] {
+            assert!(Selector::parse(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_compiled_selector_agrees_with_cascade() {
+        let article = Element::new("article").with_attribute("id", "main");
+        let p = Element::new("p").with_attribute("class", "lead");
+        for selector in [
+            "p",
+            "*",
+            ".lead",
+            "p.lead",
+            "#main p",
+            "article .lead",
+            "div p",
+            "p.x",
+        ] {
+            let sheet = StyleSheet {
+                rules: vec![CssRule {
+                    selector:     selector.into(),
+                    declarations: vec![("width".into(), "10px".into())],
+                }],
+            };
+            let cascade = Cascade::new().with_sheet(StyleOrigin::Author, &sheet);
+            let styled = cascade.computed_style_in(&p, &[&article], None).width.is_some();
+            let compiled = Selector::parse(selector).unwrap().matches(&p, &[&article]);
+            assert_eq!(styled, compiled, "{selector}");
+        }
+    }
+
+    #[test]
+    fn test_matches_selector_lists() {
+        let div = Element::new("div");
+        let section = Element::new("section");
+        let p = Element::new("p").with_attribute("class", "a foo b");
+
+        assert!(matches_selector(".foo", &p, &[]));
+        assert!(!matches_selector(".fo", &p, &[]));
+        assert!(matches_selector("*", &p, &[]));
+        assert!(matches_selector("div p", &p, &[&div, &section]));
+        assert!(!matches_selector("div p", &p, &[&section]));
+        assert!(matches_selector("h1, section > p", &p, &[&div, &section]));
+        assert!(!matches_selector("p, a:hover", &p, &[]));
+    }
+
+    #[test]
+    fn test_specificity_ordering() {
+        assert!(specificity("#a") > specificity(".b"));
+        assert!(specificity(".b") > specificity("div"));
+        assert!(specificity("#a") > specificity("div.b.c.d"));
+        assert_eq!(specificity("div.b"), (0, 1, 1));
+        assert_eq!(specificity("p, ul li#x"), (1, 0, 2));
+        assert_eq!(specificity("a:hover"), (0, 0, 0));
+    }
+}
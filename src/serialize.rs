@@ -0,0 +1,191 @@
+//! HTML serialization of documents.
+
+use crate::{
+    parser::{RAW_TEXT_TAGS, VOID_TAGS},
+    types::{Document, Element},
+};
+
+/// Elements whose content is written exactly as stored, even when pretty
+/// printing.
+const WHITESPACE_SENSITIVE_TAGS: &[&str] = &["pre", "textarea", "script", "style"];
+
+/// Elements that start a new line when pretty printing.
+const BLOCK_TAGS: &[&str] = &[
+    "html",
+    "head",
+    "body",
+    "title",
+    "meta",
+    "link",
+    "script",
+    "style",
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "ul",
+];
+
+impl Document {
+    /// Serialize the element tree as compact HTML, without added whitespace.
+    pub fn to_html(&self) -> String {
+        let mut out = String::new();
+        write_compact(&self.root, &mut out);
+        out
+    }
+
+    /// Serialize the element tree with one block element per line, nested
+    /// `indent` spaces per level.
+    ///
+    /// Inline content stays on its parent's line, and `<pre>`, `<textarea>`,
+    /// `<script>` and `<style>` content is written untouched.
+    pub fn to_pretty_html(&self, indent: usize) -> String {
+        let mut lines = Vec::new();
+        write_pretty(&self.root, 0, indent, &mut lines);
+        lines.join("\n")
+    }
+}
+
+fn write_compact(element: &Element, out: &mut String) {
+    if element.tag == "#comment" {
+        out.push_str("<!--");
+        out.push_str(element.text_content.as_deref().unwrap_or_default());
+        out.push_str("-->");
+        return;
+    }
+    write_open_tag(element, out);
+    if VOID_TAGS.contains(&element.tag.as_str()) {
+        return;
+    }
+    if let Some(text) = &element.text_content {
+        if RAW_TEXT_TAGS.contains(&element.tag.as_str()) {
+            out.push_str(text);
+        } else {
+            push_escaped(text, false, out);
+        }
+    }
+    for child in &element.children {
+        write_compact(child, out);
+    }
+    out.push_str("</");
+    out.push_str(&element.tag);
+    out.push('>');
+}
+
+fn write_pretty(element: &Element, depth: usize, indent: usize, lines: &mut Vec<String>) {
+    let padding = " ".repeat(depth * indent);
+    let has_block_children = element.children.iter().any(|c| BLOCK_TAGS.contains(&c.tag.as_str()));
+    if !has_block_children || WHITESPACE_SENSITIVE_TAGS.contains(&element.tag.as_str()) {
+        let mut line = padding;
+        write_compact(element, &mut line);
+        lines.push(line);
+        return;
+    }
+
+    let mut open = padding.clone();
+    write_open_tag(element, &mut open);
+    lines.push(open);
+    if let Some(text) = element.text_content.as_deref().map(str::trim)
+        && !text.is_empty()
+    {
+        let mut line = " ".repeat((depth + 1) * indent);
+        push_escaped(text, false, &mut line);
+        lines.push(line);
+    }
+    for child in &element.children {
+        write_pretty(child, depth + 1, indent, lines);
+    }
+    lines.push(format!("{padding}</{}>", element.tag));
+}
+
+fn write_open_tag(element: &Element, out: &mut String) {
+    out.push('<');
+    out.push_str(&element.tag);
+    for (name, value) in &element.attributes {
+        out.push(' ');
+        out.push_str(name);
+        out.push_str("=\"");
+        push_escaped(value, true, out);
+        out.push('"');
+    }
+    out.push('>');
+}
+
+fn push_escaped(text: &str, attribute: bool, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' if !attribute => out.push_str("&lt;"),
+            '>' if !attribute => out.push_str("&gt;"),
+            '"' if attribute => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(root: Element) -> Document {
+        Document { title: String::new(), root, url: "https://example.com/".into() }
+    }
+
+    #[test]
+    fn test_pretty_html_indents_blocks_and_keeps_pre() {
+        let doc = document(
+            Element::new("body").with_child(
+                Element::new("div")
+                    .with_attribute("class", "a\"b")
+                    .with_child(
+                        Element::new("p")
+                            .with_text("x < y")
+                            .with_child(Element::new("b").with_text("bold")),
+                    )
+                    .with_child(Element::new("pre").with_text("line one\n  line two"))
+                    .with_child(Element::new("img").with_attribute("src", "a.png")),
+            ),
+        );
+
+        assert_eq!(
+            doc.to_pretty_html(2),
+            "<body>\n  <div class=\"a&quot;b\">\n    <p>x &lt; y<b>bold</b></p>\n    \
+             <pre>line one\n  line two</pre>\n    <img src=\"a.png\">\n  </div>\n</body>"
+        );
+        assert_eq!(
+            doc.to_html(),
+            "<body><div class=\"a&quot;b\"><p>x &lt; y<b>bold</b></p><pre>line one\n  line \
+             two</pre><img src=\"a.png\"></div></body>"
+        );
+    }
+}
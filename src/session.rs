@@ -0,0 +1,393 @@
+//! Saved browser sessions in the `essentia/browser-session` JSON format.
+//!
+//! ```text
+//! {"version":1,"tabs":[{"id":1,"url":"https://example.com/","title":"Example",
+//!   "pinned":false,"zoom":100,"active":true}]}
+//! ```
+
+use std::{collections::BTreeMap, fmt::Write, iter::Peekable, str::Chars};
+
+/// Session format version written by [`BrowserSession::to_json`].
+const SESSION_VERSION: u64 = 1;
+
+/// Deepest array/object nesting the parser accepts, so corrupt input cannot
+/// exhaust the stack. Real sessions nest three levels.
+const MAX_JSON_DEPTH: usize = 64;
+
+/// A tab as stored in a saved session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionTab {
+    pub id:     u64,
+    pub url:    String,
+    pub title:  String,
+    pub pinned: bool,
+    /// Zoom level in percent.
+    pub zoom:   u32,
+    pub active: bool,
+}
+
+/// Open tabs in tab strip order, as saved and restored by the FlexForge
+/// integration.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BrowserSession {
+    pub tabs: Vec<SessionTab>,
+}
+
+impl BrowserSession {
+    /// Serialize the session as compact JSON.
+    pub fn to_json(&self) -> String {
+        let mut out = format!("{{\"version\":{SESSION_VERSION},\"tabs\":[");
+        for (i, tab) in self.tabs.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let _ = write!(out, "{{\"id\":{},\"url\":", tab.id);
+            push_json_string(&tab.url, &mut out);
+            out.push_str(",\"title\":");
+            push_json_string(&tab.title, &mut out);
+            let _ = write!(
+                out,
+                ",\"pinned\":{},\"zoom\":{},\"active\":{}}}",
+                tab.pinned, tab.zoom, tab.active
+            );
+        }
+        out.push_str("]}");
+        out
+    }
+
+    /// Parse a session saved by [`to_json`](Self::to_json).
+    ///
+    /// Unknown fields are ignored; `title`, `pinned`, `zoom` and `active`
+    /// may be omitted. Malformed JSON, a missing `id` or `url`, or an
+    /// unsupported version is an error.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        if json.trim().is_empty() {
+            return Err("Session is empty".to_string());
+        }
+        let mut parser = JsonParser { chars: json.chars().peekable(), offset: 0, depth: 0 };
+        let value = parser.parse_document().map_err(|e| format!("Invalid session JSON: {e}"))?;
+        let JsonValue::Object(mut fields) = value else {
+            return Err("Session must be a JSON object".to_string());
+        };
+        if let Some(version) = fields.remove("version") {
+            match version {
+                JsonValue::Number(n) if n == SESSION_VERSION.to_string() => {},
+                JsonValue::Number(n) => return Err(format!("Unsupported session version {n}")),
+                _ => return Err("Session `version` must be a number".to_string()),
+            }
+        }
+        let Some(JsonValue::Array(tabs)) = fields.remove("tabs") else {
+            return Err("Session has no `tabs` array".to_string());
+        };
+        let tabs = tabs
+            .into_iter()
+            .enumerate()
+            .map(|(index, tab)| {
+                SessionTab::from_value(tab).map_err(|e| format!("Session tab {index}: {e}"))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { tabs })
+    }
+}
+
+impl SessionTab {
+    fn from_value(value: JsonValue) -> Result<Self, String> {
+        let JsonValue::Object(mut fields) = value else {
+            return Err("expected an object".to_string());
+        };
+        let mut take = |name: &str| fields.remove(name);
+        let id = match take("id") {
+            Some(JsonValue::Number(n)) => n.parse().map_err(|_| format!("invalid id {n}"))?,
+            _ => return Err("missing numeric `id`".to_string()),
+        };
+        let url = match take("url") {
+            Some(JsonValue::String(url)) => url,
+            _ => return Err("missing string `url`".to_string()),
+        };
+        let title = match take("title") {
+            Some(JsonValue::String(title)) => title,
+            None | Some(JsonValue::Null) => String::new(),
+            Some(_) => return Err("`title` must be a string".to_string()),
+        };
+        let flag = |value: Option<JsonValue>, name: &str| match value {
+            Some(JsonValue::Bool(flag)) => Ok(flag),
+            None | Some(JsonValue::Null) => Ok(false),
+            Some(_) => Err(format!("`{name}` must be a boolean")),
+        };
+        let pinned = flag(take("pinned"), "pinned")?;
+        let active = flag(take("active"), "active")?;
+        let zoom = match take("zoom") {
+            Some(JsonValue::Number(n)) => n.parse().map_err(|_| format!("invalid zoom {n}"))?,
+            None | Some(JsonValue::Null) => 100,
+            Some(_) => return Err("`zoom` must be a number".to_string()),
+        };
+        Ok(Self { id, url, title, pinned, zoom, active })
+    }
+}
+
+/// Append `value` as a quoted JSON string.
+fn push_json_string(value: &str, out: &mut String) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            },
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Parsed JSON value. Numbers keep their source text so integer fields
+/// parse without going through `f64`.
+#[derive(Debug)]
+enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(BTreeMap<String, JsonValue>),
+}
+
+/// Recursive-descent JSON parser; errors name the character offset.
+struct JsonParser<'a> {
+    chars:  Peekable<Chars<'a>>,
+    offset: usize,
+    /// Arrays and objects currently open, bounded by [`MAX_JSON_DEPTH`].
+    depth:  usize,
+}
+
+impl JsonParser<'_> {
+    /// Parse a single value followed only by whitespace.
+    fn parse_document(&mut self) -> Result<JsonValue, String> {
+        let value = self.parse_value()?;
+        self.skip_whitespace();
+        match self.chars.peek() {
+            None => Ok(value),
+            Some(c) => Err(format!("unexpected `{c}` at offset {}", self.offset)),
+        }
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        self.offset += 1;
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            self.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => {
+                Err(format!("expected `{expected}` but found `{c}` at offset {}", self.offset - 1))
+            },
+            None => Err(format!("expected `{expected}` but the input ended")),
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<JsonValue, String> {
+        self.skip_whitespace();
+        match self.chars.peek().copied() {
+            Some('{' | '[') if self.depth >= MAX_JSON_DEPTH => Err(format!(
+                "nesting too deep at offset {} (at most {MAX_JSON_DEPTH} levels)",
+                self.offset
+            )),
+            Some('{') => self.nested(Self::parse_object),
+            Some('[') => self.nested(Self::parse_array),
+            Some('"') => self.parse_string().map(JsonValue::String),
+            Some('-' | '0'..='9') => Ok(self.parse_number()),
+            Some('t') => self.parse_keyword("true", JsonValue::Bool(true)),
+            Some('f') => self.parse_keyword("false", JsonValue::Bool(false)),
+            Some('n') => self.parse_keyword("null", JsonValue::Null),
+            Some(c) => Err(format!("unexpected `{c}` at offset {}", self.offset)),
+            None => Err("unexpected end of input".to_string()),
+        }
+    }
+
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<JsonValue, String>,
+    ) -> Result<JsonValue, String> {
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn parse_object(&mut self) -> Result<JsonValue, String> {
+        self.expect('{')?;
+        let mut fields = BTreeMap::new();
+        self.skip_whitespace();
+        if self.chars.peek() == Some(&'}') {
+            self.next();
+            return Ok(JsonValue::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.expect(':')?;
+            let value = self.parse_value()?;
+            fields.insert(key, value);
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => {},
+                Some('}') => return Ok(JsonValue::Object(fields)),
+                _ => return Err(format!("expected `,` or `}}` at offset {}", self.offset)),
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<JsonValue, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.chars.peek() == Some(&']') {
+            self.next();
+            return Ok(JsonValue::Array(items));
+        }
+        loop {
+            items.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => {},
+                Some(']') => return Ok(JsonValue::Array(items)),
+                _ => return Err(format!("expected `,` or `]` at offset {}", self.offset)),
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        if self.next() != Some('"') {
+            return Err(format!("expected a string at offset {}", self.offset.saturating_sub(1)));
+        }
+        let mut out = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(out),
+                Some('\\') => {
+                    let c = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.parse_unicode_escape()?,
+                        _ => return Err(format!("invalid escape at offset {}", self.offset)),
+                    };
+                    out.push(c);
+                },
+                Some(c) => out.push(c),
+                None => return Err("unterminated string".to_string()),
+            }
+        }
+    }
+
+    /// The character of a `\uXXXX` escape whose `\u` was just consumed,
+    /// combining a UTF-16 surrogate pair.
+    fn parse_unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.parse_hex4()?;
+        let code = if (0xD800..0xDC00).contains(&high) {
+            if self.next() != Some('\\') || self.next() != Some('u') {
+                return Err(format!("unpaired surrogate at offset {}", self.offset));
+            }
+            let low = self.parse_hex4()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(format!("unpaired surrogate at offset {}", self.offset));
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+        char::from_u32(code).ok_or_else(|| format!("invalid code point at offset {}", self.offset))
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, String> {
+        (0..4).try_fold(0, |code, _| {
+            let digit = self.next().and_then(|c| c.to_digit(16));
+            digit
+                .map(|d| code * 16 + d)
+                .ok_or_else(|| format!("invalid \\u escape at offset {}", self.offset))
+        })
+    }
+
+    fn parse_number(&mut self) -> JsonValue {
+        let mut text = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if !matches!(c, '-' | '+' | '.' | 'e' | 'E' | '0'..='9') {
+                break;
+            }
+            text.push(c);
+            self.next();
+        }
+        JsonValue::Number(text)
+    }
+
+    fn parse_keyword(&mut self, keyword: &str, value: JsonValue) -> Result<JsonValue, String> {
+        let start = self.offset;
+        for expected in keyword.chars() {
+            if self.next() != Some(expected) {
+                return Err(format!("invalid literal at offset {start}"));
+            }
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_json_round_trip_and_errors() {
+        let session = BrowserSession {
+            tabs: vec![SessionTab {
+                id:     7,
+                url:    "https://example.com/?q=\"a\\b\"".into(),
+                title:  "Tab\n\u{1}ü😀".into(),
+                pinned: true,
+                zoom:   125,
+                active: true,
+            }],
+        };
+        let json = session.to_json();
+        assert_eq!(BrowserSession::from_json(&json), Ok(session));
+
+        let minimal = r#" { "tabs": [ { "url": "about:blank", "id": 2, "extra": [1, {}] } ] } "#;
+        let tab = &BrowserSession::from_json(minimal).unwrap().tabs[0];
+        assert_eq!((tab.id, tab.zoom, tab.pinned, tab.title.as_str()), (2, 100, false, ""));
+        let escaped = r#"{"tabs":[{"id":1,"url":"x","title":"\ud83d\ude00\u00fc"}]}"#;
+        assert_eq!(BrowserSession::from_json(escaped).unwrap().tabs[0].title, "😀ü");
+
+        for (json, error) in [
+            ("", "Session is empty"),
+            ("[]", "Session must be a JSON object"),
+            ("{\"tabs\":[", "Invalid session JSON: unexpected end of input"),
+            ("{\"tabs\":[]} x", "Invalid session JSON: unexpected `x` at offset 12"),
+            ("{\"version\":2,\"tabs\":[]}", "Unsupported session version 2"),
+            ("{}", "Session has no `tabs` array"),
+            (
+                &"[".repeat(200_000),
+                "Invalid session JSON: nesting too deep at offset 64 (at most 64 levels)",
+            ),
+            ("{\"tabs\":[{\"url\":\"x\"}]}", "Session tab 0: missing numeric `id`"),
+        ] {
+            assert_eq!(BrowserSession::from_json(json), Err(error.to_string()), "{json}");
+        }
+    }
+}
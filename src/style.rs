@@ -0,0 +1,1080 @@
+//! Style cascade and computed style resolution.
+
+use std::{borrow::Cow, collections::BTreeMap, sync::OnceLock};
+
+use crate::{
+    css::{CssParser, split_selector_list},
+    selector::Selector,
+    types::{
+        BorderStyle, BoxEdges, Color, ComputedStyle, CssRule, DEFAULT_FONT_SIZE, Display, Element,
+        FlexDirection, FontStyle, StyleSheet, TextAlign, Visibility, WhiteSpace,
+    },
+    url,
+};
+
+/// Default element styling, cascaded at user-agent origin beneath every
+/// page and user sheet.
+const USER_AGENT_CSS: &str = "
+    head, link, meta, script, style, template, title, [hidden] { display: none }
+    a, abbr, b, cite, code, em, i, img, kbd, label, q, s, small, span, strong, sub, sup, u,
+    var { display: inline }
+    li { display: list-item }
+    table { display: table }
+    body { margin: 8px }
+    p, blockquote, dl, figure, pre, ul, ol { margin: 16px 0 }
+    ul, ol { padding-left: 40px }
+    blockquote, figure { margin-left: 40px; margin-right: 40px }
+    h1 { font-size: 2em; margin: 21px 0 }
+    h2 { font-size: 1.5em; margin: 20px 0 }
+    h3 { font-size: 1.17em; margin: 19px 0 }
+    h4 { margin: 21px 0 }
+    h5 { font-size: 0.83em; margin: 22px 0 }
+    h6 { font-size: 0.67em; margin: 25px 0 }
+    h1, h2, h3, h4, h5, h6, b, strong, th { font-weight: bold }
+    i, em, cite, var { font-style: italic }
+    small { font-size: smaller }
+    pre { white-space: pre }
+    a[href] { color: #0000ee }
+";
+
+/// The built-in user-agent stylesheet.
+pub(crate) fn user_agent_stylesheet() -> &'static StyleSheet {
+    static SHEET: OnceLock<StyleSheet> = OnceLock::new();
+    SHEET.get_or_init(|| CssParser::parse(USER_AGENT_CSS).unwrap_or_default())
+}
+
+/// Origin of a stylesheet.
+///
+/// Normal declarations rank user-agent < author < user, so user styles
+/// override the page's normal styles. `!important` declarations beat every
+/// normal declaration and rank author < user < user-agent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StyleOrigin {
+    UserAgent,
+    Author,
+    User,
+}
+
+impl StyleOrigin {
+    /// Cascade precedence band for a declaration from this origin.
+    fn band(self, important: bool) -> u8 {
+        match (important, self) {
+            (false, origin) => origin as u8,
+            (true, Self::Author) => 3,
+            (true, Self::User) => 4,
+            (true, Self::UserAgent) => 5,
+        }
+    }
+}
+
+/// Stylesheets participating in the cascade, in source order.
+///
+/// Rule selectors are compiled once when a sheet is added.
+#[derive(Debug, Clone, Default)]
+pub struct Cascade<'a> {
+    rules:         Vec<CompiledRule<'a>>,
+    sheet_count:   usize,
+    base_url:      Option<String>,
+    /// Set when some selector looks at siblings, so elements with the same
+    /// parent, tag and attributes may still style differently.
+    sibling_rules: bool,
+}
+
+/// A stylesheet rule with its selector list compiled.
+#[derive(Debug, Clone)]
+struct CompiledRule<'a> {
+    rule:        &'a CssRule,
+    selectors:   Vec<Selector>,
+    origin:      StyleOrigin,
+    sheet_index: usize,
+    rule_index:  usize,
+}
+
+/// A matched declaration and the key that orders it in the cascade.
+struct CascadedDeclaration<'a> {
+    rank:     (u8, (u32, u32, u32), usize, usize),
+    property: &'a str,
+    value:    &'a str,
+}
+
+impl<'a> Cascade<'a> {
+    /// Create an empty cascade.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a stylesheet from the given origin.
+    pub fn with_sheet(mut self, origin: StyleOrigin, sheet: &'a StyleSheet) -> Self {
+        for (rule_index, rule) in sheet.rules.iter().enumerate() {
+            // Rules whose selector list does not compile are dropped, as in CSS.
+            let selectors: Option<Vec<Selector>> = split_selector_list(&rule.selector)
+                .into_iter()
+                .map(|s| Selector::parse(s).ok())
+                .collect();
+            if let Some(selectors) = selectors {
+                self.sibling_rules |= selectors.iter().any(Selector::has_sibling_combinator);
+                self.rules.push(CompiledRule {
+                    rule,
+                    selectors,
+                    origin,
+                    sheet_index: self.sheet_count,
+                    rule_index,
+                });
+            }
+        }
+        self.sheet_count += 1;
+        self
+    }
+
+    /// Resolve relative `url()` values against this base URL.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Whether siblings with the same tag and attributes always compute to the
+    /// same style, so their styles can be shared.
+    pub(crate) fn shares_sibling_styles(&self) -> bool {
+        !self.sibling_rules
+    }
+
+    /// Resolve the computed style of an element without ancestor context.
+    ///
+    /// `parent` is the parent's computed style, used for inherited properties.
+    /// Selectors with combinators only match via
+    /// [`computed_style_in`](Self::computed_style_in).
+    pub fn computed_style(
+        &self,
+        element: &Element,
+        parent: Option<&ComputedStyle>,
+    ) -> ComputedStyle {
+        self.computed_style_in(element, &[], parent)
+    }
+
+    /// Resolve the computed style of an element whose ancestors are given
+    /// from the root down to its parent.
+    pub fn computed_style_in(
+        &self,
+        element: &Element,
+        ancestors: &[&Element],
+        parent: Option<&ComputedStyle>,
+    ) -> ComputedStyle {
+        let mut style = ComputedStyle::default();
+        let parent_font_size = parent.map_or(DEFAULT_FONT_SIZE, |parent| parent.font_size);
+        style.font_size = parent_font_size;
+        if let Some(parent) = parent {
+            style.color = parent.color;
+            style.visibility = parent.visibility;
+            style.text_align = parent.text_align;
+            style.font_weight = parent.font_weight;
+            style.font_style = parent.font_style;
+            style.white_space = parent.white_space;
+        }
+
+        let mut declarations = Vec::new();
+        for compiled in &self.rules {
+            let specificity = compiled
+                .selectors
+                .iter()
+                .filter(|selector| selector.matches(element, ancestors))
+                .map(Selector::specificity)
+                .max();
+            if let Some(specificity) = specificity {
+                Self::collect(compiled, specificity, &mut declarations);
+            }
+        }
+
+        declarations.sort_by_key(|d| d.rank);
+        let (custom, declarations): (Vec<_>, Vec<_>) =
+            declarations.into_iter().partition(|d| d.property.starts_with("--"));
+        let declared = custom.iter().map(|d| (d.property, d.value)).collect();
+        if let Some(parent) = parent {
+            style.custom_properties = parent.custom_properties.clone();
+        }
+        resolve_custom_properties(&mut style.custom_properties, &declared);
+
+        for declaration in declarations {
+            let value = if declaration.value.contains("var(") {
+                // A reference that cannot be substituted invalidates the
+                // declaration at computed-value time.
+                match substitute_vars(declaration.value, &mut |name| {
+                    style.custom_properties.get(name).cloned()
+                }) {
+                    Some(value) => Cow::Owned(value),
+                    None => continue,
+                }
+            } else {
+                Cow::Borrowed(declaration.value)
+            };
+            if declaration.property == "background-image" {
+                // Unsupported images are invalid and leave the cascaded
+                // image in place.
+                if value.trim().eq_ignore_ascii_case("none") {
+                    style.background_image = None;
+                } else if let Some(image) = self.parse_image(&value) {
+                    style.background_image = Some(image);
+                }
+            } else if declaration.property == "font-size" {
+                if let Some(size) = parse_font_size(&value, parent_font_size) {
+                    style.font_size = size;
+                }
+            } else {
+                apply_declaration(&mut style, declaration.property, &value);
+            }
+        }
+        style
+    }
+
+    /// Parse a `url(...)` image value, resolving the URL against the base
+    /// URL. Other values yield `None`.
+    fn parse_image(&self, value: &str) -> Option<String> {
+        let inner = value.trim().strip_prefix("url(")?.strip_suffix(')')?.trim();
+        let unquoted = ['"', '\''].iter().find_map(|quote| {
+            inner.strip_prefix(*quote).and_then(|rest| rest.strip_suffix(*quote))
+        });
+        let reference = unquoted.unwrap_or(inner);
+        match &self.base_url {
+            Some(base) => Some(url::resolve(base, reference)),
+            None => Some(reference.to_string()),
+        }
+    }
+
+    fn collect(
+        compiled: &CompiledRule<'a>,
+        specificity: (u32, u32, u32),
+        out: &mut Vec<CascadedDeclaration<'a>>,
+    ) {
+        let CompiledRule { rule, origin, sheet_index, rule_index, .. } = *compiled;
+        for (property, value) in &rule.declarations {
+            let (value, important) = match value.trim().strip_suffix("!important") {
+                Some(value) => (value.trim(), true),
+                None => (value.trim(), false),
+            };
+            out.push(CascadedDeclaration {
+                rank: (origin.band(important), specificity, sheet_index, rule_index),
+                property: property.trim(),
+                value,
+            });
+        }
+    }
+}
+
+/// Override inherited custom properties with an element's declared ones,
+/// substituting `var()` references. Declarations that are part of a
+/// reference cycle or use an undefined variable without a fallback leave the
+/// property unset.
+fn resolve_custom_properties(
+    properties: &mut BTreeMap<String, String>,
+    declared: &BTreeMap<&str, &str>,
+) {
+    for name in declared.keys() {
+        properties.remove(*name);
+    }
+    let mut resolving = Vec::new();
+    for name in declared.keys() {
+        resolve_custom_property(name, declared, properties, &mut resolving);
+    }
+}
+
+fn resolve_custom_property(
+    name: &str,
+    declared: &BTreeMap<&str, &str>,
+    properties: &mut BTreeMap<String, String>,
+    resolving: &mut Vec<String>,
+) -> Option<String> {
+    if let Some(value) = properties.get(name) {
+        return Some(value.clone());
+    }
+    let raw = *declared.get(name)?;
+    if resolving.iter().any(|pending| pending == name) {
+        return None;
+    }
+    resolving.push(name.to_string());
+    let value = substitute_vars(raw, &mut |reference| {
+        resolve_custom_property(reference, declared, properties, resolving)
+    });
+    resolving.pop();
+    if let Some(value) = &value {
+        properties.insert(name.to_string(), value.clone());
+    }
+    value
+}
+
+/// Longest value `var()` substitution may produce. Longer results are
+/// invalid at computed-value time, which stops chains of variables that each
+/// repeat the previous one from growing exponentially.
+const MAX_SUBSTITUTED_LENGTH: usize = 64 * 1024;
+
+/// Replace every `var(--name[, fallback])` in `value` with the variable's
+/// value from `lookup`, or the (itself substituted) fallback. Returns `None`
+/// if a reference is malformed or cannot be resolved, or if the result would
+/// exceed [`MAX_SUBSTITUTED_LENGTH`].
+fn substitute_vars(value: &str, lookup: &mut dyn FnMut(&str) -> Option<String>) -> Option<String> {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("var(") {
+        out.push_str(&rest[..start]);
+        let arguments = &rest[start + "var(".len()..];
+        let mut depth = 0;
+        let mut comma = None;
+        let mut close = None;
+        for (i, c) in arguments.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' if depth == 0 => {
+                    close = Some(i);
+                    break;
+                },
+                ')' => depth -= 1,
+                ',' if depth == 0 && comma.is_none() => comma = Some(i),
+                _ => {},
+            }
+        }
+        let close = close?;
+        let (name, fallback) = match comma {
+            Some(comma) => (&arguments[..comma], Some(&arguments[comma + 1..close])),
+            None => (&arguments[..close], None),
+        };
+        let name = name.trim();
+        if !name.starts_with("--") {
+            return None;
+        }
+        let substituted = match lookup(name) {
+            Some(substituted) => substituted,
+            None => substitute_vars(fallback?.trim(), lookup)?,
+        };
+        if out.len() + substituted.len() > MAX_SUBSTITUTED_LENGTH {
+            return None;
+        }
+        out.push_str(&substituted);
+        rest = &arguments[close + 1..];
+    }
+    if out.len() + rest.len() > MAX_SUBSTITUTED_LENGTH {
+        return None;
+    }
+    out.push_str(rest);
+    Some(out)
+}
+
+/// Apply a single declaration to a computed style. Unknown properties and
+/// invalid values are ignored.
+fn apply_declaration(style: &mut ComputedStyle, property: &str, value: &str) {
+    match property {
+        "display" => {
+            if let Some(display) = Display::parse(value) {
+                style.display = display;
+            }
+        },
+        "flex-direction" => {
+            if let Some(direction) = FlexDirection::parse(value) {
+                style.flex_direction = direction;
+            }
+        },
+        "width" => style.width = parse_length(value),
+        "height" => style.height = parse_length(value),
+        "color" => {
+            if let Some(color) = Color::parse(value) {
+                style.color = color;
+            }
+        },
+        "background-color" => {
+            if let Some(color) = Color::parse(value) {
+                style.background_color = color;
+            }
+        },
+        "visibility" => {
+            if let Some(visibility) = Visibility::parse(value) {
+                style.visibility = visibility;
+            }
+        },
+        "opacity" => {
+            if let Some(opacity) = parse_opacity(value) {
+                style.opacity = opacity;
+            }
+        },
+        "text-align" => {
+            if let Some(align) = TextAlign::parse(value) {
+                style.text_align = align;
+            }
+        },
+        "font-weight" => {
+            if let Some(weight) = parse_font_weight(value, style.font_weight) {
+                style.font_weight = weight;
+            }
+        },
+        "font-style" => {
+            if let Some(font_style) = FontStyle::parse(value) {
+                style.font_style = font_style;
+            }
+        },
+        "white-space" => {
+            if let Some(white_space) = WhiteSpace::parse(value) {
+                style.white_space = white_space;
+            }
+        },
+        "margin" => {
+            if let Some(margin) = parse_edges(value) {
+                style.margin = margin;
+            }
+        },
+        "padding" => {
+            if let Some(padding) = parse_edges(value) {
+                style.padding = padding;
+            }
+        },
+        "margin-top" | "margin-right" | "margin-bottom" | "margin-left" | "padding-top"
+        | "padding-right" | "padding-bottom" | "padding-left" => {
+            let (edges, side) = match property.split_once('-') {
+                Some(("margin", side)) => (&mut style.margin, side),
+                Some((_, side)) => (&mut style.padding, side),
+                None => return,
+            };
+            if let Some(size) = parse_length(value) {
+                match side {
+                    "top" => edges.top = size,
+                    "right" => edges.right = size,
+                    "bottom" => edges.bottom = size,
+                    _ => edges.left = size,
+                }
+            }
+        },
+        "border" => apply_border_shorthand(style, value),
+        "border-width" => {
+            if let Some(widths) = parse_edges(value) {
+                style.border_width = widths;
+            }
+        },
+        "border-style" => {
+            if let Some(border_style) = BorderStyle::parse(value) {
+                style.border_style = border_style;
+            }
+        },
+        "border-color" => {
+            if let Some(color) = Color::parse(value) {
+                style.border_color = Some(color);
+            }
+        },
+        _ => {},
+    }
+}
+
+/// Parse a CSS length in pixels. `auto` and unsupported units yield `None`.
+pub(crate) fn parse_length(value: &str) -> Option<f32> {
+    let value = value.trim();
+    let number = value.strip_suffix("px").unwrap_or(value);
+    if number != value || number == "0" {
+        number.trim().parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Apply the `border` shorthand (`<width> || <style> || <color>`, in any
+/// order). Omitted parts reset to their initial values.
+fn apply_border_shorthand(style: &mut ComputedStyle, value: &str) {
+    let mut width = None;
+    let mut border_style = None;
+    let mut color = None;
+    for part in value.split_whitespace() {
+        if let Some(w) = parse_length(part).filter(|_| width.is_none()) {
+            width = Some(w);
+        } else if let Some(s) = BorderStyle::parse(part).filter(|_| border_style.is_none()) {
+            border_style = Some(s);
+        } else if let Some(c) = Color::parse(part).filter(|_| color.is_none()) {
+            color = Some(c);
+        } else {
+            return;
+        }
+    }
+    // `medium` is 3px.
+    style.border_width = BoxEdges::uniform(width.unwrap_or(3.0));
+    style.border_style = border_style.unwrap_or_default();
+    style.border_color = color;
+}
+
+/// Parse one to four lengths in CSS edge order (top, right, bottom, left).
+fn parse_edges(value: &str) -> Option<BoxEdges> {
+    let sizes: Vec<f32> = value.split_whitespace().map(parse_length).collect::<Option<_>>()?;
+    let (top, right, bottom, left) = match sizes[..] {
+        [all] => (all, all, all, all),
+        [vertical, horizontal] => (vertical, horizontal, vertical, horizontal),
+        [top, horizontal, bottom] => (top, horizontal, bottom, horizontal),
+        [top, right, bottom, left] => (top, right, bottom, left),
+        _ => return None,
+    };
+    Some(BoxEdges { top, right, bottom, left })
+}
+
+/// Parse a `font-size` in px, em, rem or percent, or as an absolute or
+/// relative keyword. `em`, percentages and `smaller`/`larger` are relative to
+/// the parent's size.
+fn parse_font_size(value: &str, parent: f32) -> Option<f32> {
+    let value = value.trim().to_ascii_lowercase();
+    let keyword = match value.as_str() {
+        "xx-small" => Some(0.6),
+        "x-small" => Some(0.75),
+        "small" => Some(0.89),
+        "medium" => Some(1.0),
+        "large" => Some(1.2),
+        "x-large" => Some(1.5),
+        "xx-large" => Some(2.0),
+        _ => None,
+    };
+    let size = if let Some(scale) = keyword {
+        DEFAULT_FONT_SIZE * scale
+    } else if value == "smaller" {
+        parent / 1.2
+    } else if value == "larger" {
+        parent * 1.2
+    } else if let Some(rem) = value.strip_suffix("rem") {
+        rem.trim().parse::<f32>().ok()? * DEFAULT_FONT_SIZE
+    } else if let Some(em) = value.strip_suffix("em") {
+        em.trim().parse::<f32>().ok()? * parent
+    } else if let Some(percent) = value.strip_suffix('%') {
+        percent.trim().parse::<f32>().ok()? / 100.0 * parent
+    } else {
+        parse_length(&value)?
+    };
+    (size.is_finite() && size >= 0.0).then_some(size)
+}
+
+/// Parse a `font-weight` keyword or number. `bolder` and `lighter` are
+/// relative to the inherited weight.
+fn parse_font_weight(value: &str, inherited: u16) -> Option<u16> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "normal" => Some(400),
+        "bold" => Some(700),
+        "bolder" => Some(match inherited {
+            0..=349 => 400,
+            350..=549 => 700,
+            _ => 900,
+        }),
+        "lighter" => Some(match inherited {
+            0..=549 => 100,
+            550..=749 => 400,
+            _ => 700,
+        }),
+        number => number.parse().ok().filter(|w| (1..=1000).contains(w)),
+    }
+}
+
+/// Parse an opacity number or percentage, clamped to `0.0..=1.0`.
+fn parse_opacity(value: &str) -> Option<f32> {
+    let value = value.trim();
+    let opacity: f32 = match value.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f32>().ok()? / 100.0,
+        None => value.parse().ok()?,
+    };
+    opacity.is_finite().then(|| opacity.clamp(0.0, 1.0))
+}
+
+impl BorderStyle {
+    /// Parse a CSS `border-style` keyword. Styles other than `solid` and
+    /// `none` are not supported.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "none" | "hidden" => Some(Self::None),
+            "solid" => Some(Self::Solid),
+            _ => None,
+        }
+    }
+}
+
+impl Color {
+    /// Parse a CSS color: `#rgb`, `#rrggbb`, `#rrggbbaa`, `rgb()`, `rgba()`,
+    /// `transparent` or one of the HTML basic color keywords.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_ascii_lowercase();
+        if let Some(hex) = value.strip_prefix('#') {
+            let digits: Vec<u8> =
+                hex.chars().map(|c| c.to_digit(16).map(|d| d as u8)).collect::<Option<_>>()?;
+            return match digits[..] {
+                [r, g, b] => Some(Self { r: r * 17, g: g * 17, b: b * 17, a: 255 }),
+                [r1, r2, g1, g2, b1, b2] => {
+                    Some(Self { r: r1 * 16 + r2, g: g1 * 16 + g2, b: b1 * 16 + b2, a: 255 })
+                },
+                [r1, r2, g1, g2, b1, b2, a1, a2] => Some(Self {
+                    r: r1 * 16 + r2,
+                    g: g1 * 16 + g2,
+                    b: b1 * 16 + b2,
+                    a: a1 * 16 + a2,
+                }),
+                _ => None,
+            };
+        }
+        if let Some(args) = value
+            .strip_prefix("rgba(")
+            .or_else(|| value.strip_prefix("rgb("))
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            return Self::parse_rgb_function(args);
+        }
+
+        let rgb = |r, g, b| Some(Self { r, g, b, a: 255 });
+        match value.as_str() {
+            "transparent" => Some(Self::TRANSPARENT),
+            "black" => Some(Self::BLACK),
+            "white" => Some(Self::WHITE),
+            "silver" => rgb(192, 192, 192),
+            "gray" | "grey" => rgb(128, 128, 128),
+            "maroon" => rgb(128, 0, 0),
+            "red" => rgb(255, 0, 0),
+            "purple" => rgb(128, 0, 128),
+            "fuchsia" | "magenta" => rgb(255, 0, 255),
+            "green" => rgb(0, 128, 0),
+            "lime" => rgb(0, 255, 0),
+            "olive" => rgb(128, 128, 0),
+            "yellow" => rgb(255, 255, 0),
+            "navy" => rgb(0, 0, 128),
+            "blue" => rgb(0, 0, 255),
+            "teal" => rgb(0, 128, 128),
+            "aqua" | "cyan" => rgb(0, 255, 255),
+            "orange" => rgb(255, 165, 0),
+            "rebeccapurple" => rgb(102, 51, 153),
+            _ => None,
+        }
+    }
+
+    /// Arguments of `rgb()`/`rgba()`: three channels as integers or
+    /// percentages, then an optional alpha as a number or percentage.
+    /// Both comma- and space-separated forms are accepted.
+    fn parse_rgb_function(args: &str) -> Option<Self> {
+        let parts: Vec<&str> =
+            args.split([',', ' ', '/']).map(str::trim).filter(|p| !p.is_empty()).collect();
+        let channel = |part: &str| -> Option<u8> {
+            let value = match part.strip_suffix('%') {
+                Some(percent) => percent.parse::<f32>().ok()? * 2.55,
+                None => part.parse::<f32>().ok()?,
+            };
+            value.is_finite().then(|| value.round().clamp(0.0, 255.0) as u8)
+        };
+        let alpha = |part: &str| -> Option<u8> {
+            let value = match part.strip_suffix('%') {
+                Some(percent) => percent.parse::<f32>().ok()? / 100.0,
+                None => part.parse::<f32>().ok()?,
+            };
+            value.is_finite().then(|| (value.clamp(0.0, 1.0) * 255.0).round() as u8)
+        };
+        match parts[..] {
+            [r, g, b] => Some(Self { r: channel(r)?, g: channel(g)?, b: channel(b)?, a: 255 }),
+            [r, g, b, a] => {
+                Some(Self { r: channel(r)?, g: channel(g)?, b: channel(b)?, a: alpha(a)? })
+            },
+            _ => None,
+        }
+    }
+}
+
+impl TextAlign {
+    /// Parse a CSS `text-align` keyword. `start`/`justify` align left and
+    /// `end` aligns right.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "left" | "start" | "justify" => Some(Self::Left),
+            "center" => Some(Self::Center),
+            "right" | "end" => Some(Self::Right),
+            _ => None,
+        }
+    }
+}
+
+impl FontStyle {
+    /// Parse a CSS `font-style` keyword.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "normal" => Some(Self::Normal),
+            "italic" => Some(Self::Italic),
+            "oblique" => Some(Self::Oblique),
+            _ => None,
+        }
+    }
+}
+
+impl WhiteSpace {
+    /// Parse a CSS `white-space` keyword.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "normal" => Some(Self::Normal),
+            "nowrap" => Some(Self::Nowrap),
+            "pre" => Some(Self::Pre),
+            _ => None,
+        }
+    }
+
+    /// Text as rendered under this mode: `pre` keeps it verbatim, the other
+    /// modes collapse whitespace runs to single spaces and trim the ends.
+    pub fn apply(self, text: &str) -> Cow<'_, str> {
+        match self {
+            Self::Pre => Cow::Borrowed(text),
+            Self::Normal | Self::Nowrap => {
+                Cow::Owned(text.split_whitespace().collect::<Vec<_>>().join(" "))
+            },
+        }
+    }
+
+    /// Whether long lines wrap at the box edge.
+    pub fn wraps(self) -> bool {
+        self == Self::Normal
+    }
+}
+
+impl Visibility {
+    /// Parse a CSS `visibility` keyword. `collapse` is treated as `hidden`.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "visible" => Some(Self::Visible),
+            "hidden" | "collapse" => Some(Self::Hidden),
+            _ => None,
+        }
+    }
+}
+
+impl FlexDirection {
+    /// Parse a CSS `flex-direction` keyword.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "row" | "row-reverse" => Some(Self::Row),
+            "column" | "column-reverse" => Some(Self::Column),
+            _ => None,
+        }
+    }
+}
+
+impl Display {
+    /// Parse a CSS `display` keyword.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "block" => Some(Self::Block),
+            "inline" => Some(Self::Inline),
+            "inline-block" => Some(Self::InlineBlock),
+            "flex" => Some(Self::Flex),
+            "inline-flex" => Some(Self::InlineFlex),
+            "grid" => Some(Self::Grid),
+            "list-item" => Some(Self::ListItem),
+            "table" => Some(Self::Table),
+            "contents" => Some(Self::Contents),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(selector: &str, property: &str, value: &str) -> CssRule {
+        CssRule {
+            selector:     selector.into(),
+            declarations: vec![(property.into(), value.into())],
+        }
+    }
+
+    fn sheet(rules: Vec<CssRule>) -> StyleSheet {
+        StyleSheet { rules }
+    }
+
+    #[test]
+    fn test_user_rule_overrides_normal_author_rule() {
+        let author = sheet(vec![rule("p", "width", "100px")]);
+        let user = sheet(vec![rule("p", "width", "300px")]);
+        let cascade = Cascade::new()
+            .with_sheet(StyleOrigin::User, &user)
+            .with_sheet(StyleOrigin::Author, &author);
+
+        let style = cascade.computed_style(&Element::new("p"), None);
+        assert_eq!(style.width, Some(300.0));
+    }
+
+    #[test]
+    fn test_author_important_beats_user_rule() {
+        let author = sheet(vec![rule("p", "width", "100px !important")]);
+        let user = sheet(vec![rule("p", "width", "300px")]);
+        let cascade = Cascade::new()
+            .with_sheet(StyleOrigin::User, &user)
+            .with_sheet(StyleOrigin::Author, &author);
+
+        let style = cascade.computed_style(&Element::new("p"), None);
+        assert_eq!(style.width, Some(100.0));
+    }
+
+    #[test]
+    fn test_user_important_beats_author_important() {
+        let author = sheet(vec![rule("p", "width", "100px !important")]);
+        let user = sheet(vec![rule("p", "width", "300px !important")]);
+        let cascade = Cascade::new()
+            .with_sheet(StyleOrigin::Author, &author)
+            .with_sheet(StyleOrigin::User, &user);
+
+        let style = cascade.computed_style(&Element::new("p"), None);
+        assert_eq!(style.width, Some(300.0));
+    }
+
+    #[test]
+    fn test_specificity_and_source_order() {
+        let author = sheet(vec![
+            rule("#main", "display", "flex"),
+            rule(".box", "display", "inline"),
+            rule("div", "display", "none"),
+            rule("div", "width", "10px"),
+            rule("div", "width", "20px"),
+        ]);
+        let cascade = Cascade::new().with_sheet(StyleOrigin::Author, &author);
+        let element = Element::new("div")
+            .with_attribute("id", "main")
+            .with_attribute("class", "a box");
+
+        let style = cascade.computed_style(&element, None);
+        assert!(matches!(style.display, Display::Flex));
+        assert_eq!(style.width, Some(20.0));
+
+        // Equal specificity: the later rule wins regardless of selector text.
+        let author = sheet(vec![rule(".box", "width", "30px"), rule(".a", "width", "40px")]);
+        let cascade = Cascade::new().with_sheet(StyleOrigin::Author, &author);
+        assert_eq!(cascade.computed_style(&element, None).width, Some(40.0));
+    }
+
+    #[test]
+    fn test_display_keywords() {
+        assert_eq!(Display::parse("inline-flex"), Some(Display::InlineFlex));
+        assert_eq!(Display::parse(" GRID "), Some(Display::Grid));
+        assert_eq!(Display::parse("list-item"), Some(Display::ListItem));
+        assert_eq!(Display::parse("table"), Some(Display::Table));
+        assert_eq!(Display::parse("contents"), Some(Display::Contents));
+        assert_eq!(Display::parse("run-in"), None);
+
+        let author = sheet(vec![rule("span", "display", "inline-flex")]);
+        let cascade = Cascade::new().with_sheet(StyleOrigin::Author, &author);
+        let style = cascade.computed_style(&Element::new("span"), None);
+        assert_eq!(style.display, Display::InlineFlex);
+    }
+
+    #[test]
+    fn test_visibility_inherits_and_opacity_clamps() {
+        let author = sheet(vec![
+            rule("div", "visibility", "hidden"),
+            rule("div", "opacity", "150%"),
+            rule("em", "opacity", "0.25"),
+        ]);
+        let cascade = Cascade::new().with_sheet(StyleOrigin::Author, &author);
+
+        let div = cascade.computed_style(&Element::new("div"), None);
+        assert_eq!(div.visibility, Visibility::Hidden);
+        assert_eq!(div.opacity, 1.0);
+
+        let em = cascade.computed_style(&Element::new("em"), Some(&div));
+        assert_eq!(em.visibility, Visibility::Hidden);
+        assert_eq!(em.opacity, 0.25);
+    }
+
+    #[test]
+    fn test_border_shorthand_and_longhands() {
+        let author = sheet(vec![
+            rule("div", "border", "2px solid #000"),
+            rule("p", "border-width", "1px 4px"),
+            rule("p", "border-style", "solid"),
+            rule("p", "border-color", "#f00"),
+        ]);
+        let cascade = Cascade::new().with_sheet(StyleOrigin::Author, &author);
+
+        let div = cascade.computed_style(&Element::new("div"), None);
+        assert_eq!(div.border_width, BoxEdges::uniform(2.0));
+        assert_eq!(div.border_style, BorderStyle::Solid);
+        assert_eq!(div.border_color, Some(Color::BLACK));
+
+        let p = cascade.computed_style(&Element::new("p"), None);
+        assert_eq!(p.border_width, BoxEdges { top: 1.0, right: 4.0, bottom: 1.0, left: 4.0 });
+        assert_eq!(p.border_color, Some(Color { r: 255, g: 0, b: 0, a: 255 }));
+        assert_eq!(Color::parse("#zz0"), None);
+    }
+
+    #[test]
+    fn test_background_image_resolves_against_base() {
+        let author = sheet(vec![
+            rule("div", "background-image", "url(\"../img/bg.png\")"),
+            rule("p", "background-image", "url(tile.png)"),
+            rule("p.plain", "background-image", "none"),
+            rule("p.fancy", "background-image", "linear-gradient(red, blue)"),
+        ]);
+        let cascade = Cascade::new()
+            .with_sheet(StyleOrigin::Author, &author)
+            .with_base_url("https://example.com/css/site/");
+
+        let div = cascade.computed_style(&Element::new("div"), None);
+        assert_eq!(div.background_image.as_deref(), Some("https://example.com/css/img/bg.png"));
+        let p = cascade.computed_style(&Element::new("p"), None);
+        assert_eq!(p.background_image.as_deref(), Some("https://example.com/css/site/tile.png"));
+        let plain =
+            cascade.computed_style(&Element::new("p").with_attribute("class", "plain"), None);
+        assert_eq!(plain.background_image, None);
+        let fancy =
+            cascade.computed_style(&Element::new("p").with_attribute("class", "fancy"), None);
+        assert_eq!(fancy.background_image, p.background_image);
+    }
+
+    #[test]
+    fn test_typography_properties_inherit() {
+        let author = sheet(vec![
+            rule("div", "text-align", "center"),
+            rule("div", "font-weight", "bold"),
+            rule("em", "font-style", "italic"),
+            rule("em", "font-weight", "bolder"),
+        ]);
+        let cascade = Cascade::new().with_sheet(StyleOrigin::Author, &author);
+
+        let div = cascade.computed_style(&Element::new("div"), None);
+        assert_eq!((div.text_align, div.font_weight), (TextAlign::Center, 700));
+
+        let em = cascade.computed_style(&Element::new("em"), Some(&div));
+        assert_eq!(em.text_align, TextAlign::Center);
+        assert_eq!((em.font_weight, em.font_style), (900, FontStyle::Italic));
+    }
+
+    #[test]
+    fn test_white_space_modes() {
+        let text = "  a  b\n c ";
+        assert_eq!(WhiteSpace::Normal.apply(text), "a b c");
+        assert_eq!(WhiteSpace::Nowrap.apply(text), "a b c");
+        assert_eq!(WhiteSpace::Pre.apply(text), text);
+
+        let author = sheet(vec![rule("pre", "white-space", "pre")]);
+        let cascade = Cascade::new().with_sheet(StyleOrigin::Author, &author);
+        let pre = cascade.computed_style(&Element::new("pre"), None);
+        let code = cascade.computed_style(&Element::new("code"), Some(&pre));
+        assert_eq!(code.white_space, WhiteSpace::Pre);
+    }
+
+    #[test]
+    fn test_custom_properties_inherit_from_root() {
+        let author = sheet(vec![
+            rule(":root", "--c", "red"),
+            rule(":root", "--gap", "var(--base, 12px)"),
+            rule("a", "color", "var(--c)"),
+            rule("a", "width", "var(--gap)"),
+        ]);
+        let cascade = Cascade::new().with_sheet(StyleOrigin::Author, &author);
+        let html = Element::new("html");
+        let body = Element::new("body");
+        let link = Element::new("a");
+
+        let root = cascade.computed_style_in(&html, &[], None);
+        assert_eq!(root.custom_properties.get("--gap").map(String::as_str), Some("12px"));
+        let body_style = cascade.computed_style_in(&body, &[&html], Some(&root));
+        let link_style = cascade.computed_style_in(&link, &[&html, &body], Some(&body_style));
+        assert_eq!(Some(link_style.color), Color::parse("red"));
+        assert_eq!(link_style.width, Some(12.0));
+
+        // `:root` only matches the element without ancestors.
+        let nested = cascade.computed_style_in(&body, &[&html], None);
+        assert!(nested.custom_properties.is_empty());
+    }
+
+    #[test]
+    fn test_var_fallback_when_unset() {
+        let author = sheet(vec![
+            rule("p", "color", "var(--missing, blue)"),
+            rule("p", "width", "var(--missing)"),
+            rule("em", "--a", "var(--b)"),
+            rule("em", "--b", "var(--a)"),
+            rule("em", "color", "var(--a, green)"),
+        ]);
+        let cascade = Cascade::new().with_sheet(StyleOrigin::Author, &author);
+
+        let p = cascade.computed_style(&Element::new("p"), None);
+        assert_eq!(Some(p.color), Color::parse("blue"));
+        assert_eq!(p.width, None);
+
+        // Cyclic references are unset, so the fallback applies.
+        let em = cascade.computed_style(&Element::new("em"), None);
+        assert!(em.custom_properties.is_empty());
+        assert_eq!(Some(em.color), Color::parse("green"));
+    }
+
+    #[test]
+    fn test_var_substitution_length_is_capped() {
+        let mut rules = vec![rule("div", "--v0", "xx")];
+        for level in 1..=40 {
+            let previous = format!("var(--v{})", level - 1);
+            rules.push(rule("div", &format!("--v{level}"), &format!("{previous} {previous}")));
+        }
+        rules.push(rule("div", "color", "var(--v40, red)"));
+        rules.push(rule("div", "width", "var(--v40)"));
+        let author = sheet(rules);
+        let cascade = Cascade::new().with_sheet(StyleOrigin::Author, &author);
+
+        let style = cascade.computed_style(&Element::new("div"), None);
+        assert_eq!(style.custom_properties.get("--v1").map(String::as_str), Some("xx xx"));
+        assert!(!style.custom_properties.contains_key("--v40"));
+        assert!(
+            style
+                .custom_properties
+                .values()
+                .all(|value| value.len() <= MAX_SUBSTITUTED_LENGTH)
+        );
+        assert_eq!(Some(style.color), Color::parse("red"));
+        assert_eq!(style.width, None);
+    }
+
+    #[test]
+    fn test_margin_and_padding() {
+        let author = sheet(vec![
+            rule("div", "margin", "4px 8px"),
+            rule("div", "margin-left", "1px"),
+            rule("div", "padding", "2px"),
+            rule("div", "padding-bottom", "6px"),
+            rule("div", "padding-top", "auto"),
+        ]);
+        let cascade = Cascade::new().with_sheet(StyleOrigin::Author, &author);
+
+        let style = cascade.computed_style(&Element::new("div"), None);
+        assert_eq!(style.margin, BoxEdges { top: 4.0, right: 8.0, bottom: 4.0, left: 1.0 });
+        assert_eq!(style.padding, BoxEdges { top: 2.0, right: 2.0, bottom: 6.0, left: 2.0 });
+    }
+
+    #[test]
+    fn test_user_agent_defaults() {
+        let cascade = Cascade::new().with_sheet(StyleOrigin::UserAgent, user_agent_stylesheet());
+        let html = Element::new("html");
+        let root = cascade.computed_style_in(&html, &[], None);
+
+        let link = Element::new("a").with_attribute("href", "/next");
+        let link_style = cascade.computed_style_in(&link, &[&html], Some(&root));
+        assert_eq!(Some(link_style.color), Color::parse("#0000ee"));
+        assert_eq!(link_style.display, Display::Inline);
+
+        let heading = cascade.computed_style_in(&Element::new("h1"), &[&html], Some(&root));
+        assert_eq!(heading.display, Display::Block);
+        assert_eq!(heading.font_size, 32.0);
+        assert_eq!(heading.font_weight, 700);
+        let head = cascade.computed_style_in(&Element::new("head"), &[&html], Some(&root));
+        assert_eq!(head.display, Display::None);
+
+        // Author rules still win over the defaults.
+        let author = sheet(vec![rule("h1", "font-size", "1.5rem")]);
+        let cascade = cascade.with_sheet(StyleOrigin::Author, &author);
+        let heading = cascade.computed_style_in(&Element::new("h1"), &[&html], Some(&root));
+        assert_eq!(heading.font_size, 24.0);
+    }
+
+    #[test]
+    fn test_color_parse_forms() {
+        assert_eq!(Color::parse("#ff0000"), Some(Color { r: 255, g: 0, b: 0, a: 255 }));
+        assert_eq!(Color::parse("#FF000080"), Some(Color { r: 255, g: 0, b: 0, a: 128 }));
+        assert_eq!(Color::parse("rgb(0, 128, 255)"), Some(Color { r: 0, g: 128, b: 255, a: 255 }));
+        assert_eq!(Color::parse("rgba(0, 0, 0, 0.5)"), Some(Color { r: 0, g: 0, b: 0, a: 128 }));
+        assert_eq!(Color::parse("rebeccapurple"), Some(Color { r: 102, g: 51, b: 153, a: 255 }));
+        assert_eq!(Color::parse(" Red "), Some(Color { r: 255, g: 0, b: 0, a: 255 }));
+        assert_eq!(Color::parse("transparent"), Some(Color::TRANSPARENT));
+
+        for invalid in [
+            "#zz",
+            "#12345",
+            "rgb(1, 2)",
+            "rgb(a, b, c)",
+            "rgb(1, 2, 3",
+            "chartreuse!",
+        ] {
+            assert_eq!(Color::parse(invalid), None, "{invalid}");
+        }
+    }
+}
@@ -1,5 +1,10 @@
 //! Browser plugin type definitions.
 
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    time::Duration,
+};
+
 /// HTML document representation.
 #[derive(Debug, Clone)]
 pub struct Document {
@@ -12,7 +17,7 @@ pub struct Document {
 }
 
 /// HTML element.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Element {
     /// Tag name.
     pub tag:          String,
@@ -54,6 +59,58 @@ impl Element {
     }
 }
 
+/// A single change turning one document into another, as produced by
+/// [`Document::diff`].
+///
+/// Paths are child indices from the root element and are valid at the point
+/// the patch is applied, with earlier patches in the list already applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DomPatch {
+    /// Replace the element at `path` with a new subtree.
+    Replace {
+        path:    Vec<usize>,
+        element: Element,
+    },
+    /// Insert `element` as child `index` of the element at `parent`.
+    Insert {
+        parent:  Vec<usize>,
+        index:   usize,
+        element: Element,
+    },
+    /// Remove the element at `path`.
+    Remove { path: Vec<usize> },
+    /// Add or change an attribute.
+    SetAttribute {
+        path:  Vec<usize>,
+        name:  String,
+        value: String,
+    },
+    /// Remove an attribute.
+    RemoveAttribute { path: Vec<usize>, name: String },
+    /// Replace an element's text content.
+    SetText {
+        path: Vec<usize>,
+        text: Option<String>,
+    },
+    /// Change the document title.
+    SetTitle(String),
+}
+
+/// Statistics gathered while parsing a document.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseStats {
+    /// Elements parsed from the markup.
+    pub element_count:    usize,
+    /// Deepest nesting of parsed elements (a top-level element is depth 1).
+    pub max_depth:        usize,
+    /// Bytes of non-whitespace text between tags.
+    pub text_bytes:       usize,
+    /// `<!-- -->` comments seen.
+    pub comment_count:    usize,
+    /// Malformed constructs the parser recovered from.
+    pub recovered_errors: usize,
+}
+
 /// CSS stylesheet.
 #[derive(Debug, Clone, Default)]
 pub struct StyleSheet {
@@ -90,34 +147,259 @@ pub struct RenderNode {
     pub children:       Vec<RenderNode>,
 }
 
+/// Initial font size (px); also the base for `rem` units.
+pub(crate) const DEFAULT_FONT_SIZE: f32 = 16.0;
+
 /// Computed CSS style.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct ComputedStyle {
     /// Display mode.
-    pub display:          Display,
+    pub display:           Display,
+    /// Main axis of a flex container.
+    pub flex_direction:    FlexDirection,
     /// Width in pixels.
-    pub width:            Option<f32>,
+    pub width:             Option<f32>,
     /// Height in pixels.
-    pub height:           Option<f32>,
+    pub height:            Option<f32>,
     /// Background color.
-    pub background_color: Color,
+    pub background_color:  Color,
+    /// Resolved `background-image` URL. Fetching it is up to the embedder.
+    pub background_image:  Option<String>,
     /// Text color.
-    pub color:            Color,
+    pub color:             Color,
+    /// Visibility; hidden boxes keep their layout but are not painted.
+    pub visibility:        Visibility,
+    /// Opacity from 0.0 (transparent) to 1.0, applied to the box and its
+    /// descendants when painting.
+    pub opacity:           f32,
+    /// Margin widths in pixels.
+    pub margin:            BoxEdges,
+    /// Border widths in pixels.
+    pub border_width:      BoxEdges,
+    /// Border line style.
+    pub border_style:      BorderStyle,
+    /// Border color; `None` uses the text color (`currentcolor`).
+    pub border_color:      Option<Color>,
+    /// Padding widths in pixels.
+    pub padding:           BoxEdges,
+    /// Horizontal alignment of text within its line box.
+    pub text_align:        TextAlign,
+    /// Font size in pixels.
+    pub font_size:         f32,
+    /// Font weight, 100–900 (400 is normal, 700 bold).
+    pub font_weight:       u16,
+    /// Font style.
+    pub font_style:        FontStyle,
+    /// Whitespace collapsing and wrapping mode.
+    pub white_space:       WhiteSpace,
+    /// Custom properties (`--name`) with `var()` references substituted.
+    /// Inherited.
+    pub custom_properties: BTreeMap<String, String>,
+}
+
+impl Default for ComputedStyle {
+    fn default() -> Self {
+        Self {
+            display:           Display::default(),
+            flex_direction:    FlexDirection::default(),
+            width:             None,
+            height:            None,
+            background_color:  Color::TRANSPARENT,
+            background_image:  None,
+            color:             Color::BLACK,
+            visibility:        Visibility::default(),
+            opacity:           1.0,
+            margin:            BoxEdges::default(),
+            border_width:      BoxEdges::default(),
+            border_style:      BorderStyle::default(),
+            border_color:      None,
+            padding:           BoxEdges::default(),
+            text_align:        TextAlign::default(),
+            font_size:         DEFAULT_FONT_SIZE,
+            font_weight:       400,
+            font_style:        FontStyle::default(),
+            white_space:       WhiteSpace::default(),
+            custom_properties: BTreeMap::new(),
+        }
+    }
 }
 
 /// Display mode.
-#[derive(Debug, Clone, Copy, Default)]
+///
+/// Layout flows `inline-flex` like `inline-block` and treats the modes it
+/// does not implement yet (grid, table, list items, `contents`) as block,
+/// but the computed value is kept for devtools.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum Display {
     #[default]
     Block,
     Inline,
     InlineBlock,
     Flex,
+    InlineFlex,
+    Grid,
+    ListItem,
+    Table,
+    Contents,
     None,
 }
 
+/// CSS `flex-direction` value. The `-reverse` variants lay out in the
+/// forward direction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FlexDirection {
+    /// Children placed left to right.
+    #[default]
+    Row,
+    /// Children stacked top to bottom, as in block layout.
+    Column,
+}
+
+/// Per-side sizes of a box edge: margin, border or padding.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BoxEdges {
+    pub top:    f32,
+    pub right:  f32,
+    pub bottom: f32,
+    pub left:   f32,
+}
+
+impl BoxEdges {
+    /// The same size on all four sides.
+    pub fn uniform(size: f32) -> Self {
+        Self { top: size, right: size, bottom: size, left: size }
+    }
+}
+
+/// CSS `border-style` value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BorderStyle {
+    #[default]
+    None,
+    Solid,
+}
+
+/// CSS `text-align` value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// CSS `font-style` value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FontStyle {
+    #[default]
+    Normal,
+    Italic,
+    Oblique,
+}
+
+/// CSS `white-space` value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WhiteSpace {
+    /// Collapse whitespace and wrap lines.
+    #[default]
+    Normal,
+    /// Collapse whitespace but never wrap.
+    Nowrap,
+    /// Preserve spaces and newlines; never wrap.
+    Pre,
+}
+
+/// A JavaScript primitive value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsValue {
+    Undefined,
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+}
+
+/// Privacy protection level.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PrivacyMode {
+    #[default]
+    Standard,
+    /// Tightened defaults; content is only enabled where allowed explicitly.
+    Strict,
+    /// Private browsing; content that could leak over insecure connections is
+    /// held back.
+    Private,
+}
+
+/// Treatment of `http:` subresources on `https:` pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MixedContentPolicy {
+    /// Load them as referenced.
+    Allow,
+    /// Load them over `https:` instead.
+    Upgrade,
+    /// Do not load them.
+    Block,
+}
+
+/// How much of the requesting page's URL is sent in the `Referer` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferrerPolicy {
+    /// Never send a referrer.
+    NoReferrer,
+    /// Send only the page's origin.
+    Origin,
+    /// Send the full URL to the same origin and nothing cross-origin.
+    SameOrigin,
+    /// Send only the page's origin, and nothing from `https:` to `http:`.
+    StrictOrigin,
+}
+
+/// Length of a page's readable text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadingStats {
+    /// Whitespace-separated words.
+    pub word_count:       usize,
+    /// Reading speed the estimate assumes.
+    pub words_per_minute: u32,
+    /// Estimated time to read the words, rounded up to whole seconds.
+    pub reading_time:     Duration,
+}
+
+/// What the browser reveals about itself to servers and pages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FingerprintProfile {
+    /// `User-Agent` sent with every request.
+    pub user_agent:             String,
+    /// Viewport width reported to pages (px).
+    pub viewport_width:         f32,
+    /// Viewport height reported to pages (px).
+    pub viewport_height:        f32,
+    /// Zoom factor reported to pages.
+    pub zoom:                   f32,
+    /// Whether the values above are normalized to resist fingerprinting.
+    pub resists_fingerprinting: bool,
+}
+
+/// Capability that can be granted or denied per origin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Permission {
+    Cookies,
+    Scripts,
+    Images,
+    Popups,
+}
+
+/// CSS `visibility` value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Visibility {
+    #[default]
+    Visible,
+    Hidden,
+}
+
 /// RGBA color.
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -129,6 +411,11 @@ impl Color {
     pub const WHITE: Color = Color { r: 255, g: 255, b: 255, a: 255 };
     pub const BLACK: Color = Color { r: 0, g: 0, b: 0, a: 255 };
     pub const TRANSPARENT: Color = Color { r: 0, g: 0, b: 0, a: 0 };
+
+    /// This color with its alpha scaled by `opacity` (0.0–1.0).
+    pub fn with_opacity(self, opacity: f32) -> Self {
+        Self { a: (self.a as f32 * opacity.clamp(0.0, 1.0)).round() as u8, ..self }
+    }
 }
 
 /// Layout box dimensions.
@@ -140,19 +427,234 @@ pub struct LayoutBox {
     pub height: f32,
 }
 
+/// Paint command emitted for a laid-out render tree.
+#[derive(Debug, Clone)]
+pub enum DisplayItem {
+    /// Filled rectangle.
+    Rect { layout: LayoutBox, color: Color },
+    /// Run of text laid out inside a box.
+    Text {
+        layout:      LayoutBox,
+        content:     String,
+        color:       Color,
+        font_weight: u16,
+        font_style:  FontStyle,
+    },
+}
+
+/// A find-in-page match within a laid-out text run.
+#[derive(Debug, Clone)]
+pub struct FindMatch {
+    /// Child-index path from the render tree root to the matching node.
+    pub path:    Vec<usize>,
+    /// Character offset where the match starts in the node's text.
+    pub start:   usize,
+    /// Character offset just past the end of the match.
+    pub end:     usize,
+    /// Box covering the matched characters.
+    pub layout:  LayoutBox,
+    /// Whether this is the currently selected match.
+    pub current: bool,
+}
+
+/// Find-in-page session of a tab.
+#[derive(Debug, Clone)]
+pub struct FindState {
+    /// Text being searched for.
+    pub query:   String,
+    /// Matches in document order; the current one is marked.
+    pub matches: Vec<FindMatch>,
+    /// Index of the current match in `matches`.
+    pub current: usize,
+}
+
+/// A caret position within a laid-out text run.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TextPosition {
+    /// Child-index path from the render tree root to the text's node.
+    pub path:   Vec<usize>,
+    /// Character offset in the node's rendered text.
+    pub offset: usize,
+}
+
+/// Layout viewport requested by a `<meta name="viewport">` tag.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ViewportMeta {
+    /// Requested layout width; `None` if not specified.
+    pub width:         Option<ViewportWidth>,
+    /// Requested initial zoom factor.
+    pub initial_scale: Option<f32>,
+}
+
+/// Width value of a viewport meta tag.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ViewportWidth {
+    /// `width=device-width`.
+    DeviceWidth,
+    /// Fixed width in CSS pixels.
+    Pixels(f32),
+}
+
+/// A `<link>` hint telling the browser to warm up a connection or fetch a
+/// resource early.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceHint {
+    /// Kind of hint.
+    pub kind:        ResourceHintKind,
+    /// Resolved URL of the hinted origin or resource.
+    pub url:         String,
+    /// Request destination of a preload (its `as` attribute), lowercased.
+    pub destination: Option<String>,
+}
+
+/// Kind of resource hint, from the link's `rel`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceHintKind {
+    /// `preconnect`: open a connection to the origin.
+    Preconnect,
+    /// `dns-prefetch`: resolve the origin's host name.
+    DnsPrefetch,
+    /// `preload`: fetch the resource for use by the current page.
+    Preload,
+}
+
+/// Directives from a `<meta name="robots">` tag.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RobotsDirectives {
+    /// Page should not be indexed.
+    pub noindex:   bool,
+    /// Links on the page should not be followed.
+    pub nofollow:  bool,
+    /// Page should not be cached or archived.
+    pub noarchive: bool,
+    /// No snippet should be shown in results.
+    pub nosnippet: bool,
+}
+
+/// Node of the accessibility tree exposed to assistive technology.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AxNode {
+    /// Semantic role.
+    pub role:     AxRole,
+    /// Accessible name; empty if the node has none.
+    pub name:     String,
+    /// Heading level (1–6) for headings.
+    pub level:    Option<u8>,
+    /// Checked state for checkboxes and radio buttons.
+    pub checked:  Option<bool>,
+    /// Expanded state from `aria-expanded`.
+    pub expanded: Option<bool>,
+    /// Whether the control is disabled.
+    pub disabled: bool,
+    /// Child nodes, with presentational wrappers flattened away.
+    pub children: Vec<AxNode>,
+}
+
+/// Accessibility role of an [`AxNode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AxRole {
+    Document,
+    Article,
+    Banner,
+    Button,
+    Cell,
+    Checkbox,
+    ComboBox,
+    Complementary,
+    ContentInfo,
+    Dialog,
+    Form,
+    Heading,
+    Image,
+    Link,
+    List,
+    ListItem,
+    Main,
+    Navigation,
+    Paragraph,
+    Radio,
+    Region,
+    Row,
+    Table,
+    TextBox,
+    /// A run of text content.
+    StaticText,
+}
+
 /// Browser tab.
 #[derive(Debug, Clone)]
 pub struct BrowserTab {
     /// Tab ID.
-    pub id:               u64,
+    pub id:                 u64,
     /// Current URL.
-    pub url:              String,
+    pub url:                String,
     /// Page title.
-    pub title:            String,
+    pub title:              String,
     /// Navigation state.
-    pub navigation_state: NavigationState,
+    pub navigation_state:   NavigationState,
     /// Loaded document.
-    pub document:         Option<Document>,
+    pub document:           Option<Document>,
+    /// Original document, kept while reader mode is active.
+    pub reader_original:    Option<Document>,
+    /// Resolved favicon URL of the current document.
+    pub favicon:            Option<String>,
+    /// Image URLs already fetched for the current document.
+    pub loaded_images:      HashSet<String>,
+    /// Image URLs that failed to load for the current document; they are
+    /// retried after the next navigation or reload.
+    pub failed_images:      HashSet<String>,
+    /// Linked stylesheets fetched for the current document, by resolved URL.
+    pub linked_stylesheets: HashMap<String, StyleSheet>,
+    /// Subresource URLs blocked as mixed content for the current document.
+    pub blocked_urls:       HashSet<String>,
+    /// Pages visited in this tab, oldest first.
+    pub history:            Vec<HistoryEntry>,
+    /// Index of the current page in `history`.
+    pub history_index:      usize,
+    /// The document was dropped to save memory and is reloaded when the tab
+    /// is activated.
+    pub hibernated:         bool,
+    /// Find-in-page session for the current document.
+    pub find:               Option<FindState>,
+}
+
+/// Change in browser state reported to [`PluginObserver`]s.
+///
+/// [`PluginObserver`]: crate::PluginObserver
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PluginEvent {
+    /// A tab was opened.
+    TabCreated { tab_id: u64 },
+    /// A tab was closed.
+    TabClosed { tab_id: u64 },
+    /// A tab's title changed.
+    TitleChanged { tab_id: u64, title: String },
+    /// A tab began loading `url`.
+    LoadStarted { tab_id: u64, url: String },
+    /// A tab finished loading and laying out `url`.
+    LoadFinished { tab_id: u64, url: String },
+    /// Loading `url` in a tab failed.
+    Error {
+        tab_id:  u64,
+        url:     String,
+        message: String,
+    },
+    /// Fetching the image or favicon at `url` for a tab's page failed; the
+    /// page itself still loaded.
+    ResourceFailed {
+        tab_id:  u64,
+        url:     String,
+        message: String,
+    },
+}
+
+/// A page in a tab's session history.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    /// URL navigated to.
+    pub url:      String,
+    /// Vertical scroll offset when the page was left (px).
+    pub scroll_y: f32,
 }
 
 /// Navigation state.
@@ -165,6 +667,17 @@ pub enum NavigationState {
     Error,
 }
 
+/// Time spent in each rendering stage (ms).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RenderTiming {
+    /// Resolving computed styles and building the render tree.
+    pub style_ms:  f64,
+    /// Laying out the render tree.
+    pub layout_ms: f64,
+    /// Building the display list.
+    pub paint_ms:  f64,
+}
+
 /// Page performance metrics.
 #[derive(Debug, Clone, Default)]
 pub struct PageMetrics {
@@ -176,4 +689,6 @@ pub struct PageMetrics {
     pub load_complete:          f64,
     /// First contentful paint (ms).
     pub first_contentful_paint: f64,
+    /// Breakdown of the latest render.
+    pub render_timing:          RenderTiming,
 }
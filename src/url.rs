@@ -0,0 +1,218 @@
+//! URL resolution.
+
+/// Resolve a reference against a base URL.
+///
+/// Absolute references are returned unchanged. Bases without an authority
+/// (`about:blank`, `data:`) cannot anchor relative references, which are then
+/// returned as-is.
+pub(crate) fn resolve(base: &str, reference: &str) -> String {
+    let reference = reference.trim();
+    if has_scheme(reference) {
+        return reference.to_string();
+    }
+    let Some((scheme, rest)) = base.split_once("://") else {
+        return reference.to_string();
+    };
+
+    let rest = rest.split('#').next().unwrap_or(rest);
+    let authority_end = rest.find(['/', '?']).unwrap_or(rest.len());
+    let (authority, path_and_query) = rest.split_at(authority_end);
+    let (base_path, base_query) = match path_and_query.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (path_and_query, None),
+    };
+    let base_path = if base_path.is_empty() {
+        "/"
+    } else {
+        base_path
+    };
+
+    if let Some(network_path) = reference.strip_prefix("//") {
+        return format!("{scheme}://{network_path}");
+    }
+    if reference.is_empty() || reference.starts_with('#') {
+        let query = base_query.map(|q| format!("?{q}")).unwrap_or_default();
+        return format!("{scheme}://{authority}{base_path}{query}{reference}");
+    }
+    if reference.starts_with('?') {
+        return format!("{scheme}://{authority}{base_path}{reference}");
+    }
+
+    let suffix_start = reference.find(['?', '#']).unwrap_or(reference.len());
+    let (path, suffix) = reference.split_at(suffix_start);
+    let merged = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        let directory = &base_path[..=base_path.rfind('/').unwrap_or(0)];
+        format!("{directory}{path}")
+    };
+    format!("{scheme}://{authority}{}{suffix}", remove_dot_segments(&merged))
+}
+
+/// Lowercased host of an absolute URL, without userinfo or port.
+pub(crate) fn host(url: &str) -> Option<String> {
+    let (_, rest) = url.split_once("://")?;
+    let authority = &rest[..rest.find(['/', '?', '#']).unwrap_or(rest.len())];
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    let host = match host_port.find(']') {
+        Some(end) if host_port.starts_with('[') => &host_port[..=end],
+        _ => host_port.split(':').next().unwrap_or(host_port),
+    };
+    (!host.is_empty()).then(|| host.to_ascii_lowercase())
+}
+
+/// Lowercased origin (`scheme://host[:port]`) of an absolute URL.
+pub(crate) fn origin(url: &str) -> Option<String> {
+    let (scheme, rest) = url.split_once("://")?;
+    let authority = &rest[..rest.find(['/', '?', '#']).unwrap_or(rest.len())];
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    (!host_port.is_empty()).then(|| format!("{scheme}://{host_port}").to_ascii_lowercase())
+}
+
+/// Check whether two absolute URLs name the same page, ignoring case in
+/// the scheme and host, default ports and trailing slashes.
+pub(crate) fn equivalent(a: &str, b: &str) -> bool {
+    a == b || normalize(a).is_some_and(|a| normalize(b).is_some_and(|b| a == b))
+}
+
+/// Comparable form of an absolute URL for [`equivalent`].
+fn normalize(url: &str) -> Option<String> {
+    let (scheme, rest) = url.split_once("://")?;
+    let scheme = scheme.to_ascii_lowercase();
+    let authority_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let (authority, path_and_suffix) = rest.split_at(authority_end);
+    let mut authority = authority.to_ascii_lowercase();
+    let default_port = match scheme.as_str() {
+        "http" | "ws" => Some(":80"),
+        "https" | "wss" => Some(":443"),
+        _ => None,
+    };
+    if let Some(port) = default_port
+        && authority.ends_with(port)
+    {
+        authority.truncate(authority.len() - port.len());
+    }
+    let suffix_start = path_and_suffix.find(['?', '#']).unwrap_or(path_and_suffix.len());
+    let (path, suffix) = path_and_suffix.split_at(suffix_start);
+    Some(format!("{scheme}://{authority}{}{suffix}", path.trim_end_matches('/')))
+}
+
+/// Check whether `host` is `domain` or one of its subdomains.
+pub(crate) fn host_matches(host: &str, domain: &str) -> bool {
+    let domain = domain.trim().trim_start_matches('.');
+    host.eq_ignore_ascii_case(domain)
+        || host.len() > domain.len()
+            && host.as_bytes()[host.len() - domain.len() - 1] == b'.'
+            && host[host.len() - domain.len()..].eq_ignore_ascii_case(domain)
+}
+
+/// Build a search URL for navigation input that is not a URL.
+///
+/// Input without a scheme that contains whitespace is a query: it is
+/// percent-encoded and substituted for `{}` in `template`. Anything else
+/// returns `None` and is navigated to as typed.
+pub(crate) fn search_url(input: &str, template: &str) -> Option<String> {
+    let input = input.trim();
+    if has_scheme(input) || !input.contains(char::is_whitespace) {
+        return None;
+    }
+    let mut query = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            query.push(byte as char);
+        } else {
+            query.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    Some(template.replace("{}", &query))
+}
+
+/// Check whether a reference starts with a URL scheme such as `https:`.
+fn has_scheme(reference: &str) -> bool {
+    let Some(colon) = reference.find(':') else {
+        return false;
+    };
+    let scheme = &reference[..colon];
+    scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+/// Collapse `.` and `..` segments of an absolute path.
+fn remove_dot_segments(path: &str) -> String {
+    let mut output: Vec<&str> = Vec::new();
+    let segments: Vec<&str> = path.split('/').skip(1).collect();
+    for (i, segment) in segments.iter().enumerate() {
+        let last = i + 1 == segments.len();
+        match *segment {
+            "." if last => output.push(""),
+            "." => {},
+            ".." => {
+                output.pop();
+                if last {
+                    output.push("");
+                }
+            },
+            segment => output.push(segment),
+        }
+    }
+    format!("/{}", output.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_relative_path() {
+        let base = "https://example.com/a/b/page.html";
+        assert_eq!(resolve(base, "c.html"), "https://example.com/a/b/c.html");
+        assert_eq!(resolve(base, "../x"), "https://example.com/a/x");
+        assert_eq!(resolve(base, "/root"), "https://example.com/root");
+        assert_eq!(resolve(base, "https://other.org/"), "https://other.org/");
+    }
+
+    #[test]
+    fn test_resolve_network_query_and_fragment_references() {
+        let base = "https://example.com/a/b/page.html?q=1#top";
+        assert_eq!(resolve(base, "//cdn.example.net/x.js"), "https://cdn.example.net/x.js");
+        assert_eq!(resolve(base, "?page=2"), "https://example.com/a/b/page.html?page=2");
+        assert_eq!(resolve(base, "#notes"), "https://example.com/a/b/page.html?q=1#notes");
+        assert_eq!(resolve(base, ""), "https://example.com/a/b/page.html?q=1");
+        assert_eq!(resolve(base, "./c/../d?x#y"), "https://example.com/a/b/d?x#y");
+        assert_eq!(resolve(base, "../../../../up"), "https://example.com/up");
+        assert_eq!(resolve(base, "mailto:me@example.com"), "mailto:me@example.com");
+        assert_eq!(resolve("https://example.com", "x"), "https://example.com/x");
+        assert_eq!(resolve("about:blank", "x"), "x");
+    }
+
+    #[test]
+    fn test_host() {
+        assert_eq!(host("https://user@Example.COM:8080/a?b").as_deref(), Some("example.com"));
+        assert_eq!(host("http://[::1]:3000/").as_deref(), Some("[::1]"));
+        assert_eq!(host("about:blank"), None);
+    }
+
+    #[test]
+    fn test_search_url() {
+        let template = "https://search/?q={}";
+        assert_eq!(
+            search_url("hello world", template).as_deref(),
+            Some("https://search/?q=hello%20world")
+        );
+        assert_eq!(search_url(" a&b c ", template).as_deref(), Some("https://search/?q=a%26b%20c"));
+        assert_eq!(search_url("example.com", template), None);
+        assert_eq!(search_url("about:blank", template), None);
+    }
+
+    #[test]
+    fn test_equivalent_urls() {
+        assert!(equivalent("http://x/", "http://x"));
+        assert!(equivalent("HTTPS://Example.com:443/a/", "https://example.com/a"));
+        assert!(equivalent("http://x:80/?q=1", "http://x?q=1"));
+        assert!(!equivalent("http://x/a", "http://x/b"));
+        assert!(!equivalent("http://x:8080/", "http://x/"));
+        assert!(!equivalent("http://x/#top", "http://x/"));
+    }
+}